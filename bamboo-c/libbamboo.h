@@ -48,6 +48,11 @@
 
 #define BLAKE2B_HASH_SIZE 64
 
+/**
+ * The largest digest of any supported algorithm.
+ */
+#define MAX_HASH_DIGEST_SIZE 32
+
 #define BLAKE2B_NUMERIC_ID 0
 
 /**
@@ -59,6 +64,11 @@
  */
 #define MAX_YAMF_HASH_SIZE (BLAKE2B_HASH_SIZE + 2)
 
+/**
+ * The number of bytes in a keystore.
+ */
+#define KEYSTORE_SIZE 137
+
 typedef enum DecodeError {
   DecodeError_NoError,
   DecodeError_PayloadHashError,
@@ -73,6 +83,19 @@ typedef enum DecodeError {
   DecodeError_InputIsLengthZero,
 } DecodeError;
 
+typedef enum KeystoreError {
+  KeystoreError_NoError,
+  KeystoreError_InvalidKeystoreLength,
+  KeystoreError_InvalidKeystoreMagic,
+  KeystoreError_UnsupportedKeystoreVersion,
+  KeystoreError_InvalidKdfParams,
+  KeystoreError_DeriveKeystoreKey,
+  KeystoreError_EncryptSecretKey,
+  KeystoreError_DecryptSecretKey,
+  KeystoreError_InvalidKeystorePublicKey,
+  KeystoreError_KeystoreKeysDoNotMatch,
+} KeystoreError;
+
 typedef enum PublishError {
   PublishError_NoError,
   PublishError_PublishWithoutKeypair,
@@ -88,6 +111,9 @@ typedef enum PublishError {
   PublishError_DecodeLipmaaEntry,
   PublishError_PublishWithIncorrectBacklinkLogId,
   PublishError_PublishWithIncorrectLipmaaLinkLogId,
+  PublishError_DecodePreviousEntry,
+  PublishError_PublisherLinksFull,
+  PublishError_PreviousEntriesNotInOrder,
 } PublishError;
 
 typedef enum VerifyError {
@@ -109,19 +135,37 @@ typedef enum VerifyError {
   VerifyError_BackLinkRequired,
   VerifyError_DecodeEntry,
   VerifyError_EncodeEntryForSigning,
+  VerifyError_PayloadSizeExceedsPolicy,
+  VerifyError_LogIdNotAllowedByPolicy,
+  VerifyError_BatchLengthExceedsPolicy,
+  VerifyError_PayloadRequired,
+  VerifyError_StreamSeqNumNotAscending,
+  VerifyError_Cancelled,
   VerifyError_UnknownError,
 } VerifyError;
 
+/**
+ * A decoded entry.
+ *
+ * Each hash is stored as its yasmf hash id, the length of its digest and the digest, which fills
+ * the first `*_length` bytes of its array.
+ */
 typedef struct CEntry {
   uint64_t log_id;
   bool is_end_of_feed;
-  uint8_t payload_hash_bytes[BLAKE2B_HASH_SIZE];
+  uint64_t payload_hash_id;
+  uintptr_t payload_hash_length;
+  uint8_t payload_hash_bytes[MAX_HASH_DIGEST_SIZE];
   uint64_t payload_length;
   uint8_t author[PUBLIC_KEY_LENGTH];
   uint64_t seq_num;
-  uint8_t backlink[BLAKE2B_HASH_SIZE];
+  uint64_t backlink_hash_id;
+  uintptr_t backlink_length;
+  uint8_t backlink[MAX_HASH_DIGEST_SIZE];
   bool has_backlink;
-  uint8_t lipmaa_link[BLAKE2B_HASH_SIZE];
+  uint64_t lipmaa_link_hash_id;
+  uintptr_t lipmaa_link_length;
+  uint8_t lipmaa_link[MAX_HASH_DIGEST_SIZE];
   bool has_lipmaa_link;
   uint8_t sig[ED25519_SIGNATURE_SIZE];
 } CEntry;
@@ -132,6 +176,21 @@ typedef struct DecodeEd25519Blade2bEntryArgs {
   uintptr_t entry_length;
 } DecodeEd25519Blade2bEntryArgs;
 
+typedef struct KeystoreDecryptArgs {
+  uint8_t out_public_key[PUBLIC_KEY_LENGTH];
+  uint8_t out_secret_key[SECRET_KEY_LENGTH];
+  const uint8_t *keystore_bytes;
+  uintptr_t keystore_length;
+  const uint8_t *password_bytes;
+  uintptr_t password_length;
+} KeystoreDecryptArgs;
+
+typedef struct KeystorePublicKeyArgs {
+  uint8_t out_public_key[PUBLIC_KEY_LENGTH];
+  const uint8_t *keystore_bytes;
+  uintptr_t keystore_length;
+} KeystorePublicKeyArgs;
+
 typedef struct PublishEd25519Blake2bEntryArgs {
   uint8_t *out;
   uintptr_t out_length;
@@ -169,6 +228,18 @@ typedef struct VerifyEd25519Blake2bEntryArgs {
  */
 enum DecodeError decode_ed25519_blake2b_entry(struct DecodeEd25519Blade2bEntryArgs *args);
 
+/**
+ * Decrypts the key pair in a keystore with a password.
+ *
+ * The caller is responsible for zeroing `out_secret_key` when it's finished with it.
+ */
+enum KeystoreError keystore_decrypt(struct KeystoreDecryptArgs *args);
+
+/**
+ * Reads the public key of a keystore, without its password.
+ */
+enum KeystoreError keystore_public_key(struct KeystorePublicKeyArgs *args);
+
 enum PublishError publish_ed25519_blake2b_entry(struct PublishEd25519Blake2bEntryArgs *args);
 
 enum VerifyError verify_ed25519_blake2b_entry(struct VerifyEd25519Blake2bEntryArgs *args);
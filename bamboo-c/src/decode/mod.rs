@@ -1,23 +1,33 @@
 use bamboo_rs_core_ed25519_yasmf::entry::decode;
+use bamboo_rs_core_ed25519_yasmf::hash::{digest, HashAlgorithm, MAX_HASH_DIGEST_SIZE};
 use bamboo_rs_core_ed25519_yasmf::signature::ED25519_SIGNATURE_SIZE;
-use bamboo_rs_core_ed25519_yasmf::yasmf_hash::{YasmfHash, BLAKE3_HASH_SIZE};
 use core::slice;
 use ed25519_dalek::PUBLIC_KEY_LENGTH;
 
 mod error;
 use error::DecodeError;
 
+/// A decoded entry.
+///
+/// Each hash is stored as its yasmf hash id, the length of its digest and the digest, which fills
+/// the first `*_length` bytes of its array.
 #[repr(C)]
 pub struct CEntry {
     pub log_id: u64,
     pub is_end_of_feed: bool,
-    pub payload_hash_bytes: [u8; BLAKE3_HASH_SIZE],
+    pub payload_hash_id: u64,
+    pub payload_hash_length: usize,
+    pub payload_hash_bytes: [u8; MAX_HASH_DIGEST_SIZE],
     pub payload_length: u64,
     pub author: [u8; PUBLIC_KEY_LENGTH],
     pub seq_num: u64,
-    pub backlink: [u8; BLAKE3_HASH_SIZE],
+    pub backlink_hash_id: u64,
+    pub backlink_length: usize,
+    pub backlink: [u8; MAX_HASH_DIGEST_SIZE],
     pub has_backlink: bool,
-    pub lipmaa_link: [u8; BLAKE3_HASH_SIZE],
+    pub lipmaa_link_hash_id: u64,
+    pub lipmaa_link_length: usize,
+    pub lipmaa_link: [u8; MAX_HASH_DIGEST_SIZE],
     pub has_lipmaa_link: bool,
     pub sig: [u8; ED25519_SIGNATURE_SIZE],
 }
@@ -53,23 +63,25 @@ pub extern "C" fn decode_ed25519_blake2b_entry(
                 args.out_decoded_entry.sig[..].copy_from_slice(&sig.0[..]);
             });
 
-            entry.lipmaa_link.map(|lipmaa_link| match lipmaa_link {
-                YasmfHash::Blake3(bytes) => {
-                    args.out_decoded_entry.lipmaa_link[..].copy_from_slice(&bytes[..]);
-                }
+            entry.lipmaa_link.map(|lipmaa_link| {
+                args.out_decoded_entry.lipmaa_link_hash_id = HashAlgorithm::of(&lipmaa_link).id();
+                args.out_decoded_entry.lipmaa_link_length = copy_digest(
+                    digest(&lipmaa_link),
+                    &mut args.out_decoded_entry.lipmaa_link,
+                );
             });
 
-            entry.backlink.map(|backlink| match backlink {
-                YasmfHash::Blake3(bytes) => {
-                    args.out_decoded_entry.backlink[..].copy_from_slice(&bytes[..]);
-                }
+            entry.backlink.map(|backlink| {
+                args.out_decoded_entry.backlink_hash_id = HashAlgorithm::of(&backlink).id();
+                args.out_decoded_entry.backlink_length =
+                    copy_digest(digest(&backlink), &mut args.out_decoded_entry.backlink);
             });
 
-            match entry.payload_hash {
-                YasmfHash::Blake3(bytes) => {
-                    args.out_decoded_entry.payload_hash_bytes[..].copy_from_slice(&bytes[..]);
-                }
-            };
+            args.out_decoded_entry.payload_hash_id = HashAlgorithm::of(&entry.payload_hash).id();
+            args.out_decoded_entry.payload_hash_length = copy_digest(
+                digest(&entry.payload_hash),
+                &mut args.out_decoded_entry.payload_hash_bytes,
+            );
 
             args.out_decoded_entry.author[..].copy_from_slice(&entry.author.as_bytes()[..]);
 
@@ -77,3 +89,10 @@ pub extern "C" fn decode_ed25519_blake2b_entry(
         })
         .unwrap_err()
 }
+
+/// Copy `digest` to the start of `out`, returning its length. Every supported digest fits in
+/// [MAX_HASH_DIGEST_SIZE].
+fn copy_digest(digest: &[u8], out: &mut [u8; MAX_HASH_DIGEST_SIZE]) -> usize {
+    out[..digest.len()].copy_from_slice(digest);
+    digest.len()
+}
@@ -0,0 +1,39 @@
+use bamboo_rs_core_ed25519_yasmf::keystore::Error as BambooKeystoreError;
+
+#[repr(C)]
+/// cbindgen:prefix-with-name=true
+pub enum KeystoreError {
+    NoError,
+
+    InvalidKeystoreLength,
+    InvalidKeystoreMagic,
+    UnsupportedKeystoreVersion,
+    InvalidKdfParams,
+    DeriveKeystoreKey,
+    EncryptSecretKey,
+    DecryptSecretKey,
+    InvalidKeystorePublicKey,
+    KeystoreKeysDoNotMatch,
+}
+
+impl From<BambooKeystoreError> for KeystoreError {
+    fn from(err: BambooKeystoreError) -> KeystoreError {
+        match err {
+            BambooKeystoreError::InvalidKeystoreLength { .. } => {
+                KeystoreError::InvalidKeystoreLength
+            }
+            BambooKeystoreError::InvalidKeystoreMagic => KeystoreError::InvalidKeystoreMagic,
+            BambooKeystoreError::UnsupportedKeystoreVersion { .. } => {
+                KeystoreError::UnsupportedKeystoreVersion
+            }
+            BambooKeystoreError::InvalidKdfParams => KeystoreError::InvalidKdfParams,
+            BambooKeystoreError::DeriveKeystoreKey => KeystoreError::DeriveKeystoreKey,
+            BambooKeystoreError::EncryptSecretKey => KeystoreError::EncryptSecretKey,
+            BambooKeystoreError::DecryptSecretKey => KeystoreError::DecryptSecretKey,
+            BambooKeystoreError::InvalidKeystorePublicKey => {
+                KeystoreError::InvalidKeystorePublicKey
+            }
+            BambooKeystoreError::KeystoreKeysDoNotMatch => KeystoreError::KeystoreKeysDoNotMatch,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use bamboo_rs_core_ed25519_yasmf::keystore::Keystore;
+use core::slice;
+use ed25519_dalek::{PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH};
+
+pub mod error;
+pub use error::KeystoreError;
+
+#[repr(C)]
+pub struct KeystoreDecryptArgs<'a> {
+    pub out_public_key: [u8; PUBLIC_KEY_LENGTH],
+    pub out_secret_key: [u8; SECRET_KEY_LENGTH],
+    pub keystore_bytes: &'a u8,
+    pub keystore_length: usize,
+    pub password_bytes: &'a u8,
+    pub password_length: usize,
+}
+
+#[repr(C)]
+pub struct KeystorePublicKeyArgs<'a> {
+    pub out_public_key: [u8; PUBLIC_KEY_LENGTH],
+    pub keystore_bytes: &'a u8,
+    pub keystore_length: usize,
+}
+
+/// Decrypts the key pair in a keystore with a password.
+///
+/// The caller is responsible for zeroing `out_secret_key` when it's finished with it.
+#[no_mangle]
+pub extern "C" fn keystore_decrypt(args: &mut KeystoreDecryptArgs) -> KeystoreError {
+    let keystore_bytes =
+        unsafe { slice::from_raw_parts(args.keystore_bytes, args.keystore_length) };
+    let password = unsafe { slice::from_raw_parts(args.password_bytes, args.password_length) };
+
+    match Keystore::from_bytes(keystore_bytes).and_then(|keystore| keystore.decrypt(password)) {
+        Ok(key_pair) => {
+            args.out_public_key
+                .copy_from_slice(key_pair.public.as_bytes());
+            args.out_secret_key
+                .copy_from_slice(key_pair.secret.as_bytes());
+            KeystoreError::NoError
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// Reads the public key of a keystore, without its password.
+#[no_mangle]
+pub extern "C" fn keystore_public_key(args: &mut KeystorePublicKeyArgs) -> KeystoreError {
+    let keystore_bytes =
+        unsafe { slice::from_raw_parts(args.keystore_bytes, args.keystore_length) };
+
+    match Keystore::from_bytes(keystore_bytes) {
+        Ok(keystore) => {
+            args.out_public_key
+                .copy_from_slice(keystore.public_key().as_bytes());
+            KeystoreError::NoError
+        }
+        Err(err) => err.into(),
+    }
+}
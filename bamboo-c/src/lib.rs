@@ -21,7 +21,10 @@ pub extern "C" fn panic(panic_info: &core::panic::PanicInfo) -> ! {
 }
 
 pub mod decode;
+#[cfg(feature = "keystore")]
+pub mod keystore;
 pub mod publish;
 pub mod verify;
 
+pub use bamboo_rs_core_ed25519_yasmf::hash::MAX_HASH_DIGEST_SIZE;
 pub use bamboo_rs_core_ed25519_yasmf::BLAKE3_HASH_SIZE;
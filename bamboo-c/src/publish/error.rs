@@ -18,6 +18,9 @@ pub enum PublishError {
     DecodeLipmaaEntry,
     PublishWithIncorrectBacklinkLogId,
     PublishWithIncorrectLipmaaLinkLogId,
+    DecodePreviousEntry,
+    PublisherLinksFull,
+    PreviousEntriesNotInOrder,
 }
 
 impl From<BambooPublishError> for PublishError {
@@ -42,6 +45,11 @@ impl From<BambooPublishError> for PublishError {
                 PublishError::PublishWithoutBacklinkEntry
             }
             BambooPublishError::DecodeLipmaaEntry { .. } => PublishError::DecodeLipmaaEntry,
+            BambooPublishError::DecodePreviousEntry { .. } => PublishError::DecodePreviousEntry,
+            BambooPublishError::PublisherLinksFull => PublishError::PublisherLinksFull,
+            BambooPublishError::PreviousEntriesNotInOrder { .. } => {
+                PublishError::PreviousEntriesNotInOrder
+            }
             BambooPublishError::PublishKeypairDidNotMatchBacklinkPublicKey => {
                 PublishError::PublishKeypairDidNotMatchBacklinkPublicKey
             }
@@ -22,6 +22,12 @@ pub enum VerifyError {
     BackLinkRequired,
     DecodeEntry,
     EncodeEntryForSigning,
+    PayloadSizeExceedsPolicy,
+    LogIdNotAllowedByPolicy,
+    BatchLengthExceedsPolicy,
+    PayloadRequired,
+    StreamSeqNumNotAscending,
+    Cancelled,
     UnknownError,
 }
 
@@ -57,6 +63,20 @@ impl From<BambooVerifyError> for VerifyError {
             BambooVerifyError::BacklinkAuthorDoesNotMatch => {
                 VerifyError::BacklinkAuthorDoesNotMatch
             }
+            BambooVerifyError::PayloadSizeExceedsPolicy { .. } => {
+                VerifyError::PayloadSizeExceedsPolicy
+            }
+            BambooVerifyError::LogIdNotAllowedByPolicy { .. } => {
+                VerifyError::LogIdNotAllowedByPolicy
+            }
+            BambooVerifyError::BatchLengthExceedsPolicy { .. } => {
+                VerifyError::BatchLengthExceedsPolicy
+            }
+            BambooVerifyError::PayloadRequired => VerifyError::PayloadRequired,
+            BambooVerifyError::StreamSeqNumNotAscending { .. } => {
+                VerifyError::StreamSeqNumNotAscending
+            }
+            BambooVerifyError::Cancelled => VerifyError::Cancelled,
             BambooVerifyError::UnknownError => VerifyError::UnknownError,
         }
     }
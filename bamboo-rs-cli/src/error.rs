@@ -45,6 +45,26 @@ pub enum Error {
         filename: PathBuf,
         source: std::io::Error,
     },
+    #[snafu(display("Could not open seed phrase file at {}: {}", filename.display(), source))]
+    PhraseFile {
+        filename: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read seed phrase: {}", source))]
+    InvalidSeedPhrase {
+        source: bamboo_rs_core_ed25519_yasmf::keys::Error,
+    },
+    #[snafu(display("Could not open keystore file at {}: {}", filename.display(), source))]
+    KeystoreFile {
+        filename: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read keystore password: {}", source))]
+    KeystorePassword { source: std::io::Error },
+    #[snafu(display("Keystore error: {}", source))]
+    KeystoreAccess {
+        source: bamboo_rs_core_ed25519_yasmf::keystore::Error,
+    },
     #[snafu(display("Could not parse sequence number {}", source))]
     ParseSequenceNumber { source: std::num::ParseIntError },
     #[snafu(display("Could not parse log id {}", source))]
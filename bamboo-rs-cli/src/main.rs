@@ -1,11 +1,13 @@
 use bamboo_rs_core_ed25519_yasmf::entry::MAX_ENTRY_SIZE;
-use bamboo_rs_core_ed25519_yasmf::{decode, lipmaa, publish, verify, Keypair, YasmfHash};
-use blake2b_simd::blake2b;
+use bamboo_rs_core_ed25519_yasmf::keys::{KeyPurpose, SeedPhrase};
+use bamboo_rs_core_ed25519_yasmf::keystore::{KdfParams, Keystore};
+use bamboo_rs_core_ed25519_yasmf::{decode, lipmaa, publish, verify, HashAlgorithm, Keypair};
 use rand::rngs::OsRng;
+use rand::RngCore;
 use snafu::ResultExt;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 mod error;
@@ -27,14 +29,15 @@ fn main() -> Result<()> {
             log_id,
             public_key_file,
             secret_key_file,
+            keystore_file,
             force: _,
         } => {
-            let sk_bytes = read_file(&secret_key_file).context(SecretKeyFile {
-                filename: secret_key_file,
-            })?;
-            let pk_bytes = read_file(&public_key_file).context(PubKeyFile {
-                filename: public_key_file,
-            })?;
+            let key_pair = match keystore_file {
+                Some(keystore_file) => read_keystore(keystore_file)?
+                    .decrypt(&read_password()?)
+                    .context(KeystoreAccess)?,
+                None => read_key_pair(public_key_file.unwrap(), secret_key_file.unwrap())?,
+            };
 
             let payload_bytes = read_file(&payload_file).context(PayloadFile {
                 filename: payload_file,
@@ -63,10 +66,6 @@ fn main() -> Result<()> {
 
             let mut entry_buff: [u8; MAX_ENTRY_SIZE] = [0; MAX_ENTRY_SIZE];
 
-            let key_pair = Keypair::from_bytes(&[sk_bytes, pk_bytes].concat())
-                .map_err(|_| snafu::NoneError)
-                .context(KeypairCreate)?;
-
             let entry_size = publish(
                 &mut entry_buff,
                 &key_pair,
@@ -129,33 +128,95 @@ fn main() -> Result<()> {
 
             return Ok(());
         }
-        Opts::Decode { entry_file } => {
+        Opts::Decode { entry_file, text } => {
             let entry = read_file(&entry_file).context(DecodeEntryFile {
                 filename: entry_file,
             })?;
             let decoded = decode(&entry).map_err(|err| Error::DecodeEntry { error: err })?;
 
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&decoded).expect("Unable to serialize decoded entry")
-            );
+            if text {
+                print!("{}", decoded.to_text());
+            } else {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&decoded)
+                        .expect("Unable to serialize decoded entry")
+                );
+            }
         }
         Opts::GenerateKeys {
             public_key_file,
             secret_key_file,
+            phrase_file,
+            log_id,
         } => {
             let mut csprng = OsRng {};
-            let key_pair = Keypair::generate(&mut csprng);
+            let key_pair = match phrase_file {
+                Some(phrase_file) => {
+                    let mut entropy = [0u8; 32];
+                    csprng.fill_bytes(&mut entropy);
+                    let phrase = SeedPhrase::from_entropy(&entropy).context(InvalidSeedPhrase)?;
+
+                    let mut file = File::create(&phrase_file).context(PhraseFile {
+                        filename: phrase_file,
+                    })?;
+                    writeln!(file, "{}", phrase).unwrap();
 
-            let mut sk_file = File::create(&secret_key_file).context(SecretKeyFile {
-                filename: secret_key_file,
+                    phrase
+                        .root_key("")
+                        .derive_key_pair(KeyPurpose::FEED, log_id)
+                }
+                None => Keypair::generate(&mut csprng),
+            };
+
+            write_key_pair(&key_pair, public_key_file, secret_key_file)?;
+        }
+        Opts::RestoreKeys {
+            phrase_file,
+            log_id,
+            public_key_file,
+            secret_key_file,
+        } => {
+            let phrase_bytes = read_file(&phrase_file).context(PhraseFile {
+                filename: phrase_file,
             })?;
+            let phrase = SeedPhrase::parse(&String::from_utf8_lossy(&phrase_bytes))
+                .context(InvalidSeedPhrase)?;
+            let key_pair = phrase
+                .root_key("")
+                .derive_key_pair(KeyPurpose::FEED, log_id);
+
+            write_key_pair(&key_pair, public_key_file, secret_key_file)?;
+        }
+        Opts::EncryptKeys {
+            public_key_file,
+            secret_key_file,
+            keystore_file,
+        } => {
+            let key_pair = read_key_pair(public_key_file, secret_key_file)?;
+            let keystore = Keystore::encrypt(
+                &key_pair,
+                &read_password()?,
+                KdfParams::default(),
+                &mut OsRng {},
+            )
+            .context(KeystoreAccess)?;
+
+            let mut file = File::create(&keystore_file).context(KeystoreFile {
+                filename: keystore_file,
+            })?;
+            file.write_all(keystore.as_bytes()).unwrap();
+        }
+        Opts::ExportPublicKey {
+            keystore_file,
+            public_key_file,
+        } => {
+            let keystore = read_keystore(keystore_file)?;
+
             let mut pk_file = File::create(&public_key_file).context(PubKeyFile {
                 filename: public_key_file,
             })?;
-
-            sk_file.write_all(&key_pair.secret.to_bytes()).unwrap();
-            pk_file.write_all(&key_pair.public.to_bytes()).unwrap();
+            pk_file.write_all(keystore.public_key().as_bytes()).unwrap();
         }
         Opts::Lipmaa { sequence } => {
             let res = u64::from_str_radix(&sequence, 10).context(ParseSequenceNumber)?;
@@ -163,8 +224,7 @@ fn main() -> Result<()> {
         }
         Opts::Hash { file } => {
             let bytes = read_file(&file).context(DecodeEntryFile { filename: file })?;
-            let hash = blake2b(&bytes);
-            let yasmf_hash = YasmfHash::Blake3(hash.as_bytes());
+            let yasmf_hash = HashAlgorithm::default().hash(&bytes);
             let mut yasmf_hash_bytes = Vec::new();
             yasmf_hash.encode_write(&mut yasmf_hash_bytes).unwrap();
 
@@ -181,3 +241,56 @@ fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, std::io::Error> {
         .expect("Unable to read to end of file");
     Ok(bytes)
 }
+
+fn write_key_pair(
+    key_pair: &Keypair,
+    public_key_file: PathBuf,
+    secret_key_file: PathBuf,
+) -> Result<()> {
+    let mut sk_file = File::create(&secret_key_file).context(SecretKeyFile {
+        filename: secret_key_file,
+    })?;
+    let mut pk_file = File::create(&public_key_file).context(PubKeyFile {
+        filename: public_key_file,
+    })?;
+
+    sk_file.write_all(&key_pair.secret.to_bytes()).unwrap();
+    pk_file.write_all(&key_pair.public.to_bytes()).unwrap();
+    Ok(())
+}
+
+fn read_key_pair(public_key_file: PathBuf, secret_key_file: PathBuf) -> Result<Keypair> {
+    let sk_bytes = read_file(&secret_key_file).context(SecretKeyFile {
+        filename: secret_key_file,
+    })?;
+    let pk_bytes = read_file(&public_key_file).context(PubKeyFile {
+        filename: public_key_file,
+    })?;
+
+    Keypair::from_bytes(&[sk_bytes, pk_bytes].concat())
+        .map_err(|_| snafu::NoneError)
+        .context(KeypairCreate)
+}
+
+fn read_keystore(keystore_file: PathBuf) -> Result<Keystore> {
+    let bytes = read_file(&keystore_file).context(KeystoreFile {
+        filename: keystore_file,
+    })?;
+    Keystore::from_bytes(&bytes).context(KeystoreAccess)
+}
+
+/// Read the keystore password from `BAMBOO_KEYSTORE_PASSWORD`, or the first line of stdin.
+fn read_password() -> Result<Vec<u8>> {
+    if let Some(password) = std::env::var_os("BAMBOO_KEYSTORE_PASSWORD") {
+        return Ok(password.to_string_lossy().into_owned().into_bytes());
+    }
+
+    let mut password = String::new();
+    std::io::stdin()
+        .read_line(&mut password)
+        .context(KeystorePassword)?;
+    Ok(password
+        .trim_end_matches(&['\r', '\n'][..])
+        .as_bytes()
+        .to_vec())
+}
@@ -5,7 +5,8 @@ use structopt::StructOpt;
 ///
 /// Includes helpers for:
 /// - Calculating limpaa numbers.
-/// - Generating a new cryptographic key pair.
+/// - Generating a new cryptographic key pair, optionally backed up as a seed phrase.
+/// - Storing a key pair in a password encrypted keystore.
 /// - Decoding an entry and printing it to std out as json.
 #[derive(Debug, StructOpt)]
 #[structopt(name = "bamboo-cli", verbatim_doc_comment, rename_all = "kebab")]
@@ -35,12 +36,26 @@ pub enum Opts {
         lipmaa_entry_file: Option<PathBuf>,
 
         /// The file containing the public key of the author of this entry.
-        #[structopt(long, short = "p", parse(from_os_str))]
-        public_key_file: PathBuf,
+        #[structopt(
+            long,
+            short = "p",
+            parse(from_os_str),
+            required_unless("keystore-file")
+        )]
+        public_key_file: Option<PathBuf>,
 
         /// The file containing the secret key of the author of this entry.
-        #[structopt(long, short = "s", parse(from_os_str))]
-        secret_key_file: PathBuf,
+        #[structopt(
+            long,
+            short = "s",
+            parse(from_os_str),
+            required_unless("keystore-file")
+        )]
+        secret_key_file: Option<PathBuf>,
+
+        /// The keystore containing the key pair of the author of this entry, instead of the public and secret key files. The password is read from the BAMBOO_KEYSTORE_PASSWORD environment variable, or from the first line of stdin.
+        #[structopt(long, short = "k", parse(from_os_str), conflicts_with_all(&["public-key-file", "secret-key-file"]))]
+        keystore_file: Option<PathBuf>,
 
         /// When publishing the very first entry in a feed. If set, then omit the previous-entry-file and lipmaa-entry-file arguments.
         #[structopt(long)]
@@ -82,10 +97,14 @@ pub enum Opts {
     Decode {
         /// The file with an entry to decode.
         entry_file: String,
+
+        /// Print the entry in the canonical text format instead of json.
+        #[structopt(long)]
+        text: bool,
     },
 
     /// Calculate the hash of the bytes in a file. Useful if you want to know the hash of an entry
-    /// or a payload. Uses the blake2b hashing algorithm.
+    /// or a payload. Uses the blake3 hashing algorithm.
     Hash {
         /// The file path of the file to hash.
         file: String,
@@ -106,5 +125,58 @@ pub enum Opts {
         /// The path to the file which will store the new secret key.
         #[structopt(long, short = "s", parse(from_os_str))]
         secret_key_file: PathBuf,
+
+        /// Derive the key-pair from a new seed phrase and write the phrase to this file. Keep it safe, the keys can be restored from it with `restore-keys`.
+        #[structopt(long, parse(from_os_str))]
+        phrase_file: Option<PathBuf>,
+
+        /// The log-id to derive the key-pair for. Only used with `--phrase-file`.
+        #[structopt(long, default_value = "0")]
+        log_id: u64,
+    },
+
+    /// Restore the key-pair for a log from a seed phrase written by `generate-keys --phrase-file`. Stores them in two new files.
+    RestoreKeys {
+        /// The file containing the seed phrase.
+        #[structopt(long, parse(from_os_str))]
+        phrase_file: PathBuf,
+
+        /// The log-id to restore the key-pair for.
+        #[structopt(long, default_value = "0")]
+        log_id: u64,
+
+        /// The path to the file which will store the restored public key.
+        #[structopt(long, short = "p", parse(from_os_str))]
+        public_key_file: PathBuf,
+
+        /// The path to the file which will store the restored secret key.
+        #[structopt(long, short = "s", parse(from_os_str))]
+        secret_key_file: PathBuf,
+    },
+
+    /// Encrypt a key-pair into a keystore with a password. The password is read from the BAMBOO_KEYSTORE_PASSWORD environment variable, or from the first line of stdin.
+    EncryptKeys {
+        /// The file containing the public key.
+        #[structopt(long, short = "p", parse(from_os_str))]
+        public_key_file: PathBuf,
+
+        /// The file containing the secret key.
+        #[structopt(long, short = "s", parse(from_os_str))]
+        secret_key_file: PathBuf,
+
+        /// The path to the file which will store the new keystore.
+        #[structopt(long, short = "k", parse(from_os_str))]
+        keystore_file: PathBuf,
+    },
+
+    /// Export the public key of a keystore, without needing its password.
+    ExportPublicKey {
+        /// The file containing the keystore.
+        #[structopt(long, short = "k", parse(from_os_str))]
+        keystore_file: PathBuf,
+
+        /// The path to the file which will store the public key.
+        #[structopt(long, short = "p", parse(from_os_str))]
+        public_key_file: PathBuf,
     },
 }
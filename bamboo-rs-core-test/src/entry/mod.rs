@@ -1,18 +1,66 @@
 #[cfg(test)]
 mod tests {
-    use bamboo_rs_core_ed25519_yasmf::entry::decode;
+    use bamboo_rs_core_ed25519_yasmf::entry::decode::{EntryReader, ReadError};
+    use bamboo_rs_core_ed25519_yasmf::entry::owned::Error as OwnedError;
     use bamboo_rs_core_ed25519_yasmf::entry::publish::Error as PublishError;
+    use bamboo_rs_core_ed25519_yasmf::entry::publish::{Publisher, PublisherState};
+    use bamboo_rs_core_ed25519_yasmf::entry::text::Error as TextError;
     use bamboo_rs_core_ed25519_yasmf::entry::verify::batch::verify_batch_signatures;
     use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
+    use bamboo_rs_core_ed25519_yasmf::entry::verify::{
+        BatchVerifier, CancellationToken, StreamVerifier, VerifyPhase,
+    };
     use bamboo_rs_core_ed25519_yasmf::entry::verify_batch;
+    use bamboo_rs_core_ed25519_yasmf::entry::{
+        decode, decode_iter, decode_with_remainder, is_linked_after, OwnedEntry,
+    };
     use bamboo_rs_core_ed25519_yasmf::signature::ED25519_SIGNATURE_SIZE;
     use bamboo_rs_core_ed25519_yasmf::yasmf_hash::BLAKE3_HASH_SIZE;
-    use bamboo_rs_core_ed25519_yasmf::{publish, verify, Entry, Signature, YasmfHash};
-    use ed25519_dalek::{Keypair, PublicKey};
+    use bamboo_rs_core_ed25519_yasmf::{
+        lipmaa, publish, publish_batch, verify, verify_batch_iter, verify_batch_iter_with_policy,
+        verify_batch_with_linked_entries, verify_batch_with_policy, verify_with_policy, Entry,
+        PublishState, Signature, VerifyPolicy, YasmfHash,
+    };
+    use ed25519_dalek::{Keypair, PublicKey, Signer};
     use rand::rngs::OsRng;
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
     use std::io::Write;
     use varu64::encode_write as varu64_encode_write;
 
+    fn n_valid_entries(key_pair: &Keypair, n: u64) -> Vec<Vec<u8>> {
+        let mut entries: Vec<Vec<u8>> = Vec::new();
+
+        for seq_num in 1..=n {
+            let payload = format!("message number {}", seq_num);
+            let mut out = [0u8; 512];
+
+            let (lipmaa_entry, backlink) = match seq_num {
+                1 => (None, None),
+                _ => (
+                    Some(&entries[lipmaa(seq_num) as usize - 1][..]),
+                    Some(&entries[seq_num as usize - 2][..]),
+                ),
+            };
+
+            let size = publish(
+                &mut out,
+                key_pair,
+                0,
+                payload.as_bytes(),
+                false,
+                Some(seq_num - 1).filter(|seq_num| *seq_num > 0),
+                lipmaa_entry,
+                backlink,
+            )
+            .unwrap();
+
+            entries.push(out[..size].to_vec());
+        }
+
+        entries
+    }
+
     #[test]
     fn encode_write_decode_entry() {
         let backlink_bytes = [0xAA; BLAKE3_HASH_SIZE];
@@ -527,4 +575,936 @@ mod tests {
             err => panic!("{:?}", err),
         }
     }
+
+    #[test]
+    fn verify_with_policy_checks_payload_size() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let payload = "hello bamboo!";
+        let mut out = [0u8; 512];
+
+        let size = publish(
+            &mut out,
+            &key_pair,
+            0,
+            payload.as_bytes(),
+            false,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let policy = VerifyPolicy {
+            max_payload_size: Some(4),
+            ..Default::default()
+        };
+
+        match verify_with_policy(&out[..size], None, None, None, &policy) {
+            Err(VerifyError::PayloadSizeExceedsPolicy { actual: 13, max: 4 }) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn verify_with_policy_checks_log_id() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let payload = "hello bamboo!";
+        let mut out = [0u8; 512];
+
+        let size = publish(
+            &mut out,
+            &key_pair,
+            3,
+            payload.as_bytes(),
+            false,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let allowed_log_ids = [0, 1];
+        let mut policy = VerifyPolicy {
+            allowed_log_ids: Some(&allowed_log_ids),
+            ..Default::default()
+        };
+
+        match verify_with_policy(&out[..size], None, None, None, &policy) {
+            Err(VerifyError::LogIdNotAllowedByPolicy { log_id: 3 }) => {}
+            err => panic!("{:?}", err),
+        }
+
+        let allowed_log_ids = [3];
+        policy.allowed_log_ids = Some(&allowed_log_ids);
+        verify_with_policy(&out[..size], None, None, None, &policy).unwrap();
+    }
+
+    #[test]
+    fn verify_with_policy_requires_payload_and_backlink() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let payload = "hello bamboo!";
+        let mut out = [0u8; 512];
+
+        let size = publish(
+            &mut out,
+            &key_pair,
+            0,
+            payload.as_bytes(),
+            true,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let policy = VerifyPolicy {
+            require_payload: true,
+            require_backlink: true,
+            ..Default::default()
+        };
+
+        match verify_with_policy(&out[..size], None, None, None, &policy) {
+            Err(VerifyError::PayloadRequired) => {}
+            err => panic!("{:?}", err),
+        }
+
+        // The first entry has no backlink so it's allowed to be missing.
+        verify_with_policy(&out[..size], Some(payload.as_bytes()), None, None, &policy).unwrap();
+
+        // Forge an entry after the end of feed. Without the backlink it can't be detected.
+        let mut entry = decode(&out[..size]).unwrap();
+        let backlink = bamboo_rs_core_ed25519_yasmf::yasmf_hash::new_blake3(&out[..size]);
+        entry.seq_num = 2;
+        entry.is_end_of_feed = false;
+        entry.backlink = Some(YasmfHash::from(&backlink));
+        entry.sig = None;
+
+        let mut unsigned = Vec::new();
+        entry.encode_write(&mut unsigned).unwrap();
+        let sig_bytes = key_pair.sign(&unsigned).to_bytes();
+        entry.sig = Some(Signature(&sig_bytes[..]));
+
+        let mut entry2_vec = Vec::new();
+        entry.encode_write(&mut entry2_vec).unwrap();
+
+        verify(&entry2_vec, Some(payload.as_bytes()), None, None).unwrap();
+
+        match verify_with_policy(&entry2_vec, Some(payload.as_bytes()), None, None, &policy) {
+            Err(VerifyError::BackLinkRequired) => {}
+            err => panic!("{:?}", err),
+        }
+
+        match verify_with_policy(
+            &entry2_vec,
+            Some(payload.as_bytes()),
+            None,
+            Some(&out[..size]),
+            &policy,
+        ) {
+            Err(VerifyError::PublishedAfterEndOfFeed) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn batch_verify_with_policy_checks_batch_len() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let payload = "hello bamboo!";
+        let mut out = [0u8; 512];
+
+        let size = publish(
+            &mut out,
+            &key_pair,
+            0,
+            payload.as_bytes(),
+            false,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let mut out2 = [0u8; 512];
+        let size2 = publish(
+            &mut out2,
+            &key_pair,
+            0,
+            payload.as_bytes(),
+            false,
+            Some(1),
+            Some(&out[..size]),
+            Some(&out[..size]),
+        )
+        .unwrap();
+
+        let entries = [
+            (&out[..size], Some(payload.as_bytes())),
+            (&out2[..size2], Some(payload.as_bytes())),
+        ];
+
+        let mut policy = VerifyPolicy {
+            max_batch_len: Some(1),
+            ..Default::default()
+        };
+
+        match verify_batch_with_policy(&entries[..], &policy) {
+            Err(VerifyError::BatchLengthExceedsPolicy { actual: 2, max: 1 }) => {}
+            err => panic!("{:?}", err),
+        }
+
+        policy.max_batch_len = Some(2);
+        policy.require_backlink = true;
+        verify_batch_with_policy(&entries[..], &policy).unwrap();
+
+        match verify_batch_with_policy(&entries[1..], &policy) {
+            Err(VerifyError::BackLinkRequired) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn is_linked_after_matches_brute_force() {
+        for last_seq_num in 1..200u64 {
+            for seq_num in 1..=last_seq_num {
+                let expected = seq_num == last_seq_num
+                    || (last_seq_num + 1..=last_seq_num * 3 + 3).any(|n| lipmaa(n) == seq_num);
+
+                assert_eq!(
+                    is_linked_after(seq_num, last_seq_num),
+                    expected,
+                    "seq_num: {}, last_seq_num: {}",
+                    seq_num,
+                    last_seq_num
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn verify_batch_iter_entries() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 300);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for (i, entry) in entries.iter().enumerate() {
+            let payload = format!("message number {}", i + 1).into_bytes();
+            sender.send((entry.clone(), Some(payload))).unwrap();
+        }
+        drop(sender);
+
+        match verify_batch_iter(receiver, 16) {
+            Ok(_) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn verify_batch_with_linked_entries_checks_links_outside_the_batch() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 20);
+        let batch: Vec<(&[u8], Option<&[u8]>)> = entries[10..]
+            .iter()
+            .map(|entry| (&entry[..], None))
+            .collect();
+
+        // Entry 13 links to 4, 12 links to 8 and 11 links back to 10.
+        let linked_entries = [&entries[3][..], &entries[7][..], &entries[9][..]];
+        verify_batch_with_linked_entries(&batch, &linked_entries).unwrap();
+
+        match verify_batch_with_linked_entries(&batch, &linked_entries[1..]) {
+            Err(VerifyError::LipmaaLinkRequired) => {}
+            err => panic!("{:?}", err),
+        }
+
+        let other_key_pair: Keypair = Keypair::generate(&mut csprng);
+        let other_entries = n_valid_entries(&other_key_pair, 10);
+        match verify_batch_with_linked_entries(
+            &batch,
+            &[&entries[3][..], &entries[7][..], &other_entries[9][..]],
+        ) {
+            Err(VerifyError::BacklinkAuthorDoesNotMatch) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn stream_verifier_memory_is_bounded() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 400);
+
+        let mut verifier = StreamVerifier::new(10);
+        for entry in entries.iter() {
+            verifier.push(&entry[..], None::<&[u8]>).unwrap();
+            assert!(verifier.len() < 10 + 20);
+        }
+        verifier.finish().unwrap();
+    }
+
+    #[test]
+    fn verify_batch_iter_with_policy_checks_every_entry() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 20);
+        let entries_and_payloads = || {
+            entries.iter().enumerate().map(|(i, entry)| {
+                let payload = format!("message number {}", i + 1).into_bytes();
+                (entry, Some(payload))
+            })
+        };
+
+        let policy = VerifyPolicy {
+            max_batch_len: Some(20),
+            require_payload: true,
+            require_backlink: true,
+            ..VerifyPolicy::default()
+        };
+        verify_batch_iter_with_policy(entries_and_payloads(), 5, &policy).unwrap();
+
+        let policy = VerifyPolicy {
+            max_batch_len: Some(10),
+            ..VerifyPolicy::default()
+        };
+        match verify_batch_iter_with_policy(entries_and_payloads(), 5, &policy) {
+            Err(VerifyError::BatchLengthExceedsPolicy {
+                actual: 11,
+                max: 10,
+            }) => {}
+            err => panic!("{:?}", err),
+        }
+
+        let policy = VerifyPolicy {
+            max_payload_size: Some(5),
+            ..VerifyPolicy::default()
+        };
+        match verify_batch_iter_with_policy(entries_and_payloads(), 5, &policy) {
+            Err(VerifyError::PayloadSizeExceedsPolicy { max: 5, .. }) => {}
+            err => panic!("{:?}", err),
+        }
+
+        let policy = VerifyPolicy {
+            allowed_log_ids: Some(&[1]),
+            ..VerifyPolicy::default()
+        };
+        match verify_batch_iter_with_policy(entries_and_payloads(), 5, &policy) {
+            Err(VerifyError::LogIdNotAllowedByPolicy { log_id: 0 }) => {}
+            err => panic!("{:?}", err),
+        }
+
+        // The backlink of entry 11 is not in the stream.
+        let policy = VerifyPolicy {
+            require_backlink: true,
+            ..VerifyPolicy::default()
+        };
+        match verify_batch_iter_with_policy(entries_and_payloads().skip(10), 5, &policy) {
+            Err(VerifyError::BackLinkRequired) => {}
+            err => panic!("{:?}", err),
+        }
+
+        let policy = VerifyPolicy {
+            require_payload: true,
+            ..VerifyPolicy::default()
+        };
+        let mut verifier = StreamVerifier::with_policy(5, policy);
+        verifier
+            .push(&entries[0], Some(b"message number 1"))
+            .unwrap();
+        match verifier.push(&entries[1], None) {
+            Err(VerifyError::PayloadRequired) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn verify_batch_iter_detects_invalid_entry_in_later_window() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut entries = n_valid_entries(&key_pair, 13);
+
+        // Replace the last entry, 13, with an entry whose lipmaa link (entry 4) hash is wrong.
+        let mut entry = decode(&entries[12]).unwrap();
+        let incorrect_lipmaa = bamboo_rs_core_ed25519_yasmf::yasmf_hash::new_blake3(b"noooo");
+        entry.lipmaa_link = Some(YasmfHash::from(&incorrect_lipmaa));
+        entry.sig = None;
+        let mut unsigned = Vec::new();
+        entry.encode_write(&mut unsigned).unwrap();
+        let sig_bytes = key_pair.sign(&unsigned).to_bytes();
+        entry.sig = Some(Signature(&sig_bytes[..]));
+        let mut tampered = Vec::new();
+        entry.encode_write(&mut tampered).unwrap();
+        entries[12] = tampered;
+
+        let entries_and_payloads = entries.iter().map(|entry| (entry, None::<Vec<u8>>));
+
+        match verify_batch_iter(entries_and_payloads, 5) {
+            Err(VerifyError::LipmaaHashDoesNotMatch { .. }) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn verify_batch_iter_requires_ascending_seq_nums() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 3);
+        let entries_and_payloads = [&entries[0], &entries[2], &entries[1]]
+            .iter()
+            .map(|entry| (*entry, None::<Vec<u8>>))
+            .collect::<Vec<_>>();
+
+        match verify_batch_iter(entries_and_payloads, 5) {
+            Err(VerifyError::StreamSeqNumNotAscending {
+                previous: 3,
+                actual: 2,
+            }) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn batch_verifier_reports_progress() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 50);
+        let entries_and_payloads = entries
+            .iter()
+            .map(|entry| (entry, None::<Vec<u8>>))
+            .collect::<Vec<_>>();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let progress = std::sync::Mutex::new(Vec::new());
+
+        BatchVerifier::new()
+            .thread_pool(&pool)
+            .chunk_size(8)
+            .on_progress(|verified, total| progress.lock().unwrap().push((verified, total)))
+            .verify(&entries_and_payloads)
+            .unwrap();
+
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort();
+
+        // One call per chunk of signatures, counting entries.
+        assert_eq!(progress.len(), 7);
+        assert!(progress.iter().all(|(_, total)| *total == 50));
+        assert_eq!(progress.first(), Some(&(8, 50)));
+        assert_eq!(progress.last(), Some(&(50, 50)));
+
+        let phase_progress = std::sync::Mutex::new(Vec::new());
+        BatchVerifier::new()
+            .thread_pool(&pool)
+            .chunk_size(8)
+            .on_phase_progress(|phase, done, total| {
+                phase_progress.lock().unwrap().push((phase, done, total))
+            })
+            .verify(&entries_and_payloads)
+            .unwrap();
+
+        let phase_progress = phase_progress.into_inner().unwrap();
+        // 7 chunks each of decoding, link checking and signature checking, in that order.
+        assert_eq!(phase_progress.len(), 21);
+        assert!(phase_progress.iter().all(|(_, _, total)| *total == 50));
+        for (i, phase) in [
+            VerifyPhase::Decode,
+            VerifyPhase::Links,
+            VerifyPhase::Signatures,
+        ]
+        .iter()
+        .enumerate()
+        {
+            let chunks = &phase_progress[i * 7..(i + 1) * 7];
+            assert!(chunks
+                .iter()
+                .all(|(chunk_phase, _, _)| chunk_phase == phase));
+            assert_eq!(chunks.iter().map(|(_, done, _)| *done).max(), Some(50));
+        }
+    }
+
+    #[test]
+    fn batch_verifier_can_be_cancelled() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 20);
+        let entries_and_payloads = entries
+            .iter()
+            .map(|entry| (entry, None::<Vec<u8>>))
+            .collect::<Vec<_>>();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let token = CancellationToken::new();
+
+        // Cancel once the first chunk has been verified, like a user navigating away.
+        let result = BatchVerifier::new()
+            .thread_pool(&pool)
+            .chunk_size(1)
+            .cancellation_token(token.clone())
+            .on_progress(|_, _| token.cancel())
+            .verify(&entries_and_payloads);
+
+        match result {
+            Err(VerifyError::Cancelled) => {}
+            err => panic!("{:?}", err),
+        }
+
+        match BatchVerifier::new()
+            .cancellation_token(token)
+            .verify(&entries_and_payloads)
+        {
+            Err(VerifyError::Cancelled) => {}
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn batch_verifier_can_be_cancelled_before_checking_signatures() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 20);
+        let entries_and_payloads = entries
+            .iter()
+            .map(|entry| (entry, None::<Vec<u8>>))
+            .collect::<Vec<_>>();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let token = CancellationToken::new();
+        let phases = std::sync::Mutex::new(Vec::new());
+        let progress = std::sync::Mutex::new(Vec::new());
+
+        // Cancel while the entries are still being decoded.
+        let result = BatchVerifier::new()
+            .thread_pool(&pool)
+            .chunk_size(1)
+            .cancellation_token(token.clone())
+            .on_phase_progress(|phase, _, _| {
+                phases.lock().unwrap().push(phase);
+                token.cancel()
+            })
+            .on_progress(|verified, _| progress.lock().unwrap().push(verified))
+            .verify(&entries_and_payloads);
+
+        match result {
+            Err(VerifyError::Cancelled) => {}
+            err => panic!("{:?}", err),
+        }
+        // No signatures were checked.
+        let phases = phases.into_inner().unwrap();
+        assert!(phases.iter().all(|phase| *phase == VerifyPhase::Decode));
+        assert!(progress.into_inner().unwrap().is_empty());
+    }
+
+    #[test]
+    fn serde_entry_with_links_round_trips() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let entries = n_valid_entries(&key_pair, 4);
+        let entry = decode(&entries[3]).unwrap();
+
+        let string = serde_json::to_string(&entry).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&string).unwrap();
+        assert_eq!(
+            value["lipmaaLink"]["Blake3"],
+            hex::encode(&bamboo_rs_core_ed25519_yasmf::yasmf_hash::blake3(&entries[0]).as_bytes())
+        );
+
+        let parsed: Entry<Vec<u8>, Vec<u8>> = serde_json::from_str(&string).unwrap();
+
+        let mut encoded = Vec::new();
+        parsed.encode_write(&mut encoded).unwrap();
+        assert_eq!(encoded, entries[3]);
+    }
+
+    #[test]
+    fn publish_batch_matches_publishing_one_at_a_time() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let payloads = (1..=100).map(|seq_num| format!("message number {}", seq_num));
+        let entries = publish_batch(&key_pair, 0, None, payloads).unwrap();
+
+        assert_eq!(entries, n_valid_entries(&key_pair, 100));
+    }
+
+    #[test]
+    fn publish_batch_continues_from_state() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let expected = n_valid_entries(&key_pair, 100);
+        let payloads = |range: std::ops::RangeInclusive<u64>| {
+            range.map(|seq_num| format!("message number {}", seq_num))
+        };
+
+        let mut state = PublishState::new();
+        let mut entries = publish_batch(&key_pair, 0, Some(&mut state), payloads(1..=40)).unwrap();
+        assert_eq!(state.last_seq_num(), Some(40));
+        assert!(state.len() < 40);
+
+        entries.extend(publish_batch(&key_pair, 0, Some(&mut state), payloads(41..=100)).unwrap());
+        assert_eq!(entries, expected);
+
+        let mut state = PublishState::from_entries(&expected[..70]).unwrap();
+        let entries = publish_batch(&key_pair, 0, Some(&mut state), payloads(71..=100)).unwrap();
+        assert_eq!(&entries[..], &expected[70..]);
+    }
+
+    #[test]
+    fn publish_batch_leaves_state_unchanged_on_error() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let other_key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut state = PublishState::from_entries(n_valid_entries(&key_pair, 10)).unwrap();
+        let original = state.clone();
+
+        match publish_batch(&other_key_pair, 0, Some(&mut state), vec!["hello"]) {
+            Err(PublishError::PublishKeypairDidNotMatchBacklinkPublicKey) => {}
+            e => panic!(
+                "expected publish to fail with a different key pair, got {:?}",
+                e
+            ),
+        }
+        assert_eq!(state, original);
+    }
+
+    fn copy_key_pair(key_pair: &Keypair) -> Keypair {
+        Keypair::from_bytes(&key_pair.to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn publisher_matches_publishing_one_at_a_time() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let expected = n_valid_entries(&key_pair, 300);
+
+        let mut publisher = Publisher::new(copy_key_pair(&key_pair), 0);
+        let mut out = [0u8; 512];
+
+        for (seq_num, expected_entry) in (1..=300).zip(expected.iter()) {
+            let payload = format!("message number {}", seq_num);
+            let size = publisher
+                .publish(&mut out, payload.as_bytes(), false)
+                .unwrap();
+            assert_eq!(&out[..size], &expected_entry[..]);
+        }
+
+        assert_eq!(publisher.state().last_seq_num(), Some(300));
+    }
+
+    #[test]
+    fn publisher_state_round_trips_through_serde() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let expected = n_valid_entries(&key_pair, 50);
+        let mut out = [0u8; 512];
+
+        let mut publisher =
+            Publisher::from_entries(copy_key_pair(&key_pair), 0, &expected[..30]).unwrap();
+
+        for seq_num in 31..=50 {
+            let string = serde_json::to_string(publisher.state()).unwrap();
+            let state: PublisherState = serde_json::from_str(&string).unwrap();
+            let (key_pair, _) = publisher.into_parts();
+            publisher = Publisher::from_state(key_pair, state).unwrap();
+
+            let payload = format!("message number {}", seq_num);
+            let size = publisher
+                .publish(&mut out, payload.as_bytes(), false)
+                .unwrap();
+            assert_eq!(&out[..size], &expected[seq_num as usize - 1][..]);
+        }
+    }
+
+    #[test]
+    fn publisher_errors() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let other_key_pair: Keypair = Keypair::generate(&mut csprng);
+        let mut out = [0u8; 512];
+
+        let mut publisher = Publisher::new(copy_key_pair(&key_pair), 0);
+        publisher.publish(&mut out, b"goodbye", true).unwrap();
+
+        match publisher.publish(&mut out, b"hello again", false) {
+            Err(PublishError::PublishAfterEndOfFeed) => {}
+            e => panic!("expected publish after end of feed to fail, got {:?}", e),
+        }
+
+        match Publisher::from_state(other_key_pair, publisher.state().clone()) {
+            Err(PublishError::PublishKeypairDidNotMatchBacklinkPublicKey) => {}
+            e => panic!("expected a different key pair to fail, got {:?}", e),
+        }
+
+        match Publisher::from_entries(copy_key_pair(&key_pair), 1, n_valid_entries(&key_pair, 3)) {
+            Err(PublishError::PublishWithIncorrectBacklinkLogId) => {}
+            e => panic!(
+                "expected entries from a different log_id to fail, got {:?}",
+                e
+            ),
+        }
+
+        let entries = n_valid_entries(&key_pair, 5);
+        match Publisher::from_entries(copy_key_pair(&key_pair), 0, vec![&entries[4], &entries[2]]) {
+            Err(PublishError::PreviousEntriesNotInOrder {
+                actual: 3,
+                previous: 5,
+            }) => {}
+            e => panic!("expected entries out of order to fail, got {:?}", e),
+        }
+        match Publisher::from_entries(copy_key_pair(&key_pair), 0, vec![&entries[2], &entries[2]]) {
+            Err(PublishError::PreviousEntriesNotInOrder {
+                actual: 3,
+                previous: 3,
+            }) => {}
+            e => panic!("expected a repeated entry to fail, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn decode_concatenated_entries() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let entries = n_valid_entries(&key_pair, 20);
+        let bytes = entries.concat();
+
+        let (first, remainder) = decode_with_remainder(&bytes).unwrap();
+        assert_eq!(first.seq_num, 1);
+        assert_eq!(remainder, &bytes[entries[0].len()..]);
+
+        let seq_nums = decode_iter(&bytes)
+            .map(|entry| entry.unwrap().seq_num)
+            .collect::<Vec<_>>();
+        assert_eq!(seq_nums, (1..=20).collect::<Vec<_>>());
+
+        let mut with_garbage = bytes.clone();
+        with_garbage.extend_from_slice(&[0u8; 10]);
+        let mut iter = decode_iter(&with_garbage);
+        assert_eq!(
+            iter.by_ref().take(20).filter(|entry| entry.is_ok()).count(),
+            20
+        );
+        assert_eq!(iter.remainder(), &[0u8; 10]);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn entry_reader_reads_concatenated_entries() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let entries = n_valid_entries(&key_pair, 20);
+        let bytes = entries.concat();
+
+        let read_entries = EntryReader::new(&bytes[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read_entries, entries);
+
+        match EntryReader::new(&bytes[..bytes.len() - 1]).last() {
+            Some(Err(ReadError::ReadEntryUnexpectedEof)) => {}
+            e => panic!("expected a truncated entry to fail, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn entry_reader_does_not_read_past_the_entry() {
+        // Reads one byte at a time and panics if asked for more than `limit` bytes, like a socket
+        // that would block.
+        struct SlowReader<'a> {
+            bytes: &'a [u8],
+            limit: usize,
+        }
+
+        impl<'a> std::io::Read for SlowReader<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                assert!(self.limit > 0, "read past the end of the entry");
+                buf[0] = self.bytes[0];
+                self.bytes = &self.bytes[1..];
+                self.limit -= 1;
+                Ok(1)
+            }
+        }
+
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let entries = n_valid_entries(&key_pair, 4);
+        let bytes = entries.concat();
+
+        let mut reader = EntryReader::new(SlowReader {
+            bytes: &bytes,
+            limit: entries[0].len() + entries[1].len(),
+        });
+
+        assert_eq!(reader.read_entry().unwrap().unwrap().seq_num, 1);
+        assert_eq!(reader.read_entry().unwrap().unwrap().seq_num, 2);
+        assert_eq!(reader.entry_bytes(), &entries[1][..]);
+    }
+
+    #[test]
+    fn text_round_trips_to_the_same_bytes() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let mut entries = n_valid_entries(&key_pair, 30);
+
+        let mut publisher = Publisher::from_entries(copy_key_pair(&key_pair), 0, &entries).unwrap();
+        let mut out = [0u8; 512];
+        let size = publisher.publish(&mut out, b"goodbye", true).unwrap();
+        entries.push(out[..size].to_vec());
+
+        for bytes in entries.iter() {
+            let entry = decode(bytes).unwrap();
+            let text = entry.to_text();
+            let parsed = Entry::from_text(&text).unwrap();
+
+            let mut encoded = [0u8; 512];
+            let size = parsed.encode(&mut encoded).unwrap();
+            assert_eq!(&encoded[..size], &bytes[..]);
+            assert_eq!(parsed.to_text(), text);
+        }
+    }
+
+    #[test]
+    fn text_is_canonical() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let entries = n_valid_entries(&key_pair, 2);
+
+        let text = decode(&entries[0]).unwrap().to_text();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "is_end_of_feed: false");
+        assert_eq!(
+            lines[1],
+            format!("author: {}", hex::encode(key_pair.public))
+        );
+        assert_eq!(
+            &lines[2..6],
+            &[
+                "log_id: 0",
+                "seq_num: 1",
+                "lipmaa_link: none",
+                "backlink: none"
+            ]
+        );
+        assert_eq!(lines[6], "payload_size: 16");
+
+        match Entry::from_text(&text.replace("seq_num: 1", "seq_num: 01")) {
+            Err(TextError::TextInvalidValue { field: "seq_num" }) => {}
+            e => panic!("expected a non canonical seq_num to fail, got {:?}", e),
+        }
+
+        match Entry::from_text(&text.replace("seq_num: 1", "seq_num: 2")) {
+            Err(TextError::TextInvalidLinks { seq_num: 2 }) => {}
+            e => panic!("expected missing links to fail, got {:?}", e),
+        }
+
+        match Entry::from_text(&text.to_uppercase()) {
+            Err(TextError::TextMissingField {
+                field: "is_end_of_feed",
+            }) => {}
+            e => panic!("expected upper case text to fail, got {:?}", e),
+        }
+
+        match Entry::from_text(&format!("{}extra: 1\n", text)) {
+            Err(TextError::TextUnexpectedLine) => {}
+            e => panic!("expected an extra line to fail, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn owned_entry_round_trips_through_bytes_and_serde() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        for bytes in n_valid_entries(&key_pair, 5) {
+            let owned = OwnedEntry::try_from(&bytes[..]).unwrap();
+            assert_eq!(owned.as_bytes(), &bytes[..]);
+            assert_eq!(owned.to_vec(), bytes);
+            assert_eq!(&owned.to_bytes()[..], &bytes[..]);
+
+            let entry = decode(&bytes).unwrap();
+            assert_eq!(OwnedEntry::try_from(&entry).unwrap(), owned);
+            assert_eq!(owned.seq_num, entry.seq_num);
+
+            let json = serde_json::to_string(&owned).unwrap();
+            assert_eq!(json, serde_json::to_string(&entry).unwrap());
+            assert_eq!(serde_json::from_str::<OwnedEntry>(&json).unwrap(), owned);
+
+            let binary = bincode::serialize(&owned).unwrap();
+            assert_eq!(bincode::deserialize::<OwnedEntry>(&binary).unwrap(), owned);
+        }
+
+        let bytes = n_valid_entries(&key_pair, 1).remove(0);
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        match OwnedEntry::try_from(&trailing[..]) {
+            Err(OwnedError::OwnedEntryTrailingBytes) => {}
+            e => panic!("expected trailing bytes to be rejected, got {:?}", e),
+        }
+
+        let mut unsigned = decode(&bytes).unwrap();
+        unsigned.sig = None;
+        match OwnedEntry::try_from(&unsigned) {
+            Err(OwnedError::OwnedEntryMissingSig) => {}
+            e => panic!("expected an unsigned entry to be rejected, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn owned_entry_orders_by_author_log_id_and_seq_num() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pairs = [
+            Keypair::generate(&mut csprng),
+            Keypair::generate(&mut csprng),
+        ];
+
+        let mut entries = Vec::new();
+        for key_pair in key_pairs.iter() {
+            for log_id in 0..2 {
+                for bytes in publish_batch(key_pair, log_id, None, vec!["a", "b", "c"]).unwrap() {
+                    entries.push(OwnedEntry::try_from(&bytes[..]).unwrap());
+                }
+            }
+        }
+        entries.reverse();
+        entries.sort();
+
+        let keys = entries
+            .iter()
+            .map(|entry| (entry.author.to_bytes(), entry.log_id, entry.seq_num))
+            .collect::<Vec<_>>();
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort();
+        assert_eq!(keys, sorted_keys);
+
+        let set = entries.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(set.len(), entries.len());
+        assert!(set.contains(&entries[3]));
+    }
 }
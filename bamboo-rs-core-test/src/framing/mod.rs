@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use bamboo_rs_core_ed25519_yasmf::framing::{Error, Frame, FrameCodec, FrameDecoder};
+    use bamboo_rs_core_ed25519_yasmf::{publish_batch, Keypair};
+    use bytes::BytesMut;
+    use rand::rngs::OsRng;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    fn frames() -> Vec<Frame> {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let payloads = vec![b"hello".to_vec(), b"bamboo".to_vec(), vec![]];
+        let entries = publish_batch(&key_pair, 0, None, &payloads).unwrap();
+
+        vec![
+            Frame::new(entries[0].clone(), Some(payloads[0].clone())),
+            Frame::new(entries[1].clone(), None),
+            Frame::new(entries[2].clone(), Some(payloads[2].clone())),
+        ]
+    }
+
+    #[test]
+    fn frame_decoder_decodes_byte_at_a_time() {
+        let frames = frames();
+        let mut bytes = Vec::new();
+        for frame in frames.iter() {
+            frame.encode(&mut bytes).unwrap();
+        }
+        assert_eq!(
+            bytes.len(),
+            frames
+                .iter()
+                .map(|frame| frame.encoding_length())
+                .sum::<usize>()
+        );
+
+        let mut decoder = FrameDecoder::new();
+        let mut decoded = Vec::new();
+        for byte in bytes {
+            decoder.extend_from_slice(&[byte]);
+            if let Some(frame) = decoder.next_frame().unwrap() {
+                decoded.push(frame);
+            }
+        }
+
+        assert_eq!(decoded, frames);
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn frame_payload_length_is_checked_before_the_payload_arrives() {
+        let frame = frames().remove(0);
+
+        match Frame::new(frame.entry.clone(), Some(b"hello!".to_vec())).encode(&mut Vec::new()) {
+            Err(Error::FramePayloadSizeMismatch { .. }) => {}
+            e => panic!(
+                "expected encoding a frame with the wrong payload to fail, got {:?}",
+                e
+            ),
+        }
+
+        // Frame the entry with a 1000 byte payload but don't send the payload.
+        let mut bytes = Vec::new();
+        Frame::new(frame.entry.clone(), None)
+            .encode(&mut bytes)
+            .unwrap();
+        bytes.pop();
+        bytes.extend_from_slice(&[1, 249, 0x03, 0xe8]);
+
+        let mut decoder = FrameDecoder::new();
+        decoder.extend_from_slice(&bytes);
+        match decoder.next_frame() {
+            Err(Error::FramePayloadSizeMismatch {
+                actual: 1000,
+                payload_size: 5,
+            }) => {}
+            e => panic!("expected a payload size mismatch, got {:?}", e),
+        }
+
+        let mut bytes = Vec::new();
+        frame.encode(&mut bytes).unwrap();
+        let mut decoder = FrameDecoder::new().with_max_payload_size(4);
+        decoder.extend_from_slice(&bytes);
+        match decoder.next_frame() {
+            Err(Error::FramePayloadTooLarge { actual: 5, max: 4 }) => {}
+            e => panic!("expected the payload to be too large, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn frame_codec_round_trips() {
+        let frames = frames();
+        let mut codec = FrameCodec::new();
+        let mut bytes = BytesMut::new();
+
+        for frame in frames.iter() {
+            codec.encode(frame.clone(), &mut bytes).unwrap();
+        }
+
+        let mut decoded = Vec::new();
+        while let Some(frame) = codec.decode(&mut bytes).unwrap() {
+            decoded.push(frame);
+        }
+
+        assert_eq!(decoded, frames);
+        assert!(bytes.is_empty());
+    }
+}
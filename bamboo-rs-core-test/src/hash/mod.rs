@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use bamboo_rs_core_ed25519_yasmf::entry::{decode, OwnedEntry};
+    use bamboo_rs_core_ed25519_yasmf::hash::{
+        decode_hash, decode_hash_owned, digest, encode_hash, hash_like, to_owned_hash, Error,
+        MAX_HASH_ENCODING_SIZE,
+    };
+    use bamboo_rs_core_ed25519_yasmf::yasmf_hash::{new_blake3, BLAKE3_NUMERIC_ID};
+    use bamboo_rs_core_ed25519_yasmf::{publish_batch, HashAlgorithm, Keypair, YasmfHash};
+    use rand::rngs::OsRng;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn hash_algorithm_dispatches_on_yasmf_id() {
+        for algorithm in HashAlgorithm::ALL.iter().copied() {
+            assert_eq!(HashAlgorithm::from_id(algorithm.id()).unwrap(), algorithm);
+
+            let hash = algorithm.hash(b"bamboo");
+            assert_eq!(HashAlgorithm::of(&hash), algorithm);
+            assert_eq!(digest(&hash).len(), algorithm.digest_size());
+            assert_eq!(hash.encoding_length(), algorithm.encoding_length());
+            assert_eq!(hash_like(&hash, b"bamboo"), hash);
+        }
+
+        assert_eq!(HashAlgorithm::default().id(), BLAKE3_NUMERIC_ID);
+        assert_eq!(
+            HashAlgorithm::default().hash(b"bamboo"),
+            new_blake3(b"bamboo")
+        );
+
+        match HashAlgorithm::from_id(1) {
+            Err(Error::UnsupportedHashId { id: 1 }) => {}
+            e => panic!("expected an unsupported hash id, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn hashes_encode_and_decode_through_the_hash_algorithm() {
+        for algorithm in HashAlgorithm::ALL.iter().copied() {
+            let hash = algorithm.hash(b"bamboo");
+
+            let mut encoded = [0u8; MAX_HASH_ENCODING_SIZE + 1];
+            let len = encode_hash(&hash, &mut encoded).unwrap();
+            assert_eq!(len, algorithm.encoding_length());
+
+            let mut written = Vec::new();
+            hash.encode_write(&mut written).unwrap();
+            assert_eq!(written, &encoded[..len]);
+
+            let (decoded, remaining_bytes) = decode_hash(&encoded[..len + 1]).unwrap();
+            assert_eq!(decoded, hash);
+            assert_eq!(remaining_bytes, &[0]);
+            assert_eq!(decode_hash_owned(&encoded[..len]).unwrap().0, hash);
+
+            match decode_hash(&encoded[..len - 1]) {
+                Err(Error::DecodeHashTooShort) => {}
+                e => panic!("expected the hash to be too short, got {:?}", e),
+            }
+            match encode_hash(&hash, &mut encoded[..len - 1]) {
+                Err(Error::EncodeHashBufferLength) => {}
+                e => panic!("expected the buffer to be too short, got {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn decode_hash_checks_the_id_and_digest_size() {
+        let mut encoded = [0u8; MAX_HASH_ENCODING_SIZE];
+        let len = encode_hash(&HashAlgorithm::default().hash(b"bamboo"), &mut encoded).unwrap();
+
+        encoded[0] = 1;
+        match decode_hash(&encoded[..len]) {
+            Err(Error::UnsupportedHashId { id: 1 }) => {}
+            e => panic!("expected an unsupported hash id, got {:?}", e),
+        }
+
+        encoded[0] = BLAKE3_NUMERIC_ID as u8;
+        encoded[1] = 31;
+        match decode_hash(&encoded[..len]) {
+            Err(Error::HashDigestSizeDoesNotMatch {
+                expected: 32,
+                actual: 31,
+                ..
+            }) => {}
+            e => panic!("expected a digest size mismatch, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn owned_entry_keeps_the_hash_algorithm() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let entries =
+            publish_batch(&key_pair, 0, None, vec!["one", "two", "three", "four"]).unwrap();
+
+        let entry = decode(&entries[3]).unwrap();
+        let owned = OwnedEntry::try_from(&entry).unwrap();
+
+        assert_eq!(owned.payload_hash, entry.payload_hash);
+        let mut encoded = Vec::new();
+        owned.encode_write(&mut encoded).unwrap();
+        assert_eq!(encoded, entries[3]);
+        assert_eq!(
+            to_owned_hash(&YasmfHash::Blake3(digest(&entry.payload_hash))),
+            owned.payload_hash
+        );
+    }
+}
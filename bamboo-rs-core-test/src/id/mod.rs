@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use bamboo_rs_core_ed25519_yasmf::id::Error;
+    use bamboo_rs_core_ed25519_yasmf::{
+        decode, publish_batch, EntryId, FeedId, HashAlgorithm, Keypair,
+    };
+    use rand::rngs::OsRng;
+
+    fn entry_bytes(key_pair: &Keypair, log_id: u64) -> Vec<u8> {
+        publish_batch(key_pair, log_id, None, vec!["one", "two", "three", "four"])
+            .unwrap()
+            .remove(3)
+    }
+
+    #[test]
+    fn feed_id_round_trips() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+
+        for log_id in [0, 1, 247, 248, u64::MAX].iter() {
+            let feed_id = FeedId::new(key_pair.public, *log_id);
+
+            let text = feed_id.to_string();
+            assert!(text.starts_with("feed_"));
+            assert_eq!(text.parse::<FeedId>().unwrap(), feed_id);
+
+            let uri = feed_id.uri().to_string();
+            assert_eq!(
+                uri,
+                format!("bamboo:feed/{}/{}", hex::encode(key_pair.public), log_id)
+            );
+            assert_eq!(uri.parse::<FeedId>().unwrap(), feed_id);
+
+            let json = serde_json::to_string(&feed_id).unwrap();
+            assert_eq!(json, format!("\"{}\"", text));
+            assert_eq!(serde_json::from_str::<FeedId>(&json).unwrap(), feed_id);
+        }
+    }
+
+    #[test]
+    fn entry_id_round_trips() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let bytes = entry_bytes(&key_pair, 7);
+
+        let entry_id = EntryId::from_entry_bytes(&bytes).unwrap();
+        let entry = decode(&bytes).unwrap();
+        assert_eq!(entry_id.seq_num, 4);
+        assert_eq!(entry_id.feed_id(), entry.feed_id());
+
+        let text = entry_id.to_string();
+        assert!(text.starts_with("entry_"));
+        assert_eq!(text.parse::<EntryId>().unwrap(), entry_id);
+
+        let uri = entry_id.uri().to_string();
+        assert!(uri.starts_with(&format!(
+            "bamboo:entry/{}/7/4/",
+            hex::encode(key_pair.public)
+        )));
+        assert_eq!(uri.parse::<EntryId>().unwrap(), entry_id);
+
+        let json = serde_json::to_string(&entry_id).unwrap();
+        assert_eq!(serde_json::from_str::<EntryId>(&json).unwrap(), entry_id);
+    }
+
+    #[test]
+    fn mistyped_ids_are_rejected() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let text = FeedId::new(key_pair.public, 3).to_string();
+
+        // Change one hex character.
+        let mut mistyped = text.clone().into_bytes();
+        let index = mistyped.len() - 12;
+        mistyped[index] = if mistyped[index] == b'0' { b'1' } else { b'0' };
+        match String::from_utf8(mistyped).unwrap().parse::<FeedId>() {
+            Err(Error::IdInvalidChecksum) => {}
+            e => panic!("expected a checksum error, got {:?}", e),
+        }
+
+        match text.replace("feed_", "entry_").parse::<EntryId>() {
+            Err(Error::IdInvalidChecksum) => {}
+            e => panic!("expected a checksum error, got {:?}", e),
+        }
+
+        match text.parse::<EntryId>() {
+            Err(Error::IdInvalidPrefix { .. }) => {}
+            e => panic!("expected a prefix error, got {:?}", e),
+        }
+
+        match format!("bamboo:feed/{}/03", hex::encode(key_pair.public)).parse::<FeedId>() {
+            Err(Error::IdInvalidLogId) => {}
+            e => panic!("expected a log_id error, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn entry_id_uris_keep_the_hash_algorithm() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let bytes = entry_bytes(&key_pair, 0);
+
+        for algorithm in HashAlgorithm::ALL.iter().copied() {
+            let entry_id = EntryId::from_entry_bytes_with_algorithm(&bytes, algorithm).unwrap();
+            assert_eq!(HashAlgorithm::of(&entry_id.hash), algorithm);
+            assert_eq!(entry_id.hash, algorithm.hash(&bytes));
+
+            let uri = entry_id.uri().to_string();
+            assert_eq!(uri.parse::<EntryId>().unwrap(), entry_id);
+
+            // Dropping hex characters from the hash leaves a digest that's too short or odd hex.
+            for dropped in 1..3 {
+                match uri[..uri.len() - dropped].parse::<EntryId>() {
+                    Err(Error::IdInvalidHash) => {}
+                    e => panic!("expected a hash error, got {:?}", e),
+                }
+            }
+        }
+    }
+}
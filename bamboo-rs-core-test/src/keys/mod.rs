@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use bamboo_rs_core_ed25519_yasmf::keys::{
+        derivation_path, Error, ExtendedKey, KeyPurpose, SeedPhrase, BAMBOO_COIN_TYPE,
+        BIP44_PURPOSE,
+    };
+
+    const ABANDON_PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn bip39_known_answer() {
+        let phrase = SeedPhrase::from_entropy(&[0u8; 16]).unwrap();
+        assert_eq!(phrase.to_string(), ABANDON_PHRASE);
+        assert_eq!(phrase.words().count(), 12);
+
+        let seed = SeedPhrase::parse(ABANDON_PHRASE).unwrap().to_seed("TREZOR");
+        assert_eq!(
+            hex::encode(&seed[..]),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    #[test]
+    fn slip10_known_answer() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::from_seed(&seed);
+        let vectors = [
+            (
+                vec![],
+                "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+            ),
+            (
+                vec![0],
+                "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+            ),
+            (
+                vec![0, 1],
+                "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+                "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+                "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+            ),
+        ];
+
+        for (path, chain_code, secret, public) in vectors.iter() {
+            let key = master.derive_path(path).unwrap();
+            let key_pair = key.to_key_pair();
+            assert_eq!(hex::encode(key.chain_code()), *chain_code);
+            assert_eq!(hex::encode(key_pair.secret.as_bytes()), *secret);
+            assert_eq!(hex::encode(key_pair.public.as_bytes()), *public);
+        }
+    }
+
+    #[test]
+    fn restored_phrase_derives_the_same_feed_keys() {
+        let root = SeedPhrase::parse(ABANDON_PHRASE).unwrap().root_key("");
+        let key_pair = root.derive_key_pair(KeyPurpose::FEED, 0);
+        assert_eq!(
+            hex::encode(key_pair.public.as_bytes()),
+            "370edb95f00233befceb9463af6ad039b1109e6bdfcfa31f0a62d2aa2147ed54"
+        );
+
+        let restored =
+            SeedPhrase::parse(&SeedPhrase::from_entropy(&[0u8; 16]).unwrap().to_string())
+                .unwrap()
+                .root_key("");
+        assert_eq!(
+            restored.derive_key_pair(KeyPurpose::FEED, 0).public,
+            key_pair.public
+        );
+        assert_ne!(
+            root.derive_key_pair(KeyPurpose::FEED, 1).public,
+            key_pair.public
+        );
+        assert_ne!(
+            root.derive_key_pair(KeyPurpose::new(1).unwrap(), 0).public,
+            key_pair.public
+        );
+        assert_ne!(
+            SeedPhrase::parse(ABANDON_PHRASE)
+                .unwrap()
+                .root_key("passphrase")
+                .derive_key_pair(KeyPurpose::FEED, 0)
+                .public,
+            key_pair.public
+        );
+    }
+
+    #[test]
+    fn derivation_path_splits_log_id() {
+        assert_eq!(
+            derivation_path(KeyPurpose::FEED, u64::MAX),
+            [
+                BIP44_PURPOSE,
+                BAMBOO_COIN_TYPE,
+                0,
+                3,
+                (1 << 31) - 1,
+                (1 << 31) - 1
+            ]
+        );
+        assert_eq!(
+            derivation_path(KeyPurpose::FEED, 1 << 31),
+            [BIP44_PURPOSE, BAMBOO_COIN_TYPE, 0, 0, 1, 0]
+        );
+    }
+
+    #[test]
+    fn invalid_phrases_and_indices_are_errors() {
+        let bad_checksum = ABANDON_PHRASE.replace("about", "abandon");
+        match SeedPhrase::parse(&bad_checksum) {
+            Err(Error::InvalidSeedPhrase { .. }) => {}
+            e => panic!("expected an invalid seed phrase, got {:?}", e),
+        }
+        match SeedPhrase::from_entropy(&[0u8; 15]) {
+            Err(Error::InvalidEntropyLength { length: 15 }) => {}
+            e => panic!("expected an invalid entropy length, got {:?}", e),
+        }
+        match KeyPurpose::new(1 << 31) {
+            Err(Error::InvalidDerivationIndex { .. }) => {}
+            e => panic!("expected an invalid index, got {:?}", e),
+        }
+        assert!(ExtendedKey::from_seed(&[0u8; 16])
+            .derive_child(1 << 31)
+            .is_err());
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use bamboo_rs_core_ed25519_yasmf::keystore::{
+        Error, KdfParams, Keystore, KEYSTORE_SIZE, KEYSTORE_VERSION, MAX_ITERATIONS,
+    };
+    use bamboo_rs_core_ed25519_yasmf::{Keypair, SecretKey};
+    use rand::rngs::OsRng;
+
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    // The key pair from `key_pair` encrypted with the password "bamboo" and `TEST_PARAMS`.
+    const KEYSTORE_VECTOR: &str = "424b530001400000000100000001000000b2f7f581d6de3c06a822fd6e7e8265fbc00f8401696a5bdc34f5a6d2ff3f922f58a28c18576b71e5ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c1bdb8a88920f896f0be1117001acc582fb17286bcd116b9e289ae5728d0337eca269928a17f18a324d139fa81563fadb";
+
+    fn key_pair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        Keypair {
+            public: (&secret).into(),
+            secret,
+        }
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let key_pair = key_pair();
+        let keystore = Keystore::encrypt(&key_pair, b"bamboo", TEST_PARAMS, &mut OsRng {}).unwrap();
+        let keystore = Keystore::from_bytes(keystore.as_bytes()).unwrap();
+
+        assert_eq!(keystore.as_bytes().len(), KEYSTORE_SIZE);
+        assert_eq!(keystore.kdf_params(), TEST_PARAMS);
+        assert_eq!(keystore.public_key(), key_pair.public);
+
+        let decrypted = keystore.decrypt(b"bamboo").unwrap();
+        assert_eq!(decrypted.to_bytes()[..], key_pair.to_bytes()[..]);
+
+        match keystore.decrypt(b"bamboo!") {
+            Err(Error::DecryptSecretKey) => {}
+            e => panic!("expected a decrypt error, got {:?}", e.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn decrypts_known_keystore() {
+        let bytes = hex::decode(KEYSTORE_VECTOR).unwrap();
+        let keystore = Keystore::from_bytes(&bytes).unwrap();
+
+        assert_eq!(keystore.kdf_params(), TEST_PARAMS);
+        assert_eq!(keystore.public_key(), key_pair().public);
+        assert_eq!(
+            keystore.decrypt(b"bamboo").unwrap().to_bytes()[..],
+            key_pair().to_bytes()[..]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_and_tampered_keystores() {
+        let bytes = hex::decode(KEYSTORE_VECTOR).unwrap();
+
+        match Keystore::from_bytes(&bytes[1..]) {
+            Err(Error::InvalidKeystoreLength { .. }) => {}
+            e => panic!("expected an invalid length, got {:?}", e),
+        }
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        match Keystore::from_bytes(&bad_magic) {
+            Err(Error::InvalidKeystoreMagic) => {}
+            e => panic!("expected an invalid magic, got {:?}", e),
+        }
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = KEYSTORE_VERSION + 1;
+        match Keystore::from_bytes(&bad_version) {
+            Err(Error::UnsupportedKeystoreVersion { .. }) => {}
+            e => panic!("expected an unsupported version, got {:?}", e),
+        }
+
+        let mut huge_memory = bytes.clone();
+        huge_memory[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        match Keystore::from_bytes(&huge_memory) {
+            Err(Error::InvalidKdfParams) => {}
+            e => panic!("expected invalid kdf params, got {:?}", e),
+        }
+
+        let mut many_iterations = bytes.clone();
+        many_iterations[9..13].copy_from_slice(&(MAX_ITERATIONS + 1).to_le_bytes());
+        match Keystore::from_bytes(&many_iterations) {
+            Err(Error::InvalidKdfParams) => {}
+            e => panic!("expected invalid kdf params, got {:?}", e),
+        }
+
+        // The nonce is authenticated, so changing it fails to decrypt rather than decrypting a
+        // different key.
+        let mut tampered = bytes;
+        tampered[40] ^= 1;
+        match Keystore::from_bytes(&tampered).unwrap().decrypt(b"bamboo") {
+            Err(Error::DecryptSecretKey) => {}
+            e => panic!("expected a decrypt error, got {:?}", e.map(|_| ())),
+        }
+    }
+}
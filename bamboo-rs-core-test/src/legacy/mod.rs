@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use bamboo_rs_core_ed25519_yasmf::entry::is_lipmaa_required;
+    use bamboo_rs_core_ed25519_yasmf::legacy::{
+        decode, legacy_hash, migrate_feed, verify, Error, LegacyMigrator, MAX_LEGACY_ENTRY_SIZE,
+    };
+    use bamboo_rs_core_ed25519_yasmf::yasmf_hash::new_blake3;
+    use bamboo_rs_core_ed25519_yasmf::{
+        decode as decode_entry, publish_batch, verify_batch, Keypair,
+    };
+    use ed25519_dalek::Signer;
+    use lipmaa_link::lipmaa;
+    use rand::rngs::OsRng;
+
+    fn push_varu64(value: u64, out: &mut Vec<u8>) {
+        let mut buf = [0u8; 9];
+        let len = varu64::encode(value, &mut buf);
+        out.extend_from_slice(&buf[..len]);
+    }
+
+    fn push_legacy_hash(bytes: &[u8], out: &mut Vec<u8>) {
+        out.extend_from_slice(&[1, 64]);
+        out.extend_from_slice(&legacy_hash(bytes));
+    }
+
+    /// Encode a legacy feed the way bamboo-rs did before 0.1.0-pre-25.
+    fn publish_legacy(key_pair: &Keypair, payloads: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let mut entries: Vec<Vec<u8>> = Vec::new();
+
+        for (i, payload) in payloads.iter().enumerate() {
+            let seq_num = i as u64 + 1;
+            let mut bytes = vec![0];
+            push_legacy_hash(payload, &mut bytes);
+            push_varu64(payload.len() as u64, &mut bytes);
+            bytes.extend_from_slice(&[1, 32]);
+            bytes.extend_from_slice(key_pair.public.as_bytes());
+            push_varu64(seq_num, &mut bytes);
+
+            if seq_num > 1 {
+                if is_lipmaa_required(seq_num) {
+                    push_legacy_hash(&entries[lipmaa(seq_num) as usize - 1], &mut bytes);
+                }
+                push_legacy_hash(&entries[seq_num as usize - 2], &mut bytes);
+            }
+
+            let sig = key_pair.sign(&bytes);
+            push_varu64(64, &mut bytes);
+            bytes.extend_from_slice(&sig.to_bytes());
+            assert!(bytes.len() <= MAX_LEGACY_ENTRY_SIZE);
+
+            entries.push(bytes);
+        }
+
+        entries
+    }
+
+    fn payloads(n: usize) -> Vec<Vec<u8>> {
+        (0..n)
+            .map(|i| format!("payload {}", i).into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn legacy_entries_decode_and_verify() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let payloads = payloads(20);
+        let entries = publish_legacy(&key_pair, &payloads);
+
+        for (i, bytes) in entries.iter().enumerate() {
+            let seq_num = i as u64 + 1;
+            let entry = decode(bytes).unwrap();
+            assert_eq!(entry.seq_num, seq_num);
+            assert_eq!(entry.author, key_pair.public);
+            assert_eq!(entry.payload_hash, &legacy_hash(&payloads[i])[..]);
+
+            let backlink = i.checked_sub(1).map(|i| &entries[i][..]);
+            let lipmaa_link = entry
+                .lipmaa_link
+                .map(|_| &entries[lipmaa(seq_num) as usize - 1][..]);
+            verify(bytes, Some(&payloads[i]), lipmaa_link, backlink).unwrap();
+        }
+
+        match verify(&entries[0], Some(b"not the payload"), None, None) {
+            Err(Error::LegacyPayloadHashDidNotMatch) => {}
+            e => panic!("expected the payload hash not to match, got {:?}", e),
+        }
+
+        match verify(&entries[2], None, None, Some(&entries[0])) {
+            Err(Error::LegacyBacklinkHashDoesNotMatch) => {}
+            e => panic!("expected the backlink hash not to match, got {:?}", e),
+        }
+
+        let mut tampered = entries[0].clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        match verify(&tampered, None, None, None) {
+            Err(Error::LegacyInvalidSignature) => {}
+            e => panic!("expected an invalid signature, got {:?}", e),
+        }
+
+        // Current entries use yasmf blake3 hashes so aren't legacy entries.
+        let current = publish_batch(&key_pair, 3, None, &payloads[..1]).unwrap();
+        match decode(&current[0]) {
+            Err(Error::LegacyDecodeHash { .. }) => {}
+            e => panic!("expected a yasmf hash not to decode, got {:?}", e),
+        }
+    }
+
+    /// The example entry from `test_vectors/test_vectors.md`, published by bamboo-rs before
+    /// 0.1.0-pre-25.
+    const PRE_25_ENTRY: &str = "00014069db5208a271c53de8a1b6220e6a4d7fcccd89e6c0c7e75c833e34dc68d932624f2ccf27513f42fb7d0e4390a99b225bad41ba14a6297537246dbe4e6ce150e80d0120b46f22fbd233f30af255294701f96b9fd89220588cdbbb42150164a451e9b11101403e39afaabde37ff1eea5078e3c055c74099102ec1ca6971045ad25f801fd1e7b446bf9b6988f4dce30e5f04b554a6736878e3d2964af0773c78638e84ad20200";
+
+    #[test]
+    fn legacy_hash_matches_a_published_entry() {
+        let bytes = hex::decode(PRE_25_ENTRY).unwrap();
+        let payload = b"hello bamboo!";
+
+        let entry = decode(&bytes).unwrap();
+        assert!(!entry.is_end_of_feed);
+        assert_eq!(entry.seq_num, 1);
+        assert_eq!(entry.payload_size, payload.len() as u64);
+        assert_eq!(entry.payload_hash, &legacy_hash(payload)[..]);
+        assert_eq!(entry.author.as_bytes(), &bytes[70..102]);
+        assert_eq!(entry.lipmaa_link, None);
+        assert_eq!(entry.backlink, None);
+
+        verify(&bytes, Some(payload), None, None).unwrap();
+
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let migration = migrate_feed(key_pair, 0, vec![(&bytes, &payload[..])]).unwrap();
+        assert_eq!(migration.entries.len(), 1);
+        assert_eq!(migration.hash_mappings[0].seq_num, 1);
+        assert_eq!(migration.hash_mappings[0].legacy_hash, legacy_hash(&bytes));
+        verify_batch(&[(migration.entries[0].clone(), Some(payload.to_vec()))]).unwrap();
+    }
+
+    #[test]
+    fn migrate_legacy_feed() {
+        let mut csprng: OsRng = OsRng {};
+        let key_pair: Keypair = Keypair::generate(&mut csprng);
+        let payloads = payloads(20);
+        let entries = publish_legacy(&key_pair, &payloads);
+
+        let new_key_pair = Keypair::from_bytes(&key_pair.to_bytes()).unwrap();
+        let migration = migrate_feed(new_key_pair, 7, entries.iter().zip(payloads.iter())).unwrap();
+
+        let entries_and_payloads = migration
+            .entries
+            .iter()
+            .zip(payloads.iter())
+            .map(|(entry, payload)| (entry.clone(), Some(payload.clone())))
+            .collect::<Vec<_>>();
+        verify_batch(&entries_and_payloads).unwrap();
+
+        for (i, mapping) in migration.hash_mappings.iter().enumerate() {
+            let entry = decode_entry(&migration.entries[i]).unwrap();
+            assert_eq!(entry.seq_num, i as u64 + 1);
+            assert_eq!(entry.log_id, 7);
+            assert_eq!(mapping.seq_num, entry.seq_num);
+            assert_eq!(mapping.legacy_hash, legacy_hash(&entries[i]));
+            assert_eq!(mapping.hash, new_blake3(&migration.entries[i]));
+        }
+
+        let new_key_pair = Keypair::from_bytes(&key_pair.to_bytes()).unwrap();
+        let mut migrator = LegacyMigrator::new(new_key_pair, 0);
+        let mut out = [0u8; 512];
+        match migrator.migrate_entry(&mut out, &entries[1], &payloads[1]) {
+            Err(Error::MigrateSeqNumNotInOrder {
+                expected: 1,
+                actual: 2,
+            }) => {}
+            e => panic!("expected entries to be migrated in order, got {:?}", e),
+        }
+        match migrator.migrate_entry(&mut out, &entries[0], &payloads[1]) {
+            Err(Error::LegacyPayloadHashDidNotMatch) => {}
+            e => panic!("expected the payload hash not to match, got {:?}", e),
+        }
+    }
+}
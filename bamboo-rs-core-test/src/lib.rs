@@ -1,2 +1,8 @@
 mod entry;
+mod framing;
+mod hash;
+mod id;
+mod keys;
+mod keystore;
+mod legacy;
 mod signature;
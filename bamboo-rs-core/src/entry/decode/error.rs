@@ -1,12 +1,12 @@
+use crate::hash::Error as HashError;
 use crate::signature::error::Error as SigError;
 use snafu::Snafu;
-use yasmf_hash::error::Error as YasmfHashError;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(super)")]
 pub enum Error {
     #[snafu(display("Could not decode payload hash {}", source))]
-    DecodePayloadHashError { source: YasmfHashError },
+    DecodePayloadHashError { source: HashError },
     #[snafu(display("Could not decode payload size, error with varu64 encoding"))]
     DecodePayloadSizeError,
     #[snafu(display("Could not decode log_id, error with varu64 encoding"))]
@@ -18,13 +18,26 @@ pub enum Error {
     #[snafu(display("Entry sequence must be larger than 0 but was {}", seq_num))]
     DecodeSeqIsZero { seq_num: u64 },
     #[snafu(display("Could not decode backlink yamf hash: {}", source))]
-    DecodeBacklinkError { source: YasmfHashError },
+    DecodeBacklinkError { source: HashError },
     #[snafu(display("Could not decode lipmaa link yamf hash {}", source))]
-    DecodeLipmaaError { source: YasmfHashError },
+    DecodeLipmaaError { source: HashError },
     #[snafu(display("Could not decode signature: {}", source))]
     DecodeSigError { source: SigError },
     #[snafu(display("Bytes to decode had length of 0"))]
     DecodeInputIsLengthZero,
 }
 
+/// Errors from reading entries with an [EntryReader](super::EntryReader).
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum ReadError {
+    #[snafu(display("Could not read entry: {}", source))]
+    ReadEntryIo { source: std::io::Error },
+    #[snafu(display("Input ended part way through an entry"))]
+    ReadEntryUnexpectedEof,
+    #[snafu(display("Could not decode entry: {}", source))]
+    ReadEntryDecode { source: Error },
+}
+
 pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -2,19 +2,35 @@ use varu64::decode as varu64_decode;
 
 use ed25519_dalek::{PublicKey as DalekPublicKey, PUBLIC_KEY_LENGTH};
 
+use crate::hash::decode_hash;
 use crate::signature::Signature;
-use crate::yasmf_hash::YasmfHash;
 
 use super::{is_lipmaa_required, Entry};
 use snafu::{ensure, NoneError, ResultExt};
 
 pub mod error;
+#[cfg(feature = "std")]
+pub mod read;
 pub use error::*;
+#[cfg(feature = "std")]
+pub use read::EntryReader;
+
+/// An [Entry] that references the bytes it was decoded from.
+pub type EntryRef<'a> = Entry<&'a [u8], &'a [u8]>;
 
 /// Try and decode `bytes` as an [Entry].
 ///
-/// Returned [Entry] references `bytes`.
+/// Returned [Entry] references `bytes`. Any bytes after the entry are ignored, use
+/// [decode_with_remainder] to get them.
 pub fn decode<'a>(bytes: &'a [u8]) -> Result<Entry<&'a [u8], &'a [u8]>, Error> {
+    decode_with_remainder(bytes).map(|(entry, _)| entry)
+}
+
+/// Try and decode an [Entry] from the start of `bytes`, returning it and the bytes that follow it.
+///
+/// Entries are self-delimiting so this can be used to decode entries that are concatenated
+/// together. See also [decode_iter].
+pub fn decode_with_remainder(bytes: &[u8]) -> Result<(EntryRef<'_>, &[u8]), Error> {
     ensure!(bytes.len() > 0, DecodeInputIsLengthZero);
 
     // Decode is end of feed
@@ -47,14 +63,14 @@ pub fn decode<'a>(bytes: &'a [u8]) -> Result<Entry<&'a [u8], &'a [u8]>, Error> {
         (1, _) => (None, None, remaining_bytes),
         (_, true) => {
             let (lipmaa_link, remaining_bytes) =
-                YasmfHash::<&[u8]>::decode(remaining_bytes).context(DecodeLipmaaError)?;
+                decode_hash(remaining_bytes).context(DecodeLipmaaError)?;
             let (backlink, remaining_bytes) =
-                YasmfHash::<&[u8]>::decode(remaining_bytes).context(DecodeBacklinkError)?;
+                decode_hash(remaining_bytes).context(DecodeBacklinkError)?;
             (Some(backlink), Some(lipmaa_link), remaining_bytes)
         }
         (_, false) => {
             let (backlink, remaining_bytes) =
-                YasmfHash::<&[u8]>::decode(remaining_bytes).context(DecodeBacklinkError)?;
+                decode_hash(remaining_bytes).context(DecodeBacklinkError)?;
             (Some(backlink), None, remaining_bytes)
         }
     };
@@ -66,12 +82,13 @@ pub fn decode<'a>(bytes: &'a [u8]) -> Result<Entry<&'a [u8], &'a [u8]>, Error> {
 
     // Decode the payload hash
     let (payload_hash, remaining_bytes) =
-        YasmfHash::<&[u8]>::decode(remaining_bytes).context(DecodePayloadHashError)?;
+        decode_hash(remaining_bytes).context(DecodePayloadHashError)?;
 
     // Decode the signature
-    let (sig, _) = Signature::<&[u8]>::decode(remaining_bytes).context(DecodeSigError)?;
+    let (sig, remaining_bytes) =
+        Signature::<&[u8]>::decode(remaining_bytes).context(DecodeSigError)?;
 
-    Ok(Entry {
+    let entry = Entry {
         log_id,
         is_end_of_feed,
         payload_hash,
@@ -81,5 +98,52 @@ pub fn decode<'a>(bytes: &'a [u8]) -> Result<Entry<&'a [u8], &'a [u8]>, Error> {
         backlink,
         lipmaa_link,
         sig: Some(sig),
-    })
+    };
+
+    Ok((entry, remaining_bytes))
+}
+
+/// Iterate over the entries in `bytes`, which are encoded back to back.
+///
+/// The iterator stops after the first error.
+pub fn decode_iter(bytes: &[u8]) -> DecodeIter<'_> {
+    DecodeIter {
+        bytes,
+        is_done: false,
+    }
+}
+
+/// Iterator returned by [decode_iter].
+#[derive(Debug, Clone)]
+pub struct DecodeIter<'a> {
+    bytes: &'a [u8],
+    is_done: bool,
+}
+
+impl<'a> DecodeIter<'a> {
+    /// The bytes that have not been decoded yet.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<'a> Iterator for DecodeIter<'a> {
+    type Item = Result<EntryRef<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done || self.bytes.is_empty() {
+            return None;
+        }
+
+        match decode_with_remainder(self.bytes) {
+            Ok((entry, remaining_bytes)) => {
+                self.bytes = remaining_bytes;
+                Some(Ok(entry))
+            }
+            Err(err) => {
+                self.is_done = true;
+                Some(Err(err))
+            }
+        }
+    }
 }
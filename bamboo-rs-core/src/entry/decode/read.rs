@@ -0,0 +1,153 @@
+use ed25519_dalek::PUBLIC_KEY_LENGTH;
+use snafu::ResultExt;
+use std::io::{ErrorKind, Read};
+use varu64::decode as varu64_decode;
+
+use super::error::*;
+use super::{decode, decode_with_remainder, EntryRef};
+use crate::entry::{is_lipmaa_required, MAX_ENTRY_SIZE, TAG_BYTE_LENGTH};
+use crate::hash::HashAlgorithm;
+use crate::signature::ED25519_SIGNATURE_SIZE;
+
+/// Reads entries that are encoded back to back from a [Read], like a file or a socket.
+///
+/// Only the bytes of one entry are read at a time, so it never blocks waiting for bytes after the
+/// end of an entry.
+pub struct EntryReader<R> {
+    reader: R,
+    buf: [u8; MAX_ENTRY_SIZE],
+    len: usize,
+    is_done: bool,
+}
+
+impl<R: Read> EntryReader<R> {
+    pub fn new(reader: R) -> Self {
+        EntryReader {
+            reader,
+            buf: [0u8; MAX_ENTRY_SIZE],
+            len: 0,
+            is_done: false,
+        }
+    }
+
+    /// Read the next entry.
+    ///
+    /// Returns `Ok(None)` if the input ends before the first byte of an entry.
+    pub fn read_entry(&mut self) -> Result<Option<EntryRef<'_>>, ReadError> {
+        self.len = 0;
+
+        // Tag and author.
+        if !self.fill(TAG_BYTE_LENGTH + PUBLIC_KEY_LENGTH)? {
+            return Ok(None);
+        }
+
+        // Log id.
+        self.fill_varu64()?;
+        let seq_num = self.fill_varu64()?;
+
+        if seq_num > 1 {
+            if is_lipmaa_required(seq_num) {
+                self.fill_yasmf_hash()?;
+            }
+            self.fill_yasmf_hash()?;
+        }
+
+        // Payload size and hash.
+        self.fill_varu64()?;
+        self.fill_yasmf_hash()?;
+
+        self.fill(ED25519_SIGNATURE_SIZE)?;
+
+        decode(&self.buf[..self.len])
+            .map(Some)
+            .context(ReadEntryDecode)
+    }
+
+    /// The encoded bytes of the entry returned by the last call to
+    /// [read_entry](EntryReader::read_entry).
+    pub fn entry_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read exactly `n` more bytes. Returns false if the input ended before the start of the entry.
+    fn fill(&mut self, n: usize) -> Result<bool, ReadError> {
+        let is_start = self.len == 0;
+        let end = self.len + n;
+
+        if end > MAX_ENTRY_SIZE {
+            return Err(self.decode_error());
+        }
+
+        while self.len < end {
+            match self.reader.read(&mut self.buf[self.len..end]) {
+                Ok(0) if is_start && self.len == 0 => return Ok(false),
+                Ok(0) => return Err(ReadError::ReadEntryUnexpectedEof),
+                Ok(read) => self.len += read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err).context(ReadEntryIo),
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn fill_varu64(&mut self) -> Result<u64, ReadError> {
+        let start = self.len;
+        self.fill(1)?;
+
+        let first = self.buf[start];
+        if (first | 0b0000_0111) == 0b1111_1111 {
+            self.fill((first & 0b0000_0111) as usize + 1)?;
+        }
+
+        match varu64_decode(&self.buf[start..self.len]) {
+            Ok((value, _)) => Ok(value),
+            Err(_) => Err(self.decode_error()),
+        }
+    }
+
+    fn fill_yasmf_hash(&mut self) -> Result<(), ReadError> {
+        let hash_id = self.fill_varu64()?;
+        let algorithm = HashAlgorithm::from_id(hash_id).map_err(|_| self.decode_error())?;
+
+        // The digest length followed by the digest.
+        self.fill_varu64()?;
+        self.fill(algorithm.digest_size())?;
+        Ok(())
+    }
+
+    /// The error from decoding the bytes read so far, which end in something invalid.
+    fn decode_error(&self) -> ReadError {
+        match decode_with_remainder(&self.buf[..self.len]) {
+            Err(source) => ReadError::ReadEntryDecode { source },
+            Ok(_) => ReadError::ReadEntryUnexpectedEof,
+        }
+    }
+}
+
+/// Iterates over the encoded bytes of each entry, stopping after the first error.
+impl<R: Read> Iterator for EntryReader<R> {
+    type Item = Result<Vec<u8>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+
+        match self.read_entry() {
+            Ok(Some(_)) => Some(Ok(self.entry_bytes().to_vec())),
+            Ok(None) => {
+                self.is_done = true;
+                None
+            }
+            Err(err) => {
+                self.is_done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
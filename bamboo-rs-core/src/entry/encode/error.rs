@@ -1,6 +1,6 @@
+use crate::hash::Error as HashError;
 use crate::signature::Error as SigError;
 use snafu::Snafu;
-use yasmf_hash::error::Error as YasmfHashError;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(super)")]
@@ -8,9 +8,9 @@ pub enum Error {
     #[snafu(display("`out` buffer to encode into was length 0"))]
     EncodeBufferLength,
     #[snafu(display("Encode lipmaa link to yamf hash failed: {}", source))]
-    EncodeLipmaaError { source: YasmfHashError },
+    EncodeLipmaaError { source: HashError },
     #[snafu(display("Encode back link to yamf hash failed: {}", source))]
-    EncodeBacklinkError { source: YasmfHashError },
+    EncodeBacklinkError { source: HashError },
     #[snafu(display(
         "Lipmaa or backlinks were provided for first entry which should be impossible"
     ))]
@@ -18,7 +18,7 @@ pub enum Error {
     #[snafu(display("Encode payload size failed"))]
     EncodePayloadSizeError,
     #[snafu(display("Encode payload hash failed: {}", source))]
-    EncodePayloadHashError { source: YasmfHashError },
+    EncodePayloadHashError { source: HashError },
     #[snafu(display("Encode is_end_of_feed failed"))]
     EncodeIsEndOfFeedError,
     #[snafu(display("Encode author pub key failed"))]
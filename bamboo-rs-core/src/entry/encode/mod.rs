@@ -9,6 +9,9 @@ use varu64::{encode as varu64_encode, encoding_length as varu64_encoding_length}
 use varu64::encode_write as varu64_encode_write;
 
 use super::{Entry, TAG_BYTE_LENGTH};
+#[cfg(feature = "std")]
+use crate::hash::encode_hash_write;
+use crate::hash::{encode_hash, HashAlgorithm};
 pub mod error;
 pub use error::*;
 
@@ -57,17 +60,14 @@ where
         // Encode the backlink and lipmaa links if its not the first sequence
         next_byte_num = match (self.seq_num, &self.backlink, &self.lipmaa_link) {
             (n, Some(ref backlink), Some(ref lipmaa_link)) if n > 1 => {
-                next_byte_num += lipmaa_link
-                    .encode(&mut out[next_byte_num..])
+                next_byte_num += encode_hash(lipmaa_link, &mut out[next_byte_num..])
                     .context(EncodeLipmaaError)?;
-                next_byte_num += backlink
-                    .encode(&mut out[next_byte_num..])
+                next_byte_num += encode_hash(backlink, &mut out[next_byte_num..])
                     .context(EncodeBacklinkError)?;
                 Ok(next_byte_num)
             }
             (n, Some(ref backlink), None) if n > 1 => {
-                next_byte_num += backlink
-                    .encode(&mut out[next_byte_num..])
+                next_byte_num += encode_hash(backlink, &mut out[next_byte_num..])
                     .context(EncodeBacklinkError)?;
                 Ok(next_byte_num)
             }
@@ -79,9 +79,7 @@ where
         next_byte_num += varu64_encode(self.payload_size, &mut out[next_byte_num..]);
 
         // Encode the payload hash
-        next_byte_num += self
-            .payload_hash
-            .encode(&mut out[next_byte_num..])
+        next_byte_num += encode_hash(&self.payload_hash, &mut out[next_byte_num..])
             .context(EncodePayloadHashError)?;
 
         Ok(next_byte_num as usize)
@@ -114,14 +112,12 @@ where
         // Encode the backlink and lipmaa links if its not the first sequence
         match (self.seq_num, &self.backlink, &self.lipmaa_link) {
             (n, Some(ref backlink), Some(ref lipmaa_link)) if n > 1 => {
-                lipmaa_link
-                    .encode_write(&mut w)
-                    .context(EncodeLipmaaError)?;
+                encode_hash_write(lipmaa_link, &mut w).context(EncodeLipmaaError)?;
 
-                backlink.encode_write(&mut w).context(EncodeBacklinkError)
+                encode_hash_write(backlink, &mut w).context(EncodeBacklinkError)
             }
             (n, Some(ref backlink), None) if n > 1 => {
-                backlink.encode_write(&mut w).context(EncodeBacklinkError)
+                encode_hash_write(backlink, &mut w).context(EncodeBacklinkError)
             }
             (n, Some(_), Some(_)) if n <= 1 => Err(Error::EncodeEntryHasLinksWhenSeqZero),
             (n, None, Some(_)) if n <= 1 => Err(Error::EncodeEntryHasLinksWhenSeqZero),
@@ -134,9 +130,7 @@ where
             .map_err(|_| Error::EncodePayloadSizeError)?;
 
         // Encode the payload hash
-        self.payload_hash
-            .encode_write(&mut w)
-            .context(EncodePayloadHashError)?;
+        encode_hash_write(&self.payload_hash, &mut w).context(EncodePayloadHashError)?;
 
         Ok(())
     }
@@ -155,7 +149,7 @@ where
 
     pub fn encoding_length(&self) -> usize {
         TAG_BYTE_LENGTH
-            + self.payload_hash.encoding_length()
+            + HashAlgorithm::of(&self.payload_hash).encoding_length()
             + varu64_encoding_length(self.payload_size)
             + varu64_encoding_length(self.log_id)
             + self.author.as_bytes().len()
@@ -163,12 +157,12 @@ where
             + self
                 .backlink
                 .as_ref()
-                .map(|backlink| backlink.encoding_length())
+                .map(|backlink| HashAlgorithm::of(backlink).encoding_length())
                 .unwrap_or(0)
             + self
                 .lipmaa_link
                 .as_ref()
-                .map(|lipmaa_link| lipmaa_link.encoding_length())
+                .map(|lipmaa_link| HashAlgorithm::of(lipmaa_link).encoding_length())
                 .unwrap_or(0)
             + self
                 .sig
@@ -5,26 +5,37 @@ use lipmaa_link::lipmaa;
 
 pub mod decode;
 pub mod encode;
+pub mod owned;
 pub mod publish;
+pub mod text;
 pub mod verify;
 
-pub use decode::decode;
+pub use decode::{decode, decode_iter, decode_with_remainder};
+pub use owned::OwnedEntry;
 pub use publish::publish;
-pub use verify::verify;
+pub use verify::{verify, verify_with_policy, VerifyPolicy};
 
-#[cfg(feature = "std")]
-pub use verify::verify_batch;
+#[cfg(feature = "alloc")]
+pub use publish::{publish_batch, PublishState};
+#[cfg(feature = "alloc")]
+pub use verify::{
+    verify_batch, verify_batch_iter, verify_batch_iter_with_policy,
+    verify_batch_with_linked_entries, verify_batch_with_policy,
+};
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 use crate::util::hex_serde::*;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
 use serde::{Deserialize, Serialize};
 
 use ed25519_dalek::PublicKey as DalekPublicKey;
 
+use super::hash::{to_owned_hash, MAX_HASH_DIGEST_SIZE, MAX_HASH_ENCODING_SIZE};
 use super::signature::{Signature, MAX_SIGNATURE_SIZE};
-use super::yasmf_hash::{YasmfHash, MAX_YAMF_HASH_SIZE};
+use super::yasmf_hash::YasmfHash;
 
 pub use ed25519_dalek::PUBLIC_KEY_LENGTH;
 pub const TAG_BYTE_LENGTH: usize = 1;
@@ -32,7 +43,7 @@ pub const MAX_VARU64_SIZE: usize = 9;
 pub const MAX_ENTRY_SIZE_: usize = TAG_BYTE_LENGTH
     + MAX_SIGNATURE_SIZE
     + PUBLIC_KEY_LENGTH
-    + (MAX_YAMF_HASH_SIZE * 3)
+    + (MAX_HASH_ENCODING_SIZE * 3)
     + (MAX_VARU64_SIZE * 3);
 
 /// This is useful if you need to know at compile time how big an entry can get.
@@ -43,8 +54,8 @@ pub const MAX_ENTRY_SIZE: usize = 226;
 // that the value is actually correct.
 const_assert_eq!(max_entry_size; MAX_ENTRY_SIZE_ as isize, MAX_ENTRY_SIZE as isize);
 
-#[cfg_attr(feature = "std", derive(Deserialize, Serialize))]
-#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "alloc", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "alloc", serde(rename_all = "camelCase"))]
 #[derive(Debug, Eq, PartialEq, Clone)]
 #[repr(C)]
 pub struct Entry<H, S>
@@ -54,11 +65,14 @@ where
 {
     pub log_id: u64,
     pub is_end_of_feed: bool,
-    #[cfg_attr(feature = "std", serde(bound(deserialize = "H: From<Vec<u8>>")))]
+    #[cfg_attr(
+        feature = "alloc",
+        serde(with = "YasmfHashDef", bound(deserialize = "H: From<Vec<u8>>"))
+    )]
     pub payload_hash: YasmfHash<H>,
     pub payload_size: u64,
     #[cfg_attr(
-        feature = "std",
+        feature = "alloc",
         serde(
             serialize_with = "serialize_pub_key",
             deserialize_with = "deserialize_pub_key"
@@ -66,9 +80,25 @@ where
     )]
     pub author: DalekPublicKey,
     pub seq_num: u64,
+    #[cfg_attr(
+        feature = "alloc",
+        serde(
+            default,
+            serialize_with = "serialize_option_yasmf_hash",
+            deserialize_with = "deserialize_option_yasmf_hash"
+        )
+    )]
     pub backlink: Option<YasmfHash<H>>,
+    #[cfg_attr(
+        feature = "alloc",
+        serde(
+            default,
+            serialize_with = "serialize_option_yasmf_hash",
+            deserialize_with = "deserialize_option_yasmf_hash"
+        )
+    )]
     pub lipmaa_link: Option<YasmfHash<H>>,
-    #[cfg_attr(feature = "std", serde(bound(deserialize = "S: From<Vec<u8>>")))]
+    #[cfg_attr(feature = "alloc", serde(bound(deserialize = "S: From<Vec<u8>>")))]
     pub sig: Option<Signature<S>>,
 }
 
@@ -91,14 +121,15 @@ where
         let mut buff = [0u8; 512];
         let len = entry.encode(&mut buff)?;
         let mut vec = ArrayVec::<[u8; 512]>::from(buff);
-        unsafe {
-            vec.set_len(len);
-        }
+        vec.truncate(len);
         Ok(vec)
     }
 }
 
-pub fn into_owned<H, S>(entry: &Entry<H, S>) -> Entry<ArrayVec<[u8; 32]>, ArrayVec<[u8; 64]>>
+#[deprecated(note = "use `OwnedEntry::try_from` instead")]
+pub fn into_owned<H, S>(
+    entry: &Entry<H, S>,
+) -> Entry<ArrayVec<[u8; MAX_HASH_DIGEST_SIZE]>, ArrayVec<[u8; 64]>>
 where
     H: Borrow<[u8]> + Clone,
     S: Borrow<[u8]> + Clone,
@@ -112,31 +143,9 @@ where
         None => None,
     };
 
-    let payload_hash = match entry.payload_hash {
-        YasmfHash::Blake3(ref s) => {
-            let mut vec = ArrayVec::<[u8; 32]>::new();
-            vec.try_extend_from_slice(&s.borrow()[..]).unwrap();
-            YasmfHash::Blake3(vec)
-        }
-    };
-
-    let backlink = match entry.backlink {
-        Some(YasmfHash::Blake3(ref s)) => {
-            let mut vec = ArrayVec::<[u8; 32]>::new();
-            vec.try_extend_from_slice(&s.borrow()[..]).unwrap();
-            Some(YasmfHash::Blake3(vec))
-        }
-        None => None,
-    };
-
-    let lipmaa_link = match entry.lipmaa_link {
-        Some(YasmfHash::Blake3(ref s)) => {
-            let mut vec = ArrayVec::<[u8; 32]>::new();
-            vec.try_extend_from_slice(&s.borrow()[..]).unwrap();
-            Some(YasmfHash::Blake3(vec))
-        }
-        None => None,
-    };
+    let payload_hash = to_owned_hash(&entry.payload_hash);
+    let backlink = entry.backlink.as_ref().map(to_owned_hash);
+    let lipmaa_link = entry.lipmaa_link.as_ref().map(to_owned_hash);
 
     Entry {
         is_end_of_feed: entry.is_end_of_feed,
@@ -154,3 +163,36 @@ where
 pub fn is_lipmaa_required(sequence_num: u64) -> bool {
     lipmaa(sequence_num) != sequence_num - 1
 }
+
+/// Could an entry with a seq_num larger than `last_seq_num` have `seq_num` as its backlink or
+/// lipmaa link?
+///
+/// Use this to work out which entries must be kept around to publish or verify the entries that
+/// come after `last_seq_num`. There are only O(log n) of them.
+pub fn is_linked_after(seq_num: u64, last_seq_num: u64) -> bool {
+    if seq_num == last_seq_num {
+        return true;
+    }
+
+    // A lipmaa link always jumps back by either (3^k - 1) / 2 or 3^k.
+    let mut jump: u64 = 1;
+    let mut po3: u64 = 3;
+    loop {
+        let is_linked = |jump: u64| match seq_num.checked_add(jump) {
+            Some(n) => n > last_seq_num && lipmaa(n) == seq_num,
+            None => false,
+        };
+
+        if is_linked(jump) || is_linked(po3) {
+            return true;
+        }
+
+        match (jump.checked_mul(3), po3.checked_mul(3)) {
+            (Some(next_jump), Some(next_po3)) => {
+                jump = next_jump + 1;
+                po3 = next_po3;
+            }
+            _ => return false,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::entry::decode::Error as DecodeError;
+use crate::entry::encode::Error as EncodeError;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Entry {} has the wrong length for its hash algorithm", field))]
+    OwnedEntryInvalidHashLength { field: &'static str },
+    #[snafu(display("Entry signature has the wrong length"))]
+    OwnedEntryInvalidSigLength,
+    #[snafu(display("Entry is not signed"))]
+    OwnedEntryMissingSig,
+    #[snafu(display("Could not encode entry: {}", source))]
+    EncodeOwnedEntry { source: EncodeError },
+    #[snafu(display("Could not decode entry: {}", source))]
+    DecodeOwnedEntry { source: DecodeError },
+    #[snafu(display("Bytes continued after the end of the entry"))]
+    OwnedEntryTrailingBytes,
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,259 @@
+//! An entry that owns its bytes, without needing an allocator.
+use arrayvec::ArrayVec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use snafu::{ensure, OptionExt, ResultExt};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::fmt;
+#[cfg(feature = "alloc")]
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+#[cfg(feature = "alloc")]
+use serde::{Serialize, Serializer};
+
+use super::{decode_with_remainder, Entry, MAX_ENTRY_SIZE};
+use crate::hash::{digest, to_owned_hash, HashAlgorithm, OwnedYasmfHash, MAX_HASH_DIGEST_SIZE};
+use crate::signature::{Signature, ED25519_SIGNATURE_SIZE};
+use crate::yasmf_hash::YasmfHash;
+
+pub mod error;
+pub use error::*;
+
+type OwnedFields =
+    Entry<ArrayVec<[u8; MAX_HASH_DIGEST_SIZE]>, ArrayVec<[u8; ED25519_SIGNATURE_SIZE]>>;
+
+/// A signed [Entry] that owns its hashes, signature and encoded bytes.
+///
+/// The fields are available through [Deref]. Encoding can't fail, so [OwnedEntry::as_bytes] and
+/// [OwnedEntry::to_bytes] don't return a `Result`.
+///
+/// Entries are hashed and ordered by `(author, log_id, seq_num)`. Forks with the same key are
+/// ordered by their bytes.
+///
+/// With the `alloc` feature entries serialize as the same fields as [Entry] for human readable
+/// formats like JSON, and as their encoded bytes for binary formats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedEntry {
+    entry: OwnedFields,
+    bytes: ArrayVec<[u8; MAX_ENTRY_SIZE]>,
+}
+
+impl OwnedEntry {
+    /// Decode `bytes`, which must be exactly one entry.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (entry, remainder) = decode_with_remainder(bytes).context(DecodeOwnedEntry)?;
+        ensure!(remainder.is_empty(), OwnedEntryTrailingBytes);
+
+        OwnedEntry::try_from(&entry)
+    }
+
+    /// The encoded entry.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Copy the encoded entry.
+    pub fn to_bytes(&self) -> ArrayVec<[u8; MAX_ENTRY_SIZE]> {
+        self.bytes.clone()
+    }
+
+    /// Copy the encoded entry into a [Vec].
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.bytes.to_vec()
+    }
+
+    /// The hash of the encoded entry, as used by the entries that link to it.
+    pub fn entry_hash(&self) -> OwnedYasmfHash {
+        HashAlgorithm::default().hash(&self.bytes)
+    }
+
+    pub fn into_entry(self) -> OwnedFields {
+        self.entry
+    }
+}
+
+impl Deref for OwnedEntry {
+    type Target = OwnedFields;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entry
+    }
+}
+
+impl<'a, H, S> TryFrom<&'a Entry<H, S>> for OwnedEntry
+where
+    H: Borrow<[u8]> + Clone,
+    S: Borrow<[u8]> + Clone,
+{
+    type Error = Error;
+
+    fn try_from(entry: &'a Entry<H, S>) -> Result<Self> {
+        let sig = entry.sig.as_ref().context(OwnedEntryMissingSig)?;
+        ensure!(
+            sig.len() == ED25519_SIGNATURE_SIZE,
+            OwnedEntryInvalidSigLength
+        );
+        let mut sig_bytes = ArrayVec::new();
+        sig_bytes
+            .try_extend_from_slice(sig.0.borrow())
+            .map_err(|_| Error::OwnedEntryInvalidSigLength)?;
+
+        let entry = Entry {
+            log_id: entry.log_id,
+            is_end_of_feed: entry.is_end_of_feed,
+            payload_hash: owned_hash(&entry.payload_hash, "payload_hash")?,
+            payload_size: entry.payload_size,
+            author: entry.author,
+            seq_num: entry.seq_num,
+            backlink: entry
+                .backlink
+                .as_ref()
+                .map(|hash| owned_hash(hash, "backlink"))
+                .transpose()?,
+            lipmaa_link: entry
+                .lipmaa_link
+                .as_ref()
+                .map(|hash| owned_hash(hash, "lipmaa_link"))
+                .transpose()?,
+            sig: Some(Signature(sig_bytes)),
+        };
+
+        let mut buf = [0u8; MAX_ENTRY_SIZE];
+        let len = entry.encode(&mut buf).context(EncodeOwnedEntry)?;
+        let mut bytes = ArrayVec::from(buf);
+        bytes.truncate(len);
+
+        Ok(OwnedEntry { entry, bytes })
+    }
+}
+
+impl<H, S> TryFrom<Entry<H, S>> for OwnedEntry
+where
+    H: Borrow<[u8]> + Clone,
+    S: Borrow<[u8]> + Clone,
+{
+    type Error = Error;
+
+    fn try_from(entry: Entry<H, S>) -> Result<Self> {
+        OwnedEntry::try_from(&entry)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for OwnedEntry {
+    type Error = Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        OwnedEntry::from_bytes(bytes)
+    }
+}
+
+impl From<OwnedEntry> for OwnedFields {
+    fn from(entry: OwnedEntry) -> Self {
+        entry.entry
+    }
+}
+
+impl AsRef<[u8]> for OwnedEntry {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Hash for OwnedEntry {
+    fn hash<HS: Hasher>(&self, state: &mut HS) {
+        self.entry.author.as_bytes().hash(state);
+        self.entry.log_id.hash(state);
+        self.entry.seq_num.hash(state);
+    }
+}
+
+impl PartialOrd for OwnedEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OwnedEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |entry: &OwnedEntry| {
+            (
+                entry.entry.author.to_bytes(),
+                entry.entry.log_id,
+                entry.entry.seq_num,
+            )
+        };
+
+        key(self)
+            .cmp(&key(other))
+            .then_with(|| self.bytes.cmp(&other.bytes))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Serialize for OwnedEntry {
+    fn serialize<SE: Serializer>(&self, serializer: SE) -> core::result::Result<SE::Ok, SE::Error> {
+        if serializer.is_human_readable() {
+            self.entry.serialize(serializer)
+        } else {
+            serializer.serialize_bytes(&self.bytes)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for OwnedEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let entry = Entry::<Vec<u8>, Vec<u8>>::deserialize(deserializer)?;
+            OwnedEntry::try_from(&entry).map_err(de::Error::custom)
+        } else {
+            deserializer.deserialize_bytes(OwnedEntryVisitor)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+struct OwnedEntryVisitor;
+
+#[cfg(feature = "alloc")]
+impl<'de> Visitor<'de> for OwnedEntryVisitor {
+    type Value = OwnedEntry;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the bytes of a bamboo entry")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> core::result::Result<OwnedEntry, E> {
+        OwnedEntry::from_bytes(bytes).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> core::result::Result<OwnedEntry, A::Error> {
+        let mut bytes = ArrayVec::<[u8; MAX_ENTRY_SIZE]>::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes
+                .try_push(byte)
+                .map_err(|_| de::Error::invalid_length(MAX_ENTRY_SIZE + 1, &self))?;
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+fn owned_hash<T: Borrow<[u8]> + Clone>(
+    hash: &YasmfHash<T>,
+    field: &'static str,
+) -> Result<OwnedYasmfHash> {
+    ensure!(
+        digest(hash).len() == HashAlgorithm::of(hash).digest_size(),
+        OwnedEntryInvalidHashLength { field }
+    );
+    Ok(to_owned_hash(hash))
+}
@@ -0,0 +1,147 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use ed25519_dalek::Keypair;
+use lipmaa_link::lipmaa;
+use snafu::ResultExt;
+
+#[cfg(feature = "std")]
+use rayon::prelude::*;
+
+use super::error::*;
+use super::publish_with_payload_hash;
+use crate::entry::{decode, is_linked_after, MAX_ENTRY_SIZE};
+use crate::yasmf_hash::new_blake3;
+
+/// The previously published entries of a log that later entries may still link to.
+///
+/// Only O(log n) entries are kept, see [is_linked_after].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishState {
+    ancestors: BTreeMap<u64, Vec<u8>>,
+}
+
+impl PublishState {
+    /// State for a log with no entries published yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the state for an existing log from its previously published entries.
+    ///
+    /// `entries` must include the latest entry and any entries it or later entries link to, but
+    /// it's fine to pass the whole log. Entries that are no longer needed are dropped.
+    pub fn from_entries<I, E>(entries: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = E>,
+        E: AsRef<[u8]>,
+    {
+        let mut state = Self::new();
+
+        for bytes in entries {
+            let bytes = bytes.as_ref();
+            let seq_num = decode(bytes).context(DecodePreviousEntry)?.seq_num;
+            state.ancestors.insert(seq_num, bytes.to_vec());
+        }
+
+        if let Some(last_seq_num) = state.last_seq_num() {
+            state.retain_linked_after(last_seq_num);
+        }
+
+        Ok(state)
+    }
+
+    /// The seq_num of the latest published entry, `None` if the log is empty.
+    pub fn last_seq_num(&self) -> Option<u64> {
+        self.ancestors.keys().next_back().copied()
+    }
+
+    /// The encoded entry with `seq_num`, if it is still held.
+    pub fn get(&self, seq_num: u64) -> Option<&[u8]> {
+        self.ancestors.get(&seq_num).map(|bytes| &bytes[..])
+    }
+
+    /// The number of entries held.
+    pub fn len(&self) -> usize {
+        self.ancestors.len()
+    }
+
+    /// True when no entries are held.
+    pub fn is_empty(&self) -> bool {
+        self.ancestors.is_empty()
+    }
+
+    fn push(&mut self, seq_num: u64, bytes: Vec<u8>) {
+        self.ancestors.insert(seq_num, bytes);
+        self.retain_linked_after(seq_num);
+    }
+
+    fn retain_linked_after(&mut self, last_seq_num: u64) {
+        self.ancestors
+            .retain(|seq_num, _| is_linked_after(*seq_num, last_seq_num));
+    }
+}
+
+/// Publish an entry for each of `payloads`, returning the encoded entries in order.
+///
+/// - `key_pair`: The ed25519 cryptographic key pair used to sign the entries.
+/// - `log_id`: The integer that distinguishes different logs by the same author.
+/// - `state`: The state of the log to continue from. `None` starts a new log. When provided it
+///   is updated to include the new entries, but only if every entry was published.
+/// - `payloads`: The payloads of the entries. It's up to the caller to store them somewhere.
+///
+/// None of the entries are published as the end of the feed. Payloads are hashed in parallel with
+/// the `std` feature.
+pub fn publish_batch<I, P>(
+    key_pair: &Keypair,
+    log_id: u64,
+    state: Option<&mut PublishState>,
+    payloads: I,
+) -> Result<Vec<Vec<u8>>>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<[u8]> + Sync,
+{
+    let payloads = payloads.into_iter().collect::<Vec<_>>();
+
+    #[cfg(feature = "std")]
+    let payloads_iter = payloads.par_iter();
+    #[cfg(not(feature = "std"))]
+    let payloads_iter = payloads.iter();
+
+    let payload_hashes_and_sizes = payloads_iter
+        .map(|payload| {
+            let payload = payload.as_ref();
+            (new_blake3(payload), payload.len() as u64)
+        })
+        .collect::<Vec<_>>();
+
+    let mut next_state = state.as_deref().cloned().unwrap_or_default();
+    let mut entries = Vec::with_capacity(payload_hashes_and_sizes.len());
+
+    for (payload_hash, payload_size) in payload_hashes_and_sizes {
+        let previous_seq_num = next_state.last_seq_num();
+        let seq_num = previous_seq_num.unwrap_or(0) + 1;
+
+        let mut out = [0u8; MAX_ENTRY_SIZE];
+        let size = publish_with_payload_hash(
+            &mut out,
+            key_pair,
+            log_id,
+            payload_hash,
+            payload_size,
+            false,
+            previous_seq_num,
+            next_state.get(lipmaa(seq_num)),
+            previous_seq_num.and_then(|previous| next_state.get(previous)),
+        )?;
+
+        next_state.push(seq_num, out[..size].to_vec());
+        entries.push(out[..size].to_vec());
+    }
+
+    if let Some(state) = state {
+        *state = next_state;
+    }
+
+    Ok(entries)
+}
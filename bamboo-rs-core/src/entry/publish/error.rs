@@ -27,6 +27,19 @@ pub enum Error {
     DecodeBacklinkEntry { source: EntryDecodeError },
     #[snafu(display("Failed to decode lipmaa link, encoding error: {}", source))]
     DecodeLipmaaEntry { source: EntryDecodeError },
+    #[snafu(display(
+        "Failed to decode a previously published entry, encoding error: {}",
+        source
+    ))]
+    DecodePreviousEntry { source: EntryDecodeError },
+    #[snafu(display(
+        "Previously published entries are not in ascending seq_num order, {} came after {}",
+        actual,
+        previous
+    ))]
+    PreviousEntriesNotInOrder { actual: u64, previous: u64 },
+    #[snafu(display("Publisher has no room left to keep the entries it links to"))]
+    PublisherLinksFull,
     #[snafu(display(
         "Could not encode the entry into the out buffer. Buffer len: {}, Encoding error: {}",
         buffer_size,
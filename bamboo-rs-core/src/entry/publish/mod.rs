@@ -2,13 +2,20 @@ pub use crate::BLAKE3_HASH_SIZE;
 
 use super::decode::decode;
 use super::{is_lipmaa_required, Entry};
-use crate::signature::Signature;
-use crate::yasmf_hash::new_blake3;
+use crate::signature::{Signature, ED25519_SIGNATURE_SIZE};
+use crate::yasmf_hash::{new_blake3, YasmfHash};
+use arrayvec::ArrayVec;
 use ed25519_dalek::{Keypair, Signer};
 use snafu::{ensure, ResultExt};
 
+#[cfg(feature = "alloc")]
+pub mod batch;
 pub mod error;
+pub mod publisher;
+#[cfg(feature = "alloc")]
+pub use batch::{publish_batch, PublishState};
 pub use error::*;
+pub use publisher::{Publisher, PublisherState, MAX_LINKED_ENTRIES};
 
 /// Publish a new entry into the `out` buffer.
 ///
@@ -32,15 +39,37 @@ pub fn publish(
     lipmaa_entry_bytes: Option<&[u8]>,
     backlink_bytes: Option<&[u8]>,
 ) -> Result<usize, Error> {
-    let author = key_pair.public;
+    publish_with_payload_hash(
+        out,
+        key_pair,
+        log_id,
+        new_blake3(payload),
+        payload.len() as u64,
+        is_end_of_feed,
+        previous_seq_num,
+        lipmaa_entry_bytes,
+        backlink_bytes,
+    )
+}
 
-    // calc the payload hash
-    let payload_hash = new_blake3(payload);
-    let payload_size = payload.len() as u64;
+/// Same as [publish] but with the payload already hashed.
+#[allow(clippy::too_many_arguments)]
+fn publish_with_payload_hash(
+    out: &mut [u8],
+    key_pair: &Keypair,
+    log_id: u64,
+    payload_hash: YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>,
+    payload_size: u64,
+    is_end_of_feed: bool,
+    previous_seq_num: Option<u64>,
+    lipmaa_entry_bytes: Option<&[u8]>,
+    backlink_bytes: Option<&[u8]>,
+) -> Result<usize, Error> {
+    let author = key_pair.public;
 
     let seq_num = previous_seq_num.unwrap_or(0) + 1;
 
-    let mut entry: Entry<_, &[u8]> = Entry {
+    let mut entry = Entry {
         log_id,
         is_end_of_feed,
         payload_hash,
@@ -98,13 +127,21 @@ pub fn publish(
         }
     }
 
+    sign_and_encode(out, key_pair, entry)
+}
+
+/// Sign `entry` with `key_pair` and encode it into `out`.
+fn sign_and_encode(
+    out: &mut [u8],
+    key_pair: &Keypair,
+    mut entry: Entry<ArrayVec<[u8; BLAKE3_HASH_SIZE]>, ArrayVec<[u8; ED25519_SIGNATURE_SIZE]>>,
+) -> Result<usize, Error> {
     let buff_size = entry.encode(out).context(EncodeEntryToOutBuffer {
         buffer_size: out.len(),
     })?;
 
     let signature = key_pair.sign(&out[..buff_size]);
-    let sig_bytes = &signature.to_bytes()[..];
-    let signature = Signature(sig_bytes.into());
+    let signature = Signature(ArrayVec::from(signature.to_bytes()));
 
     entry.sig = Some(signature);
 
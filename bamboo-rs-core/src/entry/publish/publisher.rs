@@ -0,0 +1,224 @@
+use arrayvec::ArrayVec;
+use ed25519_dalek::{Keypair, PublicKey, PUBLIC_KEY_LENGTH};
+use lipmaa_link::lipmaa;
+use snafu::{ensure, OptionExt, ResultExt};
+
+use super::error::*;
+use super::sign_and_encode;
+use crate::entry::{decode, is_linked_after, is_lipmaa_required, Entry};
+use crate::yasmf_hash::{blake3, new_blake3, YasmfHash};
+use crate::BLAKE3_HASH_SIZE;
+
+/// The most entries a [Publisher] ever needs to keep the hash of.
+///
+/// Entries that can still be linked to grow with log3 of the seq_num, which is about 41 for the
+/// largest possible seq_num.
+pub const MAX_LINKED_ENTRIES: usize = 48;
+
+/// Everything a [Publisher] needs to continue a log, apart from the key pair.
+///
+/// Uses fixed capacity storage, so it can be serialized with any serde format (even without
+/// `alloc`) and restored with [Publisher::from_state].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublisherState {
+    author: [u8; PUBLIC_KEY_LENGTH],
+    log_id: u64,
+    last_seq_num: u64,
+    is_end_of_feed: bool,
+    links: ArrayVec<[(u64, [u8; BLAKE3_HASH_SIZE]); MAX_LINKED_ENTRIES]>,
+}
+
+impl PublisherState {
+    fn new(author: &PublicKey, log_id: u64) -> Self {
+        PublisherState {
+            author: author.to_bytes(),
+            log_id,
+            last_seq_num: 0,
+            is_end_of_feed: false,
+            links: ArrayVec::new(),
+        }
+    }
+
+    pub fn log_id(&self) -> u64 {
+        self.log_id
+    }
+
+    /// The seq_num of the latest published entry, `None` if the log is empty.
+    pub fn last_seq_num(&self) -> Option<u64> {
+        Some(self.last_seq_num).filter(|seq_num| *seq_num > 0)
+    }
+
+    /// Has the latest published entry ended the feed?
+    pub fn is_end_of_feed(&self) -> bool {
+        self.is_end_of_feed
+    }
+
+    /// The hash of the entry with `seq_num`, if it is still held.
+    pub fn link(&self, seq_num: u64) -> Option<YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>> {
+        self.links
+            .iter()
+            .find(|(link_seq_num, _)| *link_seq_num == seq_num)
+            .map(|(_, hash)| YasmfHash::Blake3(ArrayVec::from(*hash)))
+    }
+
+    fn push(&mut self, seq_num: u64, entry_bytes: &[u8], is_end_of_feed: bool) -> Result<()> {
+        self.links
+            .retain(|(link_seq_num, _)| is_linked_after(*link_seq_num, seq_num));
+
+        self.links
+            .try_push((seq_num, *blake3(entry_bytes).as_bytes()))
+            .ok()
+            .context(PublisherLinksFull)?;
+
+        self.last_seq_num = seq_num;
+        self.is_end_of_feed = is_end_of_feed;
+
+        Ok(())
+    }
+}
+
+/// Publishes entries to a single log, keeping only the hashes of the O(log n) entries that future
+/// entries can link to.
+///
+/// Works without `alloc`.
+///
+/// ```
+/// use bamboo_rs_core_ed25519_yasmf::entry::publish::Publisher;
+/// use bamboo_rs_core_ed25519_yasmf::entry::MAX_ENTRY_SIZE;
+/// use bamboo_rs_core_ed25519_yasmf::{verify, Keypair};
+/// use rand::rngs::OsRng;
+///
+/// let key_pair = Keypair::generate(&mut OsRng {});
+/// let mut publisher = Publisher::new(key_pair, 0);
+///
+/// let mut out = [0u8; MAX_ENTRY_SIZE];
+/// let size = publisher.publish(&mut out, b"hello bamboo!", false).unwrap();
+/// assert!(verify(&out[..size], Some(b"hello bamboo!"), None, None).is_ok());
+///
+/// // Store the state somewhere and carry on later.
+/// let state = publisher.state().clone();
+/// let (key_pair, _) = publisher.into_parts();
+/// let mut publisher = Publisher::from_state(key_pair, state).unwrap();
+/// publisher.publish(&mut out, b"hello again!", false).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Publisher {
+    key_pair: Keypair,
+    state: PublisherState,
+}
+
+impl Publisher {
+    /// Create a publisher for a new log.
+    pub fn new(key_pair: Keypair, log_id: u64) -> Self {
+        let state = PublisherState::new(&key_pair.public, log_id);
+        Publisher { key_pair, state }
+    }
+
+    /// Restore a publisher from a previously saved [PublisherState].
+    pub fn from_state(key_pair: Keypair, state: PublisherState) -> Result<Self> {
+        ensure!(
+            key_pair.public.to_bytes() == state.author,
+            PublishKeypairDidNotMatchBacklinkPublicKey
+        );
+
+        Ok(Publisher { key_pair, state })
+    }
+
+    /// Create a publisher for an existing log from its previously published entries.
+    ///
+    /// `entries` must be in strictly ascending seq_num order and include the latest entry and any entries
+    /// it or later entries link to, but it's fine to pass the whole log.
+    pub fn from_entries<I, E>(key_pair: Keypair, log_id: u64, entries: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = E>,
+        E: AsRef<[u8]>,
+    {
+        let mut state = PublisherState::new(&key_pair.public, log_id);
+
+        for bytes in entries {
+            let bytes = bytes.as_ref();
+            let entry = decode(bytes).context(DecodePreviousEntry)?;
+
+            ensure!(entry.log_id == log_id, PublishWithIncorrectBacklinkLogId);
+            ensure!(
+                entry.author == key_pair.public,
+                PublishKeypairDidNotMatchBacklinkPublicKey
+            );
+            ensure!(
+                entry.seq_num > state.last_seq_num,
+                PreviousEntriesNotInOrder {
+                    actual: entry.seq_num,
+                    previous: state.last_seq_num
+                }
+            );
+
+            state.push(entry.seq_num, bytes, entry.is_end_of_feed)?;
+        }
+
+        Ok(Publisher { key_pair, state })
+    }
+
+    /// Publish a new entry into the `out` buffer.
+    ///
+    /// - `out`: A buffer to encode the entry into. Must be >= MAX_ENTRY_SIZE.
+    /// - `payload`: The payload of the entry. It's up to the caller to store it somewhere.
+    /// - `is_end_of_feed`: Is this entry the final entry for this log?
+    ///
+    /// Returns a `Result` of the size of the entry encoded into `out`.
+    pub fn publish(
+        &mut self,
+        out: &mut [u8],
+        payload: &[u8],
+        is_end_of_feed: bool,
+    ) -> Result<usize> {
+        ensure!(!self.state.is_end_of_feed, PublishAfterEndOfFeed);
+
+        let seq_num = self.state.last_seq_num + 1;
+
+        let mut entry = Entry {
+            log_id: self.state.log_id,
+            is_end_of_feed,
+            payload_hash: new_blake3(payload),
+            payload_size: payload.len() as u64,
+            author: self.key_pair.public,
+            seq_num,
+            backlink: None,
+            lipmaa_link: None,
+            sig: None,
+        };
+
+        if seq_num > 1 {
+            entry.backlink = Some(
+                self.state
+                    .link(seq_num - 1)
+                    .context(PublishWithoutBacklinkEntry)?,
+            );
+
+            if is_lipmaa_required(seq_num) {
+                entry.lipmaa_link = Some(
+                    self.state
+                        .link(lipmaa(seq_num))
+                        .context(PublishWithoutLipmaaEntry)?,
+                );
+            }
+        }
+
+        let size = sign_and_encode(out, &self.key_pair, entry)?;
+        self.state.push(seq_num, &out[..size], is_end_of_feed)?;
+
+        Ok(size)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.key_pair.public
+    }
+
+    /// The state to save so publishing can continue later with [Publisher::from_state].
+    pub fn state(&self) -> &PublisherState {
+        &self.state
+    }
+
+    pub fn into_parts(self) -> (Keypair, PublisherState) {
+        (self.key_pair, self.state)
+    }
+}
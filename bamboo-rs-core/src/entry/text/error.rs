@@ -0,0 +1,18 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Entry text is missing the `{}` line", field))]
+    TextMissingField { field: &'static str },
+    #[snafu(display("Entry text has an invalid value for `{}`", field))]
+    TextInvalidValue { field: &'static str },
+    #[snafu(display("Entry text has an unexpected line after the signature"))]
+    TextUnexpectedLine,
+    #[snafu(display("Entry text has a seq_num of 0"))]
+    TextSeqIsZero,
+    #[snafu(display("Entry text links do not match what seq_num {} requires", seq_num))]
+    TextInvalidLinks { seq_num: u64 },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
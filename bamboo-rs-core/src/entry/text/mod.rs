@@ -0,0 +1,221 @@
+//! A canonical, human readable text format for entries.
+//!
+//! Each field is on its own line, in the order they are encoded. Keys and signatures are hex,
+//! hashes are the hex of their yasmf encoding and missing links or signatures are `none`:
+//!
+//! ```text
+//! is_end_of_feed: false
+//! author: 4f0c…
+//! log_id: 0
+//! seq_num: 4
+//! lipmaa_link: 0020…
+//! backlink: 0020…
+//! payload_size: 5
+//! payload_hash: 0020…
+//! sig: 8a1b…
+//! ```
+//!
+//! [Entry::from_text] only accepts exactly what [Entry::to_text] writes, so encoding a parsed entry
+//! gives back the same bytes.
+use arrayvec::ArrayVec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::str::Lines;
+use ed25519_dalek::{PublicKey, PUBLIC_KEY_LENGTH};
+use snafu::ensure;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+use super::{is_lipmaa_required, Entry};
+use crate::hash::{
+    decode_hash_owned, encode_hash, OwnedYasmfHash, MAX_HASH_DIGEST_SIZE, MAX_HASH_ENCODING_SIZE,
+};
+use crate::signature::{Signature, ED25519_SIGNATURE_SIZE};
+use crate::yasmf_hash::YasmfHash;
+
+pub mod error;
+pub use error::*;
+
+const IS_END_OF_FEED: &str = "is_end_of_feed";
+const AUTHOR: &str = "author";
+const LOG_ID: &str = "log_id";
+const SEQ_NUM: &str = "seq_num";
+const LIPMAA_LINK: &str = "lipmaa_link";
+const BACKLINK: &str = "backlink";
+const PAYLOAD_SIZE: &str = "payload_size";
+const PAYLOAD_HASH: &str = "payload_hash";
+const SIG: &str = "sig";
+const NONE: &str = "none";
+
+impl<H, S> Entry<H, S>
+where
+    H: Borrow<[u8]> + Clone,
+    S: Borrow<[u8]> + Clone,
+{
+    /// Write the entry in the text format to `w`.
+    pub fn write_text<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        writeln!(w, "{}: {}", IS_END_OF_FEED, self.is_end_of_feed)?;
+        write!(w, "{}: ", AUTHOR)?;
+        write_hex(w, self.author.as_bytes())?;
+        writeln!(w)?;
+        writeln!(w, "{}: {}", LOG_ID, self.log_id)?;
+        writeln!(w, "{}: {}", SEQ_NUM, self.seq_num)?;
+        write_optional_hash(w, LIPMAA_LINK, self.lipmaa_link.as_ref())?;
+        write_optional_hash(w, BACKLINK, self.backlink.as_ref())?;
+        writeln!(w, "{}: {}", PAYLOAD_SIZE, self.payload_size)?;
+        write_optional_hash(w, PAYLOAD_HASH, Some(&self.payload_hash))?;
+
+        write!(w, "{}: ", SIG)?;
+        match self.sig {
+            Some(ref sig) => write_hex(w, sig.0.borrow())?,
+            None => w.write_str(NONE)?,
+        }
+        writeln!(w)
+    }
+
+    /// The entry in the text format.
+    #[cfg(feature = "alloc")]
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // Writing to a String can't fail.
+        let _ = self.write_text(&mut text);
+        text
+    }
+}
+
+impl Entry<ArrayVec<[u8; MAX_HASH_DIGEST_SIZE]>, ArrayVec<[u8; ED25519_SIGNATURE_SIZE]>> {
+    /// Parse an entry written by [Entry::to_text].
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+
+        let is_end_of_feed = match next_value(&mut lines, IS_END_OF_FEED)? {
+            "true" => true,
+            "false" => false,
+            _ => {
+                return TextInvalidValue {
+                    field: IS_END_OF_FEED,
+                }
+                .fail()
+            }
+        };
+
+        let mut author_bytes = [0u8; PUBLIC_KEY_LENGTH];
+        parse_hex(next_value(&mut lines, AUTHOR)?, &mut author_bytes, AUTHOR)?;
+        let author = PublicKey::from_bytes(&author_bytes)
+            .map_err(|_| Error::TextInvalidValue { field: AUTHOR })?;
+
+        let log_id = parse_u64(next_value(&mut lines, LOG_ID)?, LOG_ID)?;
+        let seq_num = parse_u64(next_value(&mut lines, SEQ_NUM)?, SEQ_NUM)?;
+        ensure!(seq_num > 0, TextSeqIsZero);
+
+        let lipmaa_link = parse_optional_hash(next_value(&mut lines, LIPMAA_LINK)?, LIPMAA_LINK)?;
+        let backlink = parse_optional_hash(next_value(&mut lines, BACKLINK)?, BACKLINK)?;
+
+        let has_valid_links = match seq_num {
+            1 => lipmaa_link.is_none() && backlink.is_none(),
+            _ => backlink.is_some() && lipmaa_link.is_some() == is_lipmaa_required(seq_num),
+        };
+        ensure!(has_valid_links, TextInvalidLinks { seq_num });
+
+        let payload_size = parse_u64(next_value(&mut lines, PAYLOAD_SIZE)?, PAYLOAD_SIZE)?;
+        let payload_hash =
+            parse_optional_hash(next_value(&mut lines, PAYLOAD_HASH)?, PAYLOAD_HASH)?.ok_or(
+                Error::TextInvalidValue {
+                    field: PAYLOAD_HASH,
+                },
+            )?;
+
+        let sig = match next_value(&mut lines, SIG)? {
+            NONE => None,
+            hex => {
+                let mut sig_bytes = [0u8; ED25519_SIGNATURE_SIZE];
+                parse_hex(hex, &mut sig_bytes, SIG)?;
+                Some(Signature(ArrayVec::from(sig_bytes)))
+            }
+        };
+
+        ensure!(lines.next().is_none(), TextUnexpectedLine);
+
+        Ok(Entry {
+            log_id,
+            is_end_of_feed,
+            payload_hash,
+            payload_size,
+            author,
+            seq_num,
+            backlink,
+            lipmaa_link,
+            sig,
+        })
+    }
+}
+
+fn write_hex<W: fmt::Write>(w: &mut W, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(w, "{:02x}", byte))
+}
+
+fn write_optional_hash<W: fmt::Write, H: Borrow<[u8]> + Clone>(
+    w: &mut W,
+    field: &str,
+    hash: Option<&YasmfHash<H>>,
+) -> fmt::Result {
+    write!(w, "{}: ", field)?;
+
+    match hash {
+        Some(hash) => {
+            let mut bytes = [0u8; MAX_HASH_ENCODING_SIZE];
+            let len = encode_hash(hash, &mut bytes).map_err(|_| fmt::Error)?;
+            write_hex(w, &bytes[..len])?;
+        }
+        None => w.write_str(NONE)?,
+    }
+
+    writeln!(w)
+}
+
+fn next_value<'a>(lines: &mut Lines<'a>, field: &'static str) -> Result<&'a str> {
+    lines
+        .next()
+        .and_then(|line| line.strip_prefix(field))
+        .and_then(|line| line.strip_prefix(": "))
+        .ok_or(Error::TextMissingField { field })
+}
+
+/// Parse lowercase hex that exactly fills `out`.
+fn parse_hex(hex: &str, out: &mut [u8], field: &'static str) -> Result<()> {
+    let is_lowercase = hex.bytes().all(|b| !b.is_ascii_uppercase());
+    ensure!(
+        is_lowercase && hex.len() == out.len() * 2,
+        TextInvalidValue { field }
+    );
+
+    hex::decode_to_slice(hex, out).map_err(|_| Error::TextInvalidValue { field })
+}
+
+fn parse_u64(s: &str, field: &'static str) -> Result<u64> {
+    // Only the canonical form, without signs or leading zeros.
+    let is_canonical =
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) && (s == "0" || !s.starts_with('0'));
+    ensure!(is_canonical, TextInvalidValue { field });
+
+    s.parse().map_err(|_| Error::TextInvalidValue { field })
+}
+
+fn parse_optional_hash(s: &str, field: &'static str) -> Result<Option<OwnedYasmfHash>> {
+    if s == NONE {
+        return Ok(None);
+    }
+
+    let mut bytes = [0u8; MAX_HASH_ENCODING_SIZE];
+    ensure!(s.len() <= bytes.len() * 2, TextInvalidValue { field });
+    // Odd length hex doesn't fill the bytes, so parse_hex rejects it.
+    let bytes = &mut bytes[..s.len() / 2];
+    parse_hex(s, bytes, field)?;
+
+    let (hash, remaining_bytes) =
+        decode_hash_owned(bytes).map_err(|_| Error::TextInvalidValue { field })?;
+    ensure!(remaining_bytes.is_empty(), TextInvalidValue { field });
+
+    Ok(Some(hash))
+}
@@ -1,34 +1,51 @@
-use crate::BLAKE3_HASH_SIZE;
-use arrayvec::ArrayVec;
-use core::borrow::Borrow;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 use core::convert::TryFrom;
-use ed25519_dalek::PublicKey;
 use snafu::{NoneError, ResultExt};
-#[cfg(feature = "std")]
-use std::collections::HashMap;
-
-use ed25519_dalek::Signature as DalekSignature;
 
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
 #[cfg(feature = "std")]
-use ed25519_dalek::verify_batch as verify_batch_dalek;
+use std::collections::HashMap as Map;
+
+use ed25519_dalek::{verify_batch as verify_batch_dalek, PublicKey, Signature as DalekSignature};
 
+use crate::hash::{hash_like, HashAlgorithm, OwnedYasmfHash};
 use crate::yasmf_hash::YasmfHash;
-use yasmf_hash::blake3;
 
-use super::verify_links_and_payload;
 use super::Entry;
+use super::VerifyPolicy;
+use super::{verify_links_and_payload_with_policy, BytesAndHash};
+#[cfg(feature = "std")]
 use rayon::prelude::*;
 
 use super::error::*;
 
+/// Entries from outside a batch that entries in the batch may link to, keyed by seq_num.
+///
+/// The hashes use the default [HashAlgorithm], links using another algorithm are hashed again.
+pub(crate) type Ancestors = BTreeMap<u64, (Vec<u8>, OwnedYasmfHash)>;
+
 /// Batch verify a collection of entries that are **all from the same author and same log_id**
 ///
-/// Uses rayon and signature batch verification to utilize multiple processors + SIMD instruction.
-#[cfg(feature = "std")]
+/// Signatures are checked with dalek's batch verification. With the `std` feature this also uses
+/// rayon to utilize multiple processors + SIMD instruction.
 pub fn verify_batch<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
     entries_and_payloads: &[(E, Option<P>)],
 ) -> Result<()> {
-    verify_batch_links_and_payload(entries_and_payloads)?;
+    verify_batch_with_policy(entries_and_payloads, &VerifyPolicy::default())
+}
+
+/// Same as [verify_batch] but rejects batches and entries that are not allowed by `policy`.
+///
+/// When `policy.require_backlink` is set, the backlink of every entry (other than the first entry
+/// of the log) must also be in the batch.
+pub fn verify_batch_with_policy<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
+    entries_and_payloads: &[(E, Option<P>)],
+    policy: &VerifyPolicy,
+) -> Result<()> {
+    policy.check_batch_len(entries_and_payloads.len())?;
+    verify_batch_links_and_payload_with_policy(entries_and_payloads, policy)?;
     let bytes_iter = entries_and_payloads
         .iter()
         .map(|(bytes, _)| bytes.as_ref())
@@ -37,58 +54,197 @@ pub fn verify_batch<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
 
     Ok(())
 }
+/// Same as [verify_batch] but links to entries that aren't in the batch are looked up in
+/// `linked_entries`.
+///
+/// `linked_entries` are entries already verified, like the ones in a log the batch is being added
+/// to. They are only used to check the links and are not verified again.
+pub fn verify_batch_with_linked_entries<
+    E: AsRef<[u8]> + Sync,
+    P: AsRef<[u8]> + Sync,
+    L: AsRef<[u8]>,
+>(
+    entries_and_payloads: &[(E, Option<P>)],
+    linked_entries: &[L],
+) -> Result<()> {
+    let ancestors = linked_entries
+        .iter()
+        .map(|bytes| {
+            let bytes = bytes.as_ref();
+            let seq_num = Entry::try_from(bytes).context(DecodeEntry)?.seq_num;
+            Ok((
+                seq_num,
+                (bytes.to_vec(), HashAlgorithm::default().hash(bytes)),
+            ))
+        })
+        .collect::<Result<Ancestors>>()?;
+
+    verify_batch_links_and_payload_with_ancestors(
+        entries_and_payloads,
+        &ancestors,
+        &VerifyPolicy::default(),
+    )?;
+    let bytes_iter = entries_and_payloads
+        .iter()
+        .map(|(bytes, _)| bytes.as_ref())
+        .collect::<Vec<_>>();
+    verify_batch_signatures(&bytes_iter)?;
+
+    Ok(())
+}
+
 /// Batch verify the links + payloads of a collection of entries that are **all from the same author and same log_id**
-#[cfg(feature = "std")]
 pub fn verify_batch_links_and_payload<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
     entries_and_payloads: &[(E, Option<P>)],
 ) -> Result<()> {
-    // Build a hashmap from seq num to bytes and hashes we need.
-    let hash_map = entries_and_payloads[..]
-        .par_iter()
-        .map(|(bytes, payload)| {
-            let entry = Entry::try_from(bytes.as_ref()).context(DecodeEntry)?;
-            let entry_hash = blake3(bytes.as_ref()); //HashManyJob::new(&params, bytes.as_ref());
+    verify_batch_links_and_payload_with_policy(entries_and_payloads, &VerifyPolicy::default())
+}
 
-            let payload_and_hash = payload
-                .as_ref()
-                .map(|payload| (payload.as_ref(), blake3(payload.as_ref())));
+/// Same as [verify_batch_links_and_payload] but rejects entries that are not allowed by `policy`.
+pub fn verify_batch_links_and_payload_with_policy<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
+    entries_and_payloads: &[(E, Option<P>)],
+    policy: &VerifyPolicy,
+) -> Result<()> {
+    verify_batch_links_and_payload_with_ancestors(entries_and_payloads, &Ancestors::new(), policy)
+}
 
-            Ok((
-                entry.seq_num,
-                (bytes.as_ref(), entry, entry_hash, payload_and_hash),
-            ))
-        })
-        .collect::<Result<HashMap<u64, (_, _, _, _)>>>()?;
-
-    hash_map
-        .par_iter()
-        .map(|(seq_num, (_, entry, _, payload_and_hash))| {
-            let backlink_and_hash = hash_map.get(&(seq_num - 1)).map(
-                |(bytes, _, entry_hash, _)| -> (_, YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>) {
-                    (*bytes, (*entry_hash).into())
-                },
-            );
-
-            let lipmaa_link_and_hash = hash_map.get(&(lipmaa_link::lipmaa(*seq_num))).map(
-                |(bytes, _, entry_hash, _)| -> (_, YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>) {
-                    (*bytes, (*entry_hash).into())
-                },
-            );
-
-            let payload_and_hash = payload_and_hash
-                .as_ref()
-                .map(|(payload, job)| (*payload, (*job).into()));
-
-            verify_links_and_payload(
-                entry,
-                payload_and_hash,
-                lipmaa_link_and_hash,
-                backlink_and_hash,
-            )
+/// Batch verify the links + payloads of a collection of entries, looking up links that aren't
+/// in the batch in `ancestors`.
+pub(crate) fn verify_batch_links_and_payload_with_ancestors<
+    E: AsRef<[u8]> + Sync,
+    P: AsRef<[u8]> + Sync,
+>(
+    entries_and_payloads: &[(E, Option<P>)],
+    ancestors: &Ancestors,
+    policy: &VerifyPolicy,
+) -> Result<()> {
+    verify_batch_links_and_payload_in_chunks(
+        entries_and_payloads,
+        ancestors,
+        policy,
+        LINK_CHUNK_SIZE,
+        &|_, _| Ok(()),
+    )
+}
+
+/// The number of entries decoded or link checked per chunk by
+/// [verify_batch_links_and_payload_with_ancestors].
+const LINK_CHUNK_SIZE: usize = 125;
+
+/// A stage of batch verification, reported by `BatchVerifier::on_phase_progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyPhase {
+    /// Decoding and hashing the entries and payloads.
+    Decode,
+    /// Checking the links and payloads of the entries.
+    Links,
+    /// Checking the signatures of the entries.
+    Signatures,
+}
+
+/// Same as [verify_batch_links_and_payload_with_ancestors] but works through the entries
+/// `chunk_size` at a time, twice: once to decode and hash them, then again to check their links
+/// and payloads. `on_chunk` is called with the phase and length of each chunk once it's done, and
+/// stops the verification if it returns an error.
+pub(crate) fn verify_batch_links_and_payload_in_chunks<
+    E: AsRef<[u8]> + Sync,
+    P: AsRef<[u8]> + Sync,
+>(
+    entries_and_payloads: &[(E, Option<P>)],
+    ancestors: &Ancestors,
+    policy: &VerifyPolicy,
+    chunk_size: usize,
+    on_chunk: &(dyn Fn(VerifyPhase, usize) -> Result<()> + Sync),
+) -> Result<()> {
+    #[cfg(feature = "std")]
+    let entries_and_payloads_chunks = entries_and_payloads.par_chunks(chunk_size);
+    #[cfg(not(feature = "std"))]
+    let entries_and_payloads_chunks = entries_and_payloads.chunks(chunk_size);
+
+    // Decode and hash the entries and payloads.
+    let decoded = entries_and_payloads_chunks
+        .map(|chunk| {
+            let decoded = chunk
+                .iter()
+                .map(|(bytes, payload)| {
+                    let entry = Entry::try_from(bytes.as_ref()).context(DecodeEntry)?;
+                    let entry_hash = HashAlgorithm::default().hash(bytes.as_ref());
+
+                    let payload_and_hash = payload.as_ref().map(|payload| {
+                        (
+                            payload.as_ref(),
+                            hash_like(&entry.payload_hash, payload.as_ref()),
+                        )
+                    });
+
+                    Ok((bytes.as_ref(), entry, entry_hash, payload_and_hash))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            on_chunk(VerifyPhase::Decode, chunk.len())?;
+            Ok(decoded)
         })
-        .collect()
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    // Build a hashmap from seq num to bytes and hashes we need.
+    let hash_map = decoded
+        .iter()
+        .map(|(bytes, entry, entry_hash, _)| (entry.seq_num, (*bytes, entry_hash)))
+        .collect::<Map<u64, _>>();
+
+    #[cfg(feature = "std")]
+    let decoded_chunks = decoded.par_chunks(chunk_size);
+    #[cfg(not(feature = "std"))]
+    let mut decoded_chunks = decoded.chunks(chunk_size);
+
+    decoded_chunks.try_for_each(|chunk| {
+        chunk
+            .iter()
+            .try_for_each(|(_, entry, _, payload_and_hash)| {
+                let get_link =
+                    |link_seq_num: u64, link: Option<&YasmfHash<&[u8]>>| -> Option<BytesAndHash> {
+                        let (bytes, hash) = match hash_map.get(&link_seq_num) {
+                            Some((bytes, entry_hash)) => (*bytes, *entry_hash),
+                            None => ancestors
+                                .get(&link_seq_num)
+                                .map(|(bytes, hash)| (&bytes[..], hash))?,
+                        };
+
+                        let algorithm = link.map(HashAlgorithm::of).unwrap_or_default();
+                        if HashAlgorithm::of(hash) == algorithm {
+                            Some((bytes, hash.clone()))
+                        } else {
+                            Some((bytes, algorithm.hash(bytes)))
+                        }
+                    };
+
+                let backlink_and_hash = get_link(entry.seq_num - 1, entry.backlink.as_ref());
+                let lipmaa_link_and_hash = get_link(
+                    lipmaa_link::lipmaa(entry.seq_num),
+                    entry.lipmaa_link.as_ref(),
+                );
+
+                let payload_and_hash = payload_and_hash
+                    .as_ref()
+                    .map(|(payload, hash)| (*payload, hash.clone()));
+
+                verify_links_and_payload_with_policy(
+                    entry,
+                    payload_and_hash,
+                    lipmaa_link_and_hash,
+                    backlink_and_hash,
+                    policy,
+                )
+            })?;
+        on_chunk(VerifyPhase::Links, chunk.len())
+    })
 }
 
+/// The number of signatures [verify_batch_signatures] verifies in a single dalek batch.
+pub const DEFAULT_SIGNATURE_CHUNK_SIZE: usize = 125;
+
 /// Batch verify the signatures of a collection of entries that are **all from the same author and same log_id**
 #[cfg(feature = "std")]
 pub fn verify_batch_signatures<'a, T: AsRef<[u8]>>(entries_bytes: &'a [T]) -> Result<()>
@@ -98,54 +254,62 @@ where
 {
     entries_bytes
         .as_parallel_slice()
-        .par_chunks(125)
-        .try_fold(
-            || (),
-            |_, chunk| {
-                let entries = chunk
-                    .into_iter()
-                    .map(|bytes| Entry::try_from(bytes.as_ref()).context(DecodeEntry))
-                    .collect::<Result<Vec<_>>>()?;
-
-                let unsigned_encoding_vecs = entries
-                    .iter()
-                    .map(|entry| {
-                        // TODO more efficient?
-                        let mut vec = Vec::with_capacity(entry.encoding_length());
-                        entry
-                            .encode_for_signing_write(&mut vec)
-                            .context(EncodeEntryForSigning)?;
-                        Ok(vec)
-                    })
-                    .collect::<Result<Vec<Vec<u8>>>>()?;
-
-                let unsigned_encodings = unsigned_encoding_vecs
-                    .iter()
-                    .map(|entry| entry.as_ref())
-                    .collect::<Vec<_>>();
-
-                let signatures = entries
-                    .iter()
-                    .map(|entry| {
-                        let ssb_sig =
-                            DalekSignature::try_from(entry.sig.as_ref().unwrap().0.borrow())
-                                .map_err(|_| NoneError)
-                                .context(DecodeSigError)?;
-                        Ok(ssb_sig)
-                    })
-                    .collect::<Result<Vec<DalekSignature>>>()?;
-
-                let pub_keys = entries
-                    .iter()
-                    .map(|entry| entry.author.clone())
-                    .collect::<Vec<PublicKey>>();
-
-                verify_batch_dalek(&unsigned_encodings, &signatures, &pub_keys[..])
-                    .map_err(|_| NoneError)
-                    .context(InvalidSignature)?;
-
-                Ok(())
-            },
-        )
+        .par_chunks(DEFAULT_SIGNATURE_CHUNK_SIZE)
+        .try_fold(|| (), |_, chunk| verify_signatures_chunk(chunk))
         .try_reduce(|| (), |_, _| Ok(()))
 }
+
+/// Batch verify the signatures of a collection of entries that are **all from the same author and same log_id**
+#[cfg(not(feature = "std"))]
+pub fn verify_batch_signatures<T: AsRef<[u8]>>(entries_bytes: &[T]) -> Result<()> {
+    entries_bytes
+        .chunks(DEFAULT_SIGNATURE_CHUNK_SIZE)
+        .try_for_each(verify_signatures_chunk)
+}
+
+/// Verify the signatures of `chunk` in a single dalek batch.
+pub(crate) fn verify_signatures_chunk<T: AsRef<[u8]>>(chunk: &[T]) -> Result<()> {
+    let entries = chunk
+        .iter()
+        .map(|bytes| Entry::try_from(bytes.as_ref()).context(DecodeEntry))
+        .collect::<Result<Vec<_>>>()?;
+
+    let unsigned_encoding_vecs = entries
+        .iter()
+        .map(|entry| {
+            // TODO more efficient?
+            let mut vec = alloc::vec![0; entry.encoding_length()];
+            let len = entry
+                .encode_for_signing(&mut vec)
+                .context(EncodeEntryForSigning)?;
+            vec.truncate(len);
+            Ok(vec)
+        })
+        .collect::<Result<Vec<Vec<u8>>>>()?;
+
+    let unsigned_encodings = unsigned_encoding_vecs
+        .iter()
+        .map(|entry| entry.as_ref())
+        .collect::<Vec<_>>();
+
+    let signatures = entries
+        .iter()
+        .map(|entry| {
+            let ssb_sig = DalekSignature::try_from(entry.sig.as_ref().unwrap().0)
+                .map_err(|_| NoneError)
+                .context(DecodeSigError)?;
+            Ok(ssb_sig)
+        })
+        .collect::<Result<Vec<DalekSignature>>>()?;
+
+    let pub_keys = entries
+        .iter()
+        .map(|entry| entry.author)
+        .collect::<Vec<PublicKey>>();
+
+    verify_batch_dalek(&unsigned_encodings, &signatures, &pub_keys[..])
+        .map_err(|_| NoneError)
+        .context(InvalidSignature)?;
+
+    Ok(())
+}
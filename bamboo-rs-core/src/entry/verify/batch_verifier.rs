@@ -0,0 +1,216 @@
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use snafu::ensure;
+use std::sync::Arc;
+
+pub use super::batch::VerifyPhase;
+use super::batch::{
+    verify_batch_links_and_payload_in_chunks, verify_signatures_chunk, Ancestors,
+    DEFAULT_SIGNATURE_CHUNK_SIZE,
+};
+use super::error::*;
+use super::VerifyPolicy;
+
+/// A cheaply cloneable flag used to abort a [BatchVerifier] from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask any verification using this token to stop as soon as possible.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+type OnPhaseProgress<'a> = Box<dyn Fn(VerifyPhase, usize, usize) + Send + Sync + 'a>;
+
+/// Configurable batch verification of entries that are **all from the same author and same
+/// log_id**.
+///
+/// [verify_batch](super::verify_batch) always uses rayon's global thread pool and fixed size
+/// chunks. `BatchVerifier` lets you choose the thread pool, the number of signatures verified per
+/// chunk, get progress updates and cancel the verification.
+///
+/// ```
+/// use bamboo_rs_core_ed25519_yasmf::entry::verify::{BatchVerifier, CancellationToken};
+///
+/// let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+/// let cancel = CancellationToken::new();
+///
+/// let verifier = BatchVerifier::new()
+///     .thread_pool(&pool)
+///     .chunk_size(64)
+///     .cancellation_token(cancel.clone())
+///     .on_progress(|verified, total| println!("verified {} of {}", verified, total));
+///
+/// let entries: [(&[u8], Option<&[u8]>); 0] = [];
+/// verifier.verify(&entries).unwrap();
+/// ```
+pub struct BatchVerifier<'a> {
+    thread_pool: Option<&'a ThreadPool>,
+    chunk_size: usize,
+    on_progress: Option<Box<dyn Fn(usize, usize) + Send + Sync + 'a>>,
+    on_phase_progress: Option<OnPhaseProgress<'a>>,
+    cancellation_token: Option<CancellationToken>,
+    policy: VerifyPolicy<'a>,
+}
+
+impl<'a> Default for BatchVerifier<'a> {
+    fn default() -> Self {
+        BatchVerifier {
+            thread_pool: None,
+            chunk_size: DEFAULT_SIGNATURE_CHUNK_SIZE,
+            on_progress: None,
+            on_phase_progress: None,
+            cancellation_token: None,
+            policy: VerifyPolicy::default(),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for BatchVerifier<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchVerifier")
+            .field("thread_pool", &self.thread_pool)
+            .field("chunk_size", &self.chunk_size)
+            .field("on_progress", &self.on_progress.is_some())
+            .field("on_phase_progress", &self.on_phase_progress.is_some())
+            .field("cancellation_token", &self.cancellation_token)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<'a> BatchVerifier<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run verification on `thread_pool` instead of rayon's global pool.
+    pub fn thread_pool(mut self, thread_pool: &'a ThreadPool) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// The number of entries decoded, link checked or signature checked at a time, and so the
+    /// number of signatures verified in a single dalek batch. Progress is reported and
+    /// cancellation checked once per chunk. Defaults to [DEFAULT_SIGNATURE_CHUNK_SIZE].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Called with `(verified, total)` each time a chunk of signatures is verified, where
+    /// `verified` is the number of entries that are fully verified and `total` is the number of
+    /// entries in the batch. May be called from any thread in the pool.
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: Fn(usize, usize) + Send + Sync + 'a,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Called with `(phase, done, total)` each time a chunk of a [VerifyPhase] is done, where
+    /// `done` is the number of entries that have been through `phase` and `total` is the number of
+    /// entries in the batch. The phases run in order. May be called from any thread in the pool.
+    pub fn on_phase_progress<F>(mut self, on_phase_progress: F) -> Self
+    where
+        F: Fn(VerifyPhase, usize, usize) + Send + Sync + 'a,
+    {
+        self.on_phase_progress = Some(Box::new(on_phase_progress));
+        self
+    }
+
+    /// Stop verifying with [Error::Cancelled] once `cancellation_token` is cancelled.
+    pub fn cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Reject batches and entries that are not allowed by `policy`.
+    pub fn policy(mut self, policy: VerifyPolicy<'a>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Verify the links, payloads and signatures of `entries_and_payloads`.
+    pub fn verify<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
+        &self,
+        entries_and_payloads: &[(E, Option<P>)],
+    ) -> Result<()> {
+        match self.thread_pool {
+            Some(thread_pool) => thread_pool.install(|| self.verify_in_pool(entries_and_payloads)),
+            None => self.verify_in_pool(entries_and_payloads),
+        }
+    }
+
+    fn verify_in_pool<E: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync>(
+        &self,
+        entries_and_payloads: &[(E, Option<P>)],
+    ) -> Result<()> {
+        self.policy.check_batch_len(entries_and_payloads.len())?;
+        self.ensure_not_cancelled()?;
+
+        let total = entries_and_payloads.len();
+        let decoded = AtomicUsize::new(0);
+        let linked = AtomicUsize::new(0);
+        let verified = AtomicUsize::new(0);
+        let on_chunk = |phase: VerifyPhase, chunk_len: usize| {
+            let done = match phase {
+                VerifyPhase::Decode => &decoded,
+                VerifyPhase::Links => &linked,
+                VerifyPhase::Signatures => &verified,
+            };
+            let done = done.fetch_add(chunk_len, Ordering::SeqCst) + chunk_len;
+            if let Some(ref on_phase_progress) = self.on_phase_progress {
+                on_phase_progress(phase, done, total);
+            }
+            if let (VerifyPhase::Signatures, Some(on_progress)) = (phase, &self.on_progress) {
+                on_progress(done, total);
+            }
+            self.ensure_not_cancelled()
+        };
+
+        verify_batch_links_and_payload_in_chunks(
+            entries_and_payloads,
+            &Ancestors::new(),
+            &self.policy,
+            self.chunk_size,
+            &on_chunk,
+        )?;
+
+        entries_and_payloads
+            .par_chunks(self.chunk_size)
+            .try_for_each(|chunk| {
+                let entries_bytes = chunk
+                    .iter()
+                    .map(|(bytes, _)| bytes.as_ref())
+                    .collect::<Vec<_>>();
+                verify_signatures_chunk(&entries_bytes)?;
+
+                on_chunk(VerifyPhase::Signatures, chunk.len())
+            })
+    }
+
+    fn ensure_not_cancelled(&self) -> Result<()> {
+        let is_cancelled = self
+            .cancellation_token
+            .as_ref()
+            .map(|token| token.is_cancelled())
+            .unwrap_or(false);
+
+        ensure!(!is_cancelled, Cancelled);
+        Ok(())
+    }
+}
@@ -51,6 +51,30 @@ pub enum Error {
     DecodeEntry { source: DecodeError },
     #[snafu(display("Failed to encode entry for signing: {}", source))]
     EncodeEntryForSigning { source: EncodeError },
+    #[snafu(display(
+        "The payload size encoded in the entry ({}) exceeds the maximum allowed by the verify policy ({})",
+        actual,
+        max
+    ))]
+    PayloadSizeExceedsPolicy { actual: u64, max: u64 },
+    #[snafu(display("Entry log_id {} is not allowed by the verify policy", log_id))]
+    LogIdNotAllowedByPolicy { log_id: u64 },
+    #[snafu(display(
+        "Batch of {} entries exceeds the maximum allowed by the verify policy ({})",
+        actual,
+        max
+    ))]
+    BatchLengthExceedsPolicy { actual: usize, max: usize },
+    #[snafu(display("Payload required by the verify policy but not provided"))]
+    PayloadRequired,
+    #[snafu(display(
+        "Entries must be streamed in ascending seq_num order but seq_num {} came after {}",
+        actual,
+        previous
+    ))]
+    StreamSeqNumNotAscending { previous: u64, actual: u64 },
+    #[snafu(display("Verification was cancelled"))]
+    Cancelled,
     #[snafu(display("The entry is invalid."))]
     UnknownError,
 }
@@ -1,4 +1,3 @@
-use arrayvec::ArrayVec;
 use core::borrow::Borrow;
 use core::convert::TryFrom;
 use snafu::{ensure, NoneError, ResultExt};
@@ -6,16 +5,31 @@ use snafu::{ensure, NoneError, ResultExt};
 use ed25519_dalek::{Signature as DalekSignature, Verifier};
 
 use super::{decode::decode, is_lipmaa_required, Entry};
-use crate::yasmf_hash::{YasmfHash, new_blake3, BLAKE3_HASH_SIZE};
+use crate::hash::{hash_like, HashAlgorithm, OwnedYasmfHash};
+use crate::yasmf_hash::YasmfHash;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 pub mod batch;
+#[cfg(feature = "alloc")]
+pub use batch::{
+    verify_batch, verify_batch_signatures, verify_batch_with_linked_entries,
+    verify_batch_with_policy,
+};
+#[cfg(feature = "std")]
+pub mod batch_verifier;
 #[cfg(feature = "std")]
-pub use batch::{verify_batch, verify_batch_signatures};
+pub use batch_verifier::{BatchVerifier, CancellationToken, VerifyPhase};
+#[cfg(feature = "alloc")]
+pub mod stream;
+#[cfg(feature = "alloc")]
+pub use stream::{verify_batch_iter, verify_batch_iter_with_policy, StreamVerifier};
 
 pub mod error;
 pub use error::*;
 
+pub mod policy;
+pub use policy::VerifyPolicy;
+
 impl<'a, H, S> Entry<H, S>
 where
     H: Borrow<[u8]> + Clone,
@@ -40,12 +54,37 @@ where
     }
 }
 
+/// Some bytes paired with their hash.
+///
+/// The hash must use the same algorithm as the hash it's compared against in the entry, see
+/// [HashAlgorithm::of].
+pub type BytesAndHash<'a> = (&'a [u8], OwnedYasmfHash);
+
 pub fn verify_links_and_payload(
     entry: &Entry<&[u8], &[u8]>,
-    payload: Option<(&[u8], YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>)>,
-    lipmaa_link: Option<(&[u8], YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>)>,
-    backlink: Option<(&[u8], YasmfHash<ArrayVec<[u8; BLAKE3_HASH_SIZE]>>)>,
+    payload: Option<BytesAndHash>,
+    lipmaa_link: Option<BytesAndHash>,
+    backlink: Option<BytesAndHash>,
 ) -> Result<(), Error> {
+    verify_links_and_payload_with_policy(
+        entry,
+        payload,
+        lipmaa_link,
+        backlink,
+        &VerifyPolicy::default(),
+    )
+}
+
+/// Same as [verify_links_and_payload] but rejects entries that are not allowed by `policy`.
+pub fn verify_links_and_payload_with_policy(
+    entry: &Entry<&[u8], &[u8]>,
+    payload: Option<BytesAndHash>,
+    lipmaa_link: Option<BytesAndHash>,
+    backlink: Option<BytesAndHash>,
+    policy: &VerifyPolicy,
+) -> Result<(), Error> {
+    policy.check_entry(entry, payload.is_some(), backlink.is_some())?;
+
     // If we have the payload, check that its hash and length match what is encoded in the
     // entry.
     if let Some((payload, payload_hash)) = payload {
@@ -157,20 +196,49 @@ pub fn verify(
     payload: Option<&[u8]>,
     lipmaa_link: Option<&[u8]>,
     backlink: Option<&[u8]>,
+) -> Result<(), Error> {
+    verify_with_policy(
+        entry_bytes,
+        payload,
+        lipmaa_link,
+        backlink,
+        &VerifyPolicy::default(),
+    )
+}
+
+/// Same as [verify] but rejects entries that are not allowed by `policy`.
+///
+/// Use this when `entry_bytes` come from an untrusted source. See [VerifyPolicy].
+pub fn verify_with_policy(
+    entry_bytes: &[u8],
+    payload: Option<&[u8]>,
+    lipmaa_link: Option<&[u8]>,
+    backlink: Option<&[u8]>,
+    policy: &VerifyPolicy,
 ) -> Result<(), Error> {
     // Decode the entry that we want to verify.
     let entry = decode(entry_bytes).context(DecodeEntry)?;
 
-    let payload_and_hash = payload.map(|payload| (payload, new_blake3(payload)));
-    let lipmaa_link_and_hash = lipmaa_link.map(|link| (link, new_blake3(link)));
-    let backlink_and_hash = backlink.map(|link| (link, new_blake3(link)));
+    // Hash with the same algorithms the entry used, so the hashes can be compared.
+    let payload_and_hash =
+        payload.map(|payload| (payload, hash_like(&entry.payload_hash, payload)));
+    let lipmaa_link_and_hash =
+        lipmaa_link.map(|link| (link, hash_link(entry.lipmaa_link.as_ref(), link)));
+    let backlink_and_hash = backlink.map(|link| (link, hash_link(entry.backlink.as_ref(), link)));
 
-    verify_links_and_payload(
+    verify_links_and_payload_with_policy(
         &entry,
         payload_and_hash,
         lipmaa_link_and_hash,
         backlink_and_hash,
+        policy,
     )?;
 
     entry.verify_signature()
 }
+
+/// Hash the bytes of a linked entry with the algorithm of `link`, or the default if the entry has
+/// no such link.
+pub(crate) fn hash_link(link: Option<&YasmfHash<&[u8]>>, bytes: &[u8]) -> OwnedYasmfHash {
+    link.map(HashAlgorithm::of).unwrap_or_default().hash(bytes)
+}
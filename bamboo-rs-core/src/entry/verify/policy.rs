@@ -0,0 +1,77 @@
+use snafu::ensure;
+
+use super::error::*;
+use crate::entry::Entry;
+use core::borrow::Borrow;
+
+/// Limits to apply when verifying entries from an untrusted source.
+///
+/// The [Default] policy applies no limits, which matches the behaviour of [verify](super::verify),
+/// [verify_links_and_payload](super::verify_links_and_payload) and
+/// [verify_batch](super::verify_batch).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifyPolicy<'a> {
+    /// The largest `payload_size` an entry may declare.
+    pub max_payload_size: Option<u64>,
+    /// The only log_ids an entry may be published to.
+    pub allowed_log_ids: Option<&'a [u64]>,
+    /// The largest number of entries accepted by a single batch verification.
+    pub max_batch_len: Option<usize>,
+    /// Reject entries when the payload is not provided.
+    pub require_payload: bool,
+    /// Reject entries (other than the first entry) when the backlink is not provided.
+    ///
+    /// This guarantees that an entry published after an `is_end_of_feed` entry is detected.
+    pub require_backlink: bool,
+}
+
+impl<'a> VerifyPolicy<'a> {
+    /// Check `entry` against the policy, given whether its payload and backlink were provided.
+    pub fn check_entry<H, S>(
+        &self,
+        entry: &Entry<H, S>,
+        has_payload: bool,
+        has_backlink: bool,
+    ) -> Result<()>
+    where
+        H: Borrow<[u8]> + Clone,
+        S: Borrow<[u8]> + Clone,
+    {
+        if let Some(max) = self.max_payload_size {
+            ensure!(
+                entry.payload_size <= max,
+                PayloadSizeExceedsPolicy {
+                    actual: entry.payload_size,
+                    max
+                }
+            );
+        }
+
+        if let Some(allowed_log_ids) = self.allowed_log_ids {
+            ensure!(
+                allowed_log_ids.contains(&entry.log_id),
+                LogIdNotAllowedByPolicy {
+                    log_id: entry.log_id
+                }
+            );
+        }
+
+        ensure!(!self.require_payload || has_payload, PayloadRequired);
+
+        ensure!(
+            !self.require_backlink || entry.seq_num == 1 || has_backlink,
+            BackLinkRequired
+        );
+
+        Ok(())
+    }
+
+    /// Check the number of entries in a batch against the policy.
+    pub fn check_batch_len(&self, len: usize) -> Result<()> {
+        if let Some(max) = self.max_batch_len {
+            ensure!(len <= max, BatchLengthExceedsPolicy { actual: len, max });
+        }
+
+        Ok(())
+    }
+}
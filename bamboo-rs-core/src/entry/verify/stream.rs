@@ -0,0 +1,174 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use snafu::{ensure, ResultExt};
+
+use super::batch::{
+    verify_batch_links_and_payload_with_ancestors, verify_batch_signatures, Ancestors,
+};
+use super::error::*;
+use super::VerifyPolicy;
+use crate::entry::{is_linked_after, Entry};
+use crate::hash::HashAlgorithm;
+
+/// Verify a long stream of entries that are **all from the same author and same log_id** in
+/// windows of `window_size` entries.
+///
+/// `entries_and_payloads` can be any iterator, including the receiving end of a channel. Entries
+/// must arrive in ascending seq_num order. See [StreamVerifier] for how memory is bounded.
+pub fn verify_batch_iter<I, E, P>(entries_and_payloads: I, window_size: usize) -> Result<()>
+where
+    I: IntoIterator<Item = (E, Option<P>)>,
+    E: AsRef<[u8]> + Sync,
+    P: AsRef<[u8]> + Sync,
+{
+    verify_batch_iter_with_policy(entries_and_payloads, window_size, &VerifyPolicy::default())
+}
+
+/// Same as [verify_batch_iter] but rejects entries that are not allowed by `policy`, and streams
+/// with more than `policy.max_batch_len` entries.
+pub fn verify_batch_iter_with_policy<I, E, P>(
+    entries_and_payloads: I,
+    window_size: usize,
+    policy: &VerifyPolicy,
+) -> Result<()>
+where
+    I: IntoIterator<Item = (E, Option<P>)>,
+    E: AsRef<[u8]> + Sync,
+    P: AsRef<[u8]> + Sync,
+{
+    let mut verifier = StreamVerifier::with_policy(window_size, *policy);
+
+    for (entry, payload) in entries_and_payloads {
+        verifier.push(entry, payload)?;
+    }
+
+    verifier.finish()
+}
+
+/// Verifies entries **all from the same author and same log_id** as they are pushed, a window at a
+/// time.
+///
+/// Once a window of entries is full it is verified just like
+/// [verify_batch](super::verify_batch), using rayon and signature batch verification with the
+/// `std` feature. The only entries kept after that are the
+/// ones later entries can still link to, so memory is bounded by `window_size` plus O(log n)
+/// entries rather than the length of the log.
+pub struct StreamVerifier<'a, E, P> {
+    window_size: usize,
+    window: Vec<(E, Option<P>)>,
+    ancestors: Ancestors,
+    last_seq_num: Option<u64>,
+    policy: VerifyPolicy<'a>,
+    pushed: usize,
+}
+
+impl<'a, E, P> StreamVerifier<'a, E, P>
+where
+    E: AsRef<[u8]> + Sync,
+    P: AsRef<[u8]> + Sync,
+{
+    /// Create a verifier that verifies `window_size` entries at a time.
+    pub fn new(window_size: usize) -> Self {
+        Self::with_policy(window_size, VerifyPolicy::default())
+    }
+
+    /// Same as [new](StreamVerifier::new) but rejects entries that are not allowed by `policy`.
+    /// `policy.max_batch_len` limits the number of entries pushed in total.
+    pub fn with_policy(window_size: usize, policy: VerifyPolicy<'a>) -> Self {
+        let window_size = window_size.max(1);
+
+        StreamVerifier {
+            window_size,
+            window: Vec::with_capacity(window_size),
+            ancestors: Ancestors::new(),
+            last_seq_num: None,
+            policy,
+            pushed: 0,
+        }
+    }
+
+    /// Add an entry to the current window, verifying the window if it is full.
+    ///
+    /// Errors if `entry` does not have a larger seq_num than the previous entry, or is not allowed
+    /// by the policy.
+    pub fn push(&mut self, entry: E, payload: Option<P>) -> Result<()> {
+        self.policy.check_batch_len(self.pushed + 1)?;
+
+        let decoded = Entry::try_from(entry.as_ref()).context(DecodeEntry)?;
+        // The backlink is checked once the window is verified, it may not have been pushed yet.
+        self.policy.check_entry(&decoded, payload.is_some(), true)?;
+        let seq_num = decoded.seq_num;
+
+        if let Some(previous) = self.last_seq_num {
+            ensure!(
+                seq_num > previous,
+                StreamSeqNumNotAscending {
+                    previous,
+                    actual: seq_num
+                }
+            );
+        }
+        self.last_seq_num = Some(seq_num);
+
+        self.pushed += 1;
+        self.window.push((entry, payload));
+
+        if self.window.len() >= self.window_size {
+            self.verify_window()?;
+        }
+
+        Ok(())
+    }
+
+    /// Verify any entries left in the current window.
+    pub fn finish(mut self) -> Result<()> {
+        self.verify_window()
+    }
+
+    /// The number of entries currently held in memory, including the window and the ancestors
+    /// that later entries may link to.
+    pub fn len(&self) -> usize {
+        self.window.len() + self.ancestors.len()
+    }
+
+    /// True when no entries are held in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn verify_window(&mut self) -> Result<()> {
+        if self.window.is_empty() {
+            return Ok(());
+        }
+
+        verify_batch_links_and_payload_with_ancestors(&self.window, &self.ancestors, &self.policy)?;
+
+        let entries_bytes = self
+            .window
+            .iter()
+            .map(|(bytes, _)| bytes.as_ref())
+            .collect::<Vec<_>>();
+        verify_batch_signatures(&entries_bytes)?;
+
+        // Entries are pushed in ascending order so this is the largest seq_num seen so far.
+        let last_seq_num = self.last_seq_num.unwrap_or(0);
+
+        self.ancestors
+            .retain(|seq_num, _| is_linked_after(*seq_num, last_seq_num));
+
+        for (bytes, _) in self.window.drain(..) {
+            let bytes = bytes.as_ref();
+            // Already decoded successfully during verification.
+            let seq_num = Entry::try_from(bytes).context(DecodeEntry)?.seq_num;
+
+            if is_linked_after(seq_num, last_seq_num) {
+                self.ancestors.insert(
+                    seq_num,
+                    (bytes.to_vec(), HashAlgorithm::default().hash(bytes)),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{decode_frame, Error, Frame};
+
+/// A [tokio_util::codec] adapter for [Frame]s, for use with `FramedRead` and `FramedWrite`.
+#[derive(Debug, Clone, Default)]
+pub struct FrameCodec {
+    max_payload_size: Option<u64>,
+}
+
+impl FrameCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject frames with payloads larger than `max_payload_size`.
+    pub fn with_max_payload_size(mut self, max_payload_size: u64) -> Self {
+        self.max_payload_size = Some(max_payload_size);
+        self
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, Error> {
+        match decode_frame(src, self.max_payload_size)? {
+            Some((frame, len)) => {
+                src.advance(len);
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<Frame> for FrameCodec {
+    type Error = Error;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), Error> {
+        let mut out = Vec::with_capacity(frame.encoding_length());
+        frame.encode(&mut out)?;
+        dst.extend_from_slice(&out);
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::entry::decode::Error as DecodeError;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Frame had a non canonical varu64 length"))]
+    FrameLengthNonCanonical,
+    #[snafu(display(
+        "Frame entry length of {} is larger than the max entry size of {}",
+        len,
+        max
+    ))]
+    FrameEntryTooLarge { len: u64, max: usize },
+    #[snafu(display("Could not decode the entry in a frame: {}", source))]
+    DecodeFrameEntry { source: DecodeError },
+    #[snafu(display("Frame entry length did not match the length of the decoded entry"))]
+    FrameEntryLengthMismatch,
+    #[snafu(display("Frame had an invalid payload tag: {}", tag))]
+    FrameInvalidPayloadTag { tag: u8 },
+    #[snafu(display(
+        "Frame payload length of {} does not match the entry payload_size of {}",
+        actual,
+        payload_size
+    ))]
+    FramePayloadSizeMismatch { actual: u64, payload_size: u64 },
+    #[snafu(display(
+        "Frame payload length of {} is larger than the max payload size of {}",
+        actual,
+        max
+    ))]
+    FramePayloadTooLarge { actual: u64, max: u64 },
+    #[cfg(feature = "std")]
+    #[snafu(display("IO error reading or writing frames: {}", source))]
+    FrameIo { source: std::io::Error },
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Error::FrameIo { source }
+    }
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
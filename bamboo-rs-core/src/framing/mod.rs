@@ -0,0 +1,221 @@
+//! Length prefixed framing for sending entries and their payloads over a byte stream.
+//!
+//! A frame is encoded as:
+//!
+//! - varu64 length of the entry, followed by the entry bytes.
+//! - `0` if there is no payload, or `1` followed by the varu64 length of the payload and the
+//!   payload bytes.
+//!
+//! The payload length must equal the `payload_size` of the entry. Decoders check this as soon as
+//! the length is read, before buffering the payload.
+use alloc::vec::Vec;
+use snafu::{ensure, ResultExt};
+use varu64::{
+    decode as varu64_decode, encode as varu64_encode, encoding_length as varu64_encoding_length,
+    DecodeError as Varu64DecodeError,
+};
+
+use crate::entry::{decode_with_remainder, MAX_ENTRY_SIZE, MAX_VARU64_SIZE};
+
+#[cfg(feature = "tokio-codec")]
+pub mod codec;
+pub mod error;
+
+#[cfg(feature = "tokio-codec")]
+pub use codec::FrameCodec;
+pub use error::*;
+
+const NO_PAYLOAD_TAG: u8 = 0;
+const PAYLOAD_TAG: u8 = 1;
+
+/// An encoded entry and optionally its payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub entry: Vec<u8>,
+    pub payload: Option<Vec<u8>>,
+}
+
+impl Frame {
+    pub fn new(entry: Vec<u8>, payload: Option<Vec<u8>>) -> Self {
+        Frame { entry, payload }
+    }
+
+    /// Append the encoded frame to `out`.
+    ///
+    /// Errors if the entry can't be decoded or the payload length doesn't match its
+    /// `payload_size`.
+    pub fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        let (entry, remainder) = decode_with_remainder(&self.entry).context(DecodeFrameEntry)?;
+        ensure!(remainder.is_empty(), FrameEntryLengthMismatch);
+
+        if let Some(ref payload) = self.payload {
+            ensure!(
+                payload.len() as u64 == entry.payload_size,
+                FramePayloadSizeMismatch {
+                    actual: payload.len() as u64,
+                    payload_size: entry.payload_size
+                }
+            );
+        }
+
+        out.reserve(self.encoding_length());
+
+        push_varu64(self.entry.len() as u64, out);
+        out.extend_from_slice(&self.entry);
+
+        match self.payload {
+            Some(ref payload) => {
+                out.push(PAYLOAD_TAG);
+                push_varu64(payload.len() as u64, out);
+                out.extend_from_slice(payload);
+            }
+            None => out.push(NO_PAYLOAD_TAG),
+        }
+
+        Ok(())
+    }
+
+    /// The number of bytes this frame encodes to.
+    pub fn encoding_length(&self) -> usize {
+        let payload_length = match self.payload {
+            Some(ref payload) => varu64_encoding_length(payload.len() as u64) + payload.len(),
+            None => 0,
+        };
+
+        varu64_encoding_length(self.entry.len() as u64) + self.entry.len() + 1 + payload_length
+    }
+}
+
+/// Try and decode a frame from the start of `bytes`.
+///
+/// Returns `Ok(None)` if `bytes` doesn't hold a whole frame yet, otherwise the frame and the
+/// number of bytes it used. Payloads larger than `max_payload_size` are rejected before they are
+/// received.
+pub fn decode_frame(bytes: &[u8], max_payload_size: Option<u64>) -> Result<Option<(Frame, usize)>> {
+    let (entry_len, remaining_bytes) = match read_varu64(bytes)? {
+        Some(decoded) => decoded,
+        None => return Ok(None),
+    };
+
+    ensure!(
+        entry_len <= MAX_ENTRY_SIZE as u64,
+        FrameEntryTooLarge {
+            len: entry_len,
+            max: MAX_ENTRY_SIZE
+        }
+    );
+    let entry_len = entry_len as usize;
+
+    if remaining_bytes.len() < entry_len {
+        return Ok(None);
+    }
+
+    let entry_bytes = &remaining_bytes[..entry_len];
+    let (entry, remainder) = decode_with_remainder(entry_bytes).context(DecodeFrameEntry)?;
+    ensure!(remainder.is_empty(), FrameEntryLengthMismatch);
+
+    let remaining_bytes = &remaining_bytes[entry_len..];
+
+    let (payload, remaining_bytes) = match remaining_bytes.split_first() {
+        None => return Ok(None),
+        Some((&NO_PAYLOAD_TAG, remaining_bytes)) => (None, remaining_bytes),
+        Some((&PAYLOAD_TAG, remaining_bytes)) => {
+            let (payload_len, remaining_bytes) = match read_varu64(remaining_bytes)? {
+                Some(decoded) => decoded,
+                None => return Ok(None),
+            };
+
+            ensure!(
+                payload_len == entry.payload_size,
+                FramePayloadSizeMismatch {
+                    actual: payload_len,
+                    payload_size: entry.payload_size
+                }
+            );
+
+            if let Some(max) = max_payload_size {
+                ensure!(
+                    payload_len <= max,
+                    FramePayloadTooLarge {
+                        actual: payload_len,
+                        max
+                    }
+                );
+            }
+
+            if (remaining_bytes.len() as u64) < payload_len {
+                return Ok(None);
+            }
+
+            let payload_len = payload_len as usize;
+            (
+                Some(&remaining_bytes[..payload_len]),
+                &remaining_bytes[payload_len..],
+            )
+        }
+        Some((&tag, _)) => return FrameInvalidPayloadTag { tag }.fail(),
+    };
+
+    let frame = Frame::new(
+        entry_bytes.to_vec(),
+        payload.map(|payload| payload.to_vec()),
+    );
+
+    Ok(Some((frame, bytes.len() - remaining_bytes.len())))
+}
+
+/// Decodes frames from bytes as they arrive, without depending on an async runtime.
+///
+/// After an error the stream is no longer in sync and should be dropped.
+#[derive(Debug, Clone, Default)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    max_payload_size: Option<u64>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject frames with payloads larger than `max_payload_size`.
+    pub fn with_max_payload_size(mut self, max_payload_size: u64) -> Self {
+        self.max_payload_size = Some(max_payload_size);
+        self
+    }
+
+    /// Add bytes received from the stream.
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Decode the next whole frame, `Ok(None)` if more bytes are needed.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>> {
+        match decode_frame(&self.buf, self.max_payload_size)? {
+            Some((frame, len)) => {
+                self.buf.drain(..len);
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The number of bytes received that are not part of a decoded frame yet.
+    pub fn buffered_len(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+fn push_varu64(value: u64, out: &mut Vec<u8>) {
+    let mut buf = [0u8; MAX_VARU64_SIZE];
+    let len = varu64_encode(value, &mut buf);
+    out.extend_from_slice(&buf[..len]);
+}
+
+fn read_varu64(bytes: &[u8]) -> Result<Option<(u64, &[u8])>> {
+    match varu64_decode(bytes) {
+        Ok(decoded) => Ok(Some(decoded)),
+        Err((Varu64DecodeError::UnexpectedEndOfInput, _)) => Ok(None),
+        Err((Varu64DecodeError::NonCanonical(_), _)) => FrameLengthNonCanonical.fail(),
+    }
+}
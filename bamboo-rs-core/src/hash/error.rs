@@ -0,0 +1,31 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Unsupported yasmf hash id: {}", id))]
+    UnsupportedHashId { id: u64 },
+    #[snafu(display("Could not decode yasmf hash id, error with varu64 encoding"))]
+    DecodeHashId,
+    #[snafu(display("Could not decode yasmf hash digest size, error with varu64 encoding"))]
+    DecodeHashDigestSize,
+    #[snafu(display(
+        "Yasmf hash with id {} has a digest size of {}, expected {}",
+        id,
+        actual,
+        expected
+    ))]
+    HashDigestSizeDoesNotMatch {
+        id: u64,
+        expected: usize,
+        actual: u64,
+    },
+    #[snafu(display("Not enough bytes to decode yasmf hash digest"))]
+    DecodeHashTooShort,
+    #[snafu(display("Buffer is too small to encode yasmf hash"))]
+    EncodeHashBufferLength,
+    #[snafu(display("Could not write yasmf hash"))]
+    EncodeHashWrite,
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
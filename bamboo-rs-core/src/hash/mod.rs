@@ -0,0 +1,178 @@
+//! Dispatch on the hash algorithm of a [YasmfHash].
+//!
+//! Code that needs to know which algorithm a hash uses, how big its digest is, how to hash bytes
+//! to compare against it or how to encode and decode it should go through [HashAlgorithm] and the
+//! functions here rather than matching on [YasmfHash] variants, so new yasmf hash ids only need
+//! adding here.
+//!
+//! Only the algorithms that have a [YasmfHash] variant are supported, which is currently just
+//! BLAKE3.
+use arrayvec::ArrayVec;
+use core::borrow::Borrow;
+use snafu::{ensure, OptionExt};
+#[cfg(feature = "std")]
+use std::io::Write;
+use varu64::{
+    decode as varu64_decode, encode as varu64_encode, encoding_length as varu64_encoding_length,
+};
+
+use crate::yasmf_hash::{new_blake3, YasmfHash, BLAKE3_HASH_SIZE, BLAKE3_NUMERIC_ID};
+
+pub mod error;
+pub use error::*;
+
+/// The largest digest of any supported algorithm.
+pub const MAX_HASH_DIGEST_SIZE: usize = BLAKE3_HASH_SIZE;
+
+/// The most bytes a yasmf hash of any supported algorithm encodes to.
+///
+/// The ids and digest sizes are all below 248, so their varu64s are a single byte each.
+pub const MAX_HASH_ENCODING_SIZE: usize = 2 + MAX_HASH_DIGEST_SIZE;
+
+/// A [YasmfHash] that owns a digest of any supported algorithm.
+pub type OwnedYasmfHash = YasmfHash<ArrayVec<[u8; MAX_HASH_DIGEST_SIZE]>>;
+
+/// A yasmf hash algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// All the supported algorithms.
+    pub const ALL: &'static [HashAlgorithm] = &[HashAlgorithm::Blake3];
+
+    /// The algorithm with the yasmf numeric id `id`.
+    pub fn from_id(id: u64) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.id() == id)
+            .context(UnsupportedHashId { id })
+    }
+
+    /// The algorithm that `hash` was made with.
+    pub fn of<T: Borrow<[u8]> + Clone>(hash: &YasmfHash<T>) -> Self {
+        match hash {
+            YasmfHash::Blake3(_) => HashAlgorithm::Blake3,
+        }
+    }
+
+    /// The yasmf numeric id.
+    pub fn id(self) -> u64 {
+        match self {
+            HashAlgorithm::Blake3 => BLAKE3_NUMERIC_ID,
+        }
+    }
+
+    /// The number of bytes in a digest.
+    pub fn digest_size(self) -> usize {
+        match self {
+            HashAlgorithm::Blake3 => BLAKE3_HASH_SIZE,
+        }
+    }
+
+    /// The number of bytes a yasmf hash made with this algorithm encodes to.
+    pub fn encoding_length(self) -> usize {
+        varu64_encoding_length(self.id())
+            + varu64_encoding_length(self.digest_size() as u64)
+            + self.digest_size()
+    }
+
+    /// Hash `bytes`.
+    pub fn hash(self, bytes: &[u8]) -> OwnedYasmfHash {
+        match self {
+            HashAlgorithm::Blake3 => new_blake3(bytes),
+        }
+    }
+
+    /// Wrap `digest`, which must be [digest_size](HashAlgorithm::digest_size) bytes, in the
+    /// [YasmfHash] variant for this algorithm.
+    pub fn from_digest<T: Borrow<[u8]> + Clone>(self, digest: T) -> YasmfHash<T> {
+        match self {
+            HashAlgorithm::Blake3 => YasmfHash::Blake3(digest),
+        }
+    }
+}
+
+/// BLAKE3, which is what entries are published with.
+impl Default for HashAlgorithm {
+    fn default() -> Self {
+        HashAlgorithm::Blake3
+    }
+}
+
+/// The digest bytes of `hash`, without the yasmf id and length.
+pub fn digest<T: Borrow<[u8]> + Clone>(hash: &YasmfHash<T>) -> &[u8] {
+    match hash {
+        YasmfHash::Blake3(ref bytes) => bytes.borrow(),
+    }
+}
+
+/// Copy `hash` into an [OwnedYasmfHash].
+pub fn to_owned_hash<T: Borrow<[u8]> + Clone>(hash: &YasmfHash<T>) -> OwnedYasmfHash {
+    let mut bytes = ArrayVec::new();
+    // Every supported digest fits in MAX_HASH_DIGEST_SIZE.
+    bytes.try_extend_from_slice(digest(hash)).unwrap();
+
+    HashAlgorithm::of(hash).from_digest(bytes)
+}
+
+/// Hash `bytes` with the same algorithm as `hash`, so the results can be compared.
+pub fn hash_like<T: Borrow<[u8]> + Clone>(hash: &YasmfHash<T>, bytes: &[u8]) -> OwnedYasmfHash {
+    HashAlgorithm::of(hash).hash(bytes)
+}
+
+/// Decode the yasmf hash at the start of `bytes`, returning it and the bytes after it.
+pub fn decode_hash(bytes: &[u8]) -> Result<(YasmfHash<&[u8]>, &[u8])> {
+    let (id, remaining_bytes) = varu64_decode(bytes).map_err(|_| Error::DecodeHashId)?;
+    let algorithm = HashAlgorithm::from_id(id)?;
+
+    let (digest_size, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::DecodeHashDigestSize)?;
+    ensure!(
+        digest_size == algorithm.digest_size() as u64,
+        HashDigestSizeDoesNotMatch {
+            id,
+            expected: algorithm.digest_size(),
+            actual: digest_size
+        }
+    );
+    ensure!(
+        remaining_bytes.len() >= algorithm.digest_size(),
+        DecodeHashTooShort
+    );
+
+    let (digest, remaining_bytes) = remaining_bytes.split_at(algorithm.digest_size());
+    Ok((algorithm.from_digest(digest), remaining_bytes))
+}
+
+/// Same as [decode_hash] but copies the hash into an [OwnedYasmfHash].
+pub fn decode_hash_owned(bytes: &[u8]) -> Result<(OwnedYasmfHash, &[u8])> {
+    let (hash, remaining_bytes) = decode_hash(bytes)?;
+    Ok((to_owned_hash(&hash), remaining_bytes))
+}
+
+/// Encode `hash` into `out`, returning the number of bytes written.
+pub fn encode_hash<T: Borrow<[u8]> + Clone>(hash: &YasmfHash<T>, out: &mut [u8]) -> Result<usize> {
+    let algorithm = HashAlgorithm::of(hash);
+    let encoding_length = algorithm.encoding_length();
+    ensure!(out.len() >= encoding_length, EncodeHashBufferLength);
+
+    let mut next_byte_num = varu64_encode(algorithm.id(), out);
+    next_byte_num += varu64_encode(algorithm.digest_size() as u64, &mut out[next_byte_num..]);
+    out[next_byte_num..encoding_length].copy_from_slice(digest(hash));
+
+    Ok(encoding_length)
+}
+
+/// Encode `hash` into the writer.
+#[cfg(feature = "std")]
+pub fn encode_hash_write<T: Borrow<[u8]> + Clone, W: Write>(
+    hash: &YasmfHash<T>,
+    mut w: W,
+) -> Result<()> {
+    let mut out = [0u8; MAX_HASH_ENCODING_SIZE];
+    let len = encode_hash(hash, &mut out)?;
+    w.write_all(&out[..len]).map_err(|_| Error::EncodeHashWrite)
+}
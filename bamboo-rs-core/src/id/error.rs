@@ -0,0 +1,27 @@
+use crate::entry::decode::Error as DecodeError;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Id did not start with the expected prefix: {}", prefix))]
+    IdInvalidPrefix { prefix: &'static str },
+    #[snafu(display("Id was not valid hex"))]
+    IdInvalidHex,
+    #[snafu(display("Id had the wrong length"))]
+    IdInvalidLength,
+    #[snafu(display("Id checksum did not match, it may have been mistyped"))]
+    IdInvalidChecksum,
+    #[snafu(display("Id had an invalid author public key"))]
+    IdInvalidAuthor,
+    #[snafu(display("Id had an invalid log_id"))]
+    IdInvalidLogId,
+    #[snafu(display("Id had an invalid seq_num"))]
+    IdInvalidSeqNum,
+    #[snafu(display("Id had an invalid entry hash"))]
+    IdInvalidHash,
+    #[snafu(display("Could not decode entry to get its id: {}", source))]
+    IdDecodeEntry { source: DecodeError },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,430 @@
+//! Identifiers for feeds and entries, with text and `bamboo:` URI forms.
+//!
+//! The text forms are a prefix followed by the hex encoded id and a 4 byte checksum, so mistyped
+//! ids are rejected:
+//!
+//! - [FeedId] as `feed_<hex>`
+//! - [EntryId] as `entry_<hex>`
+//!
+//! The URI forms are easier to read but have no checksum:
+//!
+//! - [FeedId] as `bamboo:feed/<author hex>/<log_id>`
+//! - [EntryId] as `bamboo:entry/<author hex>/<log_id>/<seq_num>/<yasmf hash hex>`
+//!
+//! [FromStr](core::str::FromStr) accepts either form.
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+use ed25519_dalek::{PublicKey, PUBLIC_KEY_LENGTH};
+use snafu::{ensure, ResultExt};
+use varu64::{decode as varu64_decode, encode as varu64_encode};
+
+use crate::entry::{decode, Entry, MAX_VARU64_SIZE};
+use crate::hash::{
+    decode_hash_owned, digest, encode_hash, HashAlgorithm, OwnedYasmfHash, MAX_HASH_ENCODING_SIZE,
+};
+use crate::yasmf_hash::blake3;
+
+pub mod error;
+pub use error::*;
+
+const FEED_ID_PREFIX: &str = "feed_";
+const ENTRY_ID_PREFIX: &str = "entry_";
+const FEED_URI_PREFIX: &str = "bamboo:feed/";
+const ENTRY_URI_PREFIX: &str = "bamboo:entry/";
+
+const MAX_PREFIX_SIZE: usize = 8;
+const CHECKSUM_SIZE: usize = 4;
+const MAX_ID_SIZE: usize =
+    PUBLIC_KEY_LENGTH + (MAX_VARU64_SIZE * 2) + MAX_HASH_ENCODING_SIZE + CHECKSUM_SIZE;
+
+/// Names a feed: an author and one of their log_ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedId {
+    pub author: PublicKey,
+    pub log_id: u64,
+}
+
+/// Names an entry: the feed it's in, its seq_num and the hash of the encoded entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryId {
+    pub author: PublicKey,
+    pub log_id: u64,
+    pub seq_num: u64,
+    pub hash: OwnedYasmfHash,
+}
+
+impl FeedId {
+    pub fn new(author: PublicKey, log_id: u64) -> Self {
+        FeedId { author, log_id }
+    }
+
+    /// Displays as the `bamboo:` URI form.
+    pub fn uri(&self) -> FeedIdUri<'_> {
+        FeedIdUri(self)
+    }
+
+    fn encode(&self, out: &mut [u8]) -> usize {
+        out[..PUBLIC_KEY_LENGTH].copy_from_slice(self.author.as_bytes());
+        PUBLIC_KEY_LENGTH + varu64_encode(self.log_id, &mut out[PUBLIC_KEY_LENGTH..])
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let (author, remaining_bytes) = decode_author(bytes)?;
+        let (log_id, remaining_bytes) =
+            varu64_decode(remaining_bytes).map_err(|_| Error::IdInvalidLogId)?;
+        ensure!(remaining_bytes.is_empty(), IdInvalidLength);
+
+        Ok(FeedId { author, log_id })
+    }
+
+    fn from_uri(uri: &str) -> Result<Self> {
+        let mut parts = strip_prefix(uri, FEED_URI_PREFIX)?.split('/');
+
+        let author = parse_uri_author(parts.next())?;
+        let log_id = parse_uri_u64(parts.next()).ok_or(Error::IdInvalidLogId)?;
+        ensure!(parts.next().is_none(), IdInvalidLength);
+
+        Ok(FeedId { author, log_id })
+    }
+}
+
+impl EntryId {
+    pub fn new(author: PublicKey, log_id: u64, seq_num: u64, hash: OwnedYasmfHash) -> Self {
+        EntryId {
+            author,
+            log_id,
+            seq_num,
+            hash,
+        }
+    }
+
+    /// The id of the encoded entry `entry_bytes`, hashed with the default [HashAlgorithm].
+    pub fn from_entry_bytes(entry_bytes: &[u8]) -> Result<Self> {
+        Self::from_entry_bytes_with_algorithm(entry_bytes, HashAlgorithm::default())
+    }
+
+    /// The id of the encoded entry `entry_bytes`, hashed with `algorithm`.
+    pub fn from_entry_bytes_with_algorithm(
+        entry_bytes: &[u8],
+        algorithm: HashAlgorithm,
+    ) -> Result<Self> {
+        let entry = decode(entry_bytes).context(IdDecodeEntry)?;
+
+        Ok(EntryId {
+            author: entry.author,
+            log_id: entry.log_id,
+            seq_num: entry.seq_num,
+            hash: algorithm.hash(entry_bytes),
+        })
+    }
+
+    /// The id of the feed this entry is in.
+    pub fn feed_id(&self) -> FeedId {
+        FeedId::new(self.author, self.log_id)
+    }
+
+    /// Displays as the `bamboo:` URI form.
+    pub fn uri(&self) -> EntryIdUri<'_> {
+        EntryIdUri(self)
+    }
+
+    fn encode(&self, out: &mut [u8]) -> usize {
+        let mut len = self.feed_id().encode(out);
+        len += varu64_encode(self.seq_num, &mut out[len..]);
+        // `out` is always large enough for a yasmf hash of any supported algorithm.
+        len + encode_hash(&self.hash, &mut out[len..]).unwrap_or(0)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let (author, remaining_bytes) = decode_author(bytes)?;
+        let (log_id, remaining_bytes) =
+            varu64_decode(remaining_bytes).map_err(|_| Error::IdInvalidLogId)?;
+        let (seq_num, remaining_bytes) =
+            varu64_decode(remaining_bytes).map_err(|_| Error::IdInvalidSeqNum)?;
+        ensure!(seq_num > 0, IdInvalidSeqNum);
+
+        let (hash, remaining_bytes) =
+            decode_hash_owned(remaining_bytes).map_err(|_| Error::IdInvalidHash)?;
+        ensure!(remaining_bytes.is_empty(), IdInvalidLength);
+
+        Ok(EntryId {
+            author,
+            log_id,
+            seq_num,
+            hash,
+        })
+    }
+
+    fn from_uri(uri: &str) -> Result<Self> {
+        let mut parts = strip_prefix(uri, ENTRY_URI_PREFIX)?.split('/');
+
+        let author = parse_uri_author(parts.next())?;
+        let log_id = parse_uri_u64(parts.next()).ok_or(Error::IdInvalidLogId)?;
+        let seq_num = parse_uri_u64(parts.next())
+            .filter(|seq_num| *seq_num > 0)
+            .ok_or(Error::IdInvalidSeqNum)?;
+
+        let hash_hex = parts.next().ok_or(Error::IdInvalidHash)?;
+        let mut hash_bytes = [0u8; MAX_HASH_ENCODING_SIZE];
+        ensure!(hash_hex.len() <= hash_bytes.len() * 2, IdInvalidHash);
+        // Odd length hex doesn't fill the bytes, so decoding rejects it.
+        let hash_bytes = &mut hash_bytes[..hash_hex.len() / 2];
+        hex::decode_to_slice(hash_hex, hash_bytes).map_err(|_| Error::IdInvalidHash)?;
+        let (hash, remaining_bytes) =
+            decode_hash_owned(hash_bytes).map_err(|_| Error::IdInvalidHash)?;
+        ensure!(remaining_bytes.is_empty(), IdInvalidHash);
+
+        ensure!(parts.next().is_none(), IdInvalidLength);
+
+        Ok(EntryId {
+            author,
+            log_id,
+            seq_num,
+            hash,
+        })
+    }
+}
+
+impl<H, S> Entry<H, S>
+where
+    H: Borrow<[u8]> + Clone,
+    S: Borrow<[u8]> + Clone,
+{
+    /// The id of the feed this entry is in.
+    pub fn feed_id(&self) -> FeedId {
+        FeedId::new(self.author, self.log_id)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for EntryId {
+    type Error = Error;
+
+    fn try_from(entry_bytes: &'a [u8]) -> Result<Self> {
+        EntryId::from_entry_bytes(entry_bytes)
+    }
+}
+
+impl Hash for FeedId {
+    fn hash<HS: Hasher>(&self, state: &mut HS) {
+        self.author.as_bytes().hash(state);
+        self.log_id.hash(state);
+    }
+}
+
+impl PartialOrd for FeedId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FeedId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.author.as_bytes(), self.log_id).cmp(&(other.author.as_bytes(), other.log_id))
+    }
+}
+
+impl Hash for EntryId {
+    fn hash<HS: Hasher>(&self, state: &mut HS) {
+        self.feed_id().hash(state);
+        self.seq_num.hash(state);
+        digest(&self.hash).hash(state);
+    }
+}
+
+impl fmt::Display for FeedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0u8; MAX_ID_SIZE];
+        let len = self.encode(&mut buf);
+        write_text(f, FEED_ID_PREFIX, &buf[..len])
+    }
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0u8; MAX_ID_SIZE];
+        let len = self.encode(&mut buf);
+        write_text(f, ENTRY_ID_PREFIX, &buf[..len])
+    }
+}
+
+impl FromStr for FeedId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with(FEED_URI_PREFIX) {
+            return FeedId::from_uri(s);
+        }
+
+        let mut buf = [0u8; MAX_ID_SIZE];
+        FeedId::decode(parse_text(s, FEED_ID_PREFIX, &mut buf)?)
+    }
+}
+
+impl FromStr for EntryId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with(ENTRY_URI_PREFIX) {
+            return EntryId::from_uri(s);
+        }
+
+        let mut buf = [0u8; MAX_ID_SIZE];
+        EntryId::decode(parse_text(s, ENTRY_ID_PREFIX, &mut buf)?)
+    }
+}
+
+/// The `bamboo:` URI form of a [FeedId], returned by [FeedId::uri].
+#[derive(Debug, Clone, Copy)]
+pub struct FeedIdUri<'a>(&'a FeedId);
+
+impl<'a> fmt::Display for FeedIdUri<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(FEED_URI_PREFIX)?;
+        write_hex(f, self.0.author.as_bytes())?;
+        write!(f, "/{}", self.0.log_id)
+    }
+}
+
+/// The `bamboo:` URI form of an [EntryId], returned by [EntryId::uri].
+#[derive(Debug, Clone, Copy)]
+pub struct EntryIdUri<'a>(&'a EntryId);
+
+impl<'a> fmt::Display for EntryIdUri<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = self.0;
+        f.write_str(ENTRY_URI_PREFIX)?;
+        write_hex(f, id.author.as_bytes())?;
+        write!(f, "/{}/{}/", id.log_id, id.seq_num)?;
+
+        let mut hash_bytes = [0u8; MAX_HASH_ENCODING_SIZE];
+        let len = encode_hash(&id.hash, &mut hash_bytes).map_err(|_| fmt::Error)?;
+        write_hex(f, &hash_bytes[..len])
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod serde_impls {
+    use super::{EntryId, FeedId};
+    use core::fmt;
+    use core::marker::PhantomData;
+    use core::str::FromStr;
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+
+    struct FromStrVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: FromStr> Visitor<'de> for FromStrVisitor<T>
+    where
+        T::Err: fmt::Display,
+    {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a bamboo id string")
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+            T::from_str(s).map_err(E::custom)
+        }
+    }
+
+    impl Serialize for FeedId {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for FeedId {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(FromStrVisitor(PhantomData))
+        }
+    }
+
+    impl Serialize for EntryId {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for EntryId {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(FromStrVisitor(PhantomData))
+        }
+    }
+}
+
+fn checksum(prefix: &str, bytes: &[u8]) -> [u8; CHECKSUM_SIZE] {
+    let mut buf = [0u8; MAX_PREFIX_SIZE + MAX_ID_SIZE];
+    buf[..prefix.len()].copy_from_slice(prefix.as_bytes());
+    buf[prefix.len()..prefix.len() + bytes.len()].copy_from_slice(bytes);
+
+    let mut checksum = [0u8; CHECKSUM_SIZE];
+    checksum
+        .copy_from_slice(&blake3(&buf[..prefix.len() + bytes.len()]).as_bytes()[..CHECKSUM_SIZE]);
+    checksum
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+}
+
+fn write_text(f: &mut fmt::Formatter<'_>, prefix: &str, bytes: &[u8]) -> fmt::Result {
+    f.write_str(prefix)?;
+    write_hex(f, bytes)?;
+    write_hex(f, &checksum(prefix, bytes))
+}
+
+/// Check the prefix and checksum of the text form `s`, returning the bytes of the id.
+fn parse_text<'a>(s: &str, prefix: &'static str, buf: &'a mut [u8]) -> Result<&'a [u8]> {
+    let hex = strip_prefix(s, prefix)?;
+
+    ensure!(
+        hex.len() % 2 == 0 && hex.len() <= buf.len() * 2,
+        IdInvalidLength
+    );
+    let len = hex.len() / 2;
+    ensure!(len > CHECKSUM_SIZE, IdInvalidLength);
+
+    hex::decode_to_slice(hex, &mut buf[..len]).map_err(|_| Error::IdInvalidHex)?;
+
+    let (bytes, expected_checksum) = buf[..len].split_at(len - CHECKSUM_SIZE);
+    ensure!(
+        checksum(prefix, bytes) == expected_checksum,
+        IdInvalidChecksum
+    );
+
+    Ok(bytes)
+}
+
+fn strip_prefix<'a>(s: &'a str, prefix: &'static str) -> Result<&'a str> {
+    s.strip_prefix(prefix)
+        .ok_or(Error::IdInvalidPrefix { prefix })
+}
+
+fn decode_author(bytes: &[u8]) -> Result<(PublicKey, &[u8])> {
+    ensure!(bytes.len() >= PUBLIC_KEY_LENGTH, IdInvalidLength);
+    let (author_bytes, remaining_bytes) = bytes.split_at(PUBLIC_KEY_LENGTH);
+    let author = PublicKey::from_bytes(author_bytes).map_err(|_| Error::IdInvalidAuthor)?;
+    Ok((author, remaining_bytes))
+}
+
+fn parse_uri_author(hex: Option<&str>) -> Result<PublicKey> {
+    let hex = hex.ok_or(Error::IdInvalidAuthor)?;
+    ensure!(hex.len() == PUBLIC_KEY_LENGTH * 2, IdInvalidAuthor);
+
+    let mut bytes = [0u8; PUBLIC_KEY_LENGTH];
+    hex::decode_to_slice(hex, &mut bytes).map_err(|_| Error::IdInvalidAuthor)?;
+    PublicKey::from_bytes(&bytes).map_err(|_| Error::IdInvalidAuthor)
+}
+
+fn parse_uri_u64(s: Option<&str>) -> Option<u64> {
+    // Only plain digits without leading zeros, so each number has a single URI form.
+    s.filter(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+        .filter(|s| *s == "0" || !s.starts_with('0'))
+        .and_then(|s| s.parse().ok())
+}
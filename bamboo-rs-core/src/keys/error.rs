@@ -0,0 +1,14 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Invalid seed phrase: {}", reason))]
+    InvalidSeedPhrase { reason: bip39::Error },
+    #[snafu(display("Entropy must be 16, 20, 24, 28 or 32 bytes, was {} bytes", length))]
+    InvalidEntropyLength { length: usize },
+    #[snafu(display("Derivation index must be less than 2^31, was {}", index))]
+    InvalidDerivationIndex { index: u32 },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,251 @@
+//! Back up keys as a seed phrase and derive a key pair per log from it.
+//!
+//! A [SeedPhrase] is a [BIP-39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+//! mnemonic. Its seed is the root of a
+//! [SLIP-0010](https://github.com/satoshilabs/slips/blob/master/slip-0010.md) ed25519 key tree,
+//! where every key is derived with hardened derivation.
+//!
+//! Key pairs are derived along the path returned by [derivation_path]:
+//!
+//! `m / 44' / BAMBOO_COIN_TYPE' / purpose' / log_id[63..62]' / log_id[61..31]' / log_id[30..0]'`
+//!
+//! The log id is split over three indices because a hardened index only has 31 bits. Restoring
+//! the same phrase with the same passphrase derives the same key pairs.
+//!
+//! ```
+//! use bamboo_rs_core_ed25519_yasmf::keys::{KeyPurpose, SeedPhrase};
+//!
+//! let phrase = SeedPhrase::from_entropy(&[7u8; 32]).unwrap();
+//! let words = phrase.to_string();
+//!
+//! let root = phrase.root_key("");
+//! let key_pair = root.derive_key_pair(KeyPurpose::FEED, 0);
+//!
+//! // On a new device.
+//! let restored = SeedPhrase::parse(&words).unwrap().root_key("");
+//! assert_eq!(restored.derive_key_pair(KeyPurpose::FEED, 0).public, key_pair.public);
+//! ```
+use bip39::Mnemonic;
+use core::fmt;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha512;
+use snafu::ensure;
+use zeroize::Zeroize;
+
+pub mod error;
+pub use error::*;
+
+/// Hardened indices start at 2^31.
+pub const HARDENED_OFFSET: u32 = 1 << 31;
+/// The BIP-44 purpose index.
+pub const BIP44_PURPOSE: u32 = 44;
+/// The coin type index used for bamboo keys. It isn't registered in SLIP-0044.
+pub const BAMBOO_COIN_TYPE: u32 = 0x6261_6d62;
+/// The number of indices in a path returned by [derivation_path].
+pub const DERIVATION_PATH_LENGTH: usize = 6;
+/// The number of bytes in a BIP-39 seed.
+pub const SEED_SIZE: usize = 64;
+
+const ED25519_CURVE_KEY: &[u8] = b"ed25519 seed";
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// A BIP-39 mnemonic seed phrase.
+///
+/// [Display] writes the words separated by spaces.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SeedPhrase {
+    mnemonic: Mnemonic,
+}
+
+impl SeedPhrase {
+    /// A phrase for `entropy`, which must be 16, 20, 24, 28 or 32 bytes. 32 bytes of entropy is a
+    /// 24 word phrase.
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self> {
+        ensure!(
+            matches!(entropy.len(), 16 | 20 | 24 | 28 | 32),
+            InvalidEntropyLength {
+                length: entropy.len()
+            }
+        );
+
+        Mnemonic::from_entropy(entropy)
+            .map(|mnemonic| SeedPhrase { mnemonic })
+            .map_err(|reason| Error::InvalidSeedPhrase { reason })
+    }
+
+    /// Parse an english phrase, checking its checksum.
+    ///
+    /// Without the `std` feature `phrase` must already be unicode NFKD normalized.
+    pub fn parse(phrase: &str) -> Result<Self> {
+        #[cfg(feature = "std")]
+        let mnemonic = Mnemonic::parse(phrase);
+        #[cfg(not(feature = "std"))]
+        let mnemonic = Mnemonic::parse_normalized(phrase);
+
+        mnemonic
+            .map(|mnemonic| SeedPhrase { mnemonic })
+            .map_err(|reason| Error::InvalidSeedPhrase { reason })
+    }
+
+    /// The words of the phrase.
+    pub fn words(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.mnemonic.words()
+    }
+
+    /// The BIP-39 seed of the phrase, stretched with `passphrase`. Use `""` for no passphrase.
+    ///
+    /// Without the `std` feature `passphrase` must already be unicode NFKD normalized.
+    pub fn to_seed(&self, passphrase: &str) -> [u8; SEED_SIZE] {
+        #[cfg(feature = "std")]
+        return self.mnemonic.to_seed(passphrase);
+        #[cfg(not(feature = "std"))]
+        return self.mnemonic.to_seed_normalized(passphrase);
+    }
+
+    /// The root of the key tree for `passphrase`.
+    ///
+    /// Computing the seed is deliberately slow, so keep the root key to derive more than one key
+    /// pair.
+    pub fn root_key(&self, passphrase: &str) -> ExtendedKey {
+        let mut seed = self.to_seed(passphrase);
+        let root = ExtendedKey::from_seed(&seed);
+        seed.zeroize();
+        root
+    }
+}
+
+impl fmt::Display for SeedPhrase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.mnemonic.fmt(f)
+    }
+}
+
+impl fmt::Debug for SeedPhrase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SeedPhrase(..)")
+    }
+}
+
+/// What a derived key is used for. Keys with different purposes are unrelated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyPurpose(u32);
+
+impl KeyPurpose {
+    /// Keys that author feeds.
+    pub const FEED: KeyPurpose = KeyPurpose(0);
+
+    /// A purpose for application specific keys. `index` must be less than 2^31.
+    pub fn new(index: u32) -> Result<Self> {
+        ensure!(index < HARDENED_OFFSET, InvalidDerivationIndex { index });
+        Ok(KeyPurpose(index))
+    }
+
+    /// The index of the purpose in the derivation path.
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+/// The unhardened derivation path of the key pair for `purpose` and `log_id`.
+pub fn derivation_path(purpose: KeyPurpose, log_id: u64) -> [u32; DERIVATION_PATH_LENGTH] {
+    let mask = u64::from(HARDENED_OFFSET - 1);
+
+    [
+        BIP44_PURPOSE,
+        BAMBOO_COIN_TYPE,
+        purpose.0,
+        (log_id >> 62) as u32,
+        ((log_id >> 31) & mask) as u32,
+        (log_id & mask) as u32,
+    ]
+}
+
+/// A SLIP-0010 ed25519 extended private key: a secret key and a chain code.
+///
+/// Both are zeroed when the key is dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedKey {
+    secret: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    /// The master key for `seed`.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        ExtendedKey::from_hmac(ED25519_CURVE_KEY, &[seed])
+    }
+
+    /// Derive the hardened child at `index`, which must be less than 2^31.
+    pub fn derive_child(&self, index: u32) -> Result<Self> {
+        ensure!(index < HARDENED_OFFSET, InvalidDerivationIndex { index });
+
+        let hardened = (index | HARDENED_OFFSET).to_be_bytes();
+        Ok(ExtendedKey::from_hmac(
+            &self.chain_code,
+            &[&[0u8], &self.secret, &hardened],
+        ))
+    }
+
+    /// Derive the hardened descendant along `path`.
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self> {
+        path.iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+
+    /// Derive the key pair for `purpose` and `log_id`.
+    pub fn derive_key_pair(&self, purpose: KeyPurpose, log_id: u64) -> Keypair {
+        // Every index in the path is less than 2^31.
+        self.derive_path(&derivation_path(purpose, log_id))
+            .expect("derivation path is valid")
+            .to_key_pair()
+    }
+
+    /// The chain code.
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// The secret key.
+    pub fn to_secret_key(&self) -> SecretKey {
+        // Any 32 bytes are a valid secret key.
+        SecretKey::from_bytes(&self.secret).expect("secret is 32 bytes")
+    }
+
+    /// The key pair of the secret key.
+    pub fn to_key_pair(&self) -> Keypair {
+        let secret = self.to_secret_key();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn from_hmac(key: &[u8], data: &[&[u8]]) -> Self {
+        let mut mac = HmacSha512::new_from_slice(key).expect("hmac takes keys of any length");
+        for bytes in data {
+            mac.update(bytes);
+        }
+        let output = mac.finalize().into_bytes();
+
+        let mut extended_key = ExtendedKey {
+            secret: [0u8; 32],
+            chain_code: [0u8; 32],
+        };
+        extended_key.secret.copy_from_slice(&output[..32]);
+        extended_key.chain_code.copy_from_slice(&output[32..]);
+        extended_key
+    }
+}
+
+impl Drop for ExtendedKey {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+impl fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ExtendedKey(..)")
+    }
+}
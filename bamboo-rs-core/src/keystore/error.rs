@@ -0,0 +1,28 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("A keystore is {} bytes, was {} bytes", expected, actual))]
+    InvalidKeystoreLength { expected: usize, actual: usize },
+    #[snafu(display("Not a keystore"))]
+    InvalidKeystoreMagic,
+    #[snafu(display("Unsupported keystore version: {}", version))]
+    UnsupportedKeystoreVersion { version: u8 },
+    #[snafu(display("Invalid keystore kdf params"))]
+    InvalidKdfParams,
+    #[snafu(display("Could not derive the keystore key from the password"))]
+    DeriveKeystoreKey,
+    #[snafu(display("Could not encrypt the secret key"))]
+    EncryptSecretKey,
+    #[snafu(display(
+        "Could not decrypt the secret key, the password is wrong or the keystore is corrupt"
+    ))]
+    DecryptSecretKey,
+    #[snafu(display("Invalid public key in keystore"))]
+    InvalidKeystorePublicKey,
+    #[snafu(display("The decrypted secret key does not match the public key"))]
+    KeystoreKeysDoNotMatch,
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,267 @@
+//! Store a key pair in a file encrypted with a password.
+//!
+//! A keystore is [KEYSTORE_SIZE] bytes:
+//!
+//! | bytes | field |
+//! |---|---|
+//! | 4 | [KEYSTORE_MAGIC] |
+//! | 1 | version, [KEYSTORE_VERSION] |
+//! | 12 | argon2id memory in KiB, iterations and parallelism, as little endian u32s |
+//! | 16 | argon2id salt |
+//! | 24 | XChaCha20-Poly1305 nonce |
+//! | 32 | ed25519 public key |
+//! | 48 | the encrypted ed25519 secret key and its Poly1305 tag |
+//!
+//! The encryption key is derived from the password with argon2id, and every byte before the
+//! encrypted secret key is authenticated as associated data. The public key can be read without
+//! the password.
+//!
+//! ```
+//! use bamboo_rs_core_ed25519_yasmf::keystore::{KdfParams, Keystore};
+//! use bamboo_rs_core_ed25519_yasmf::Keypair;
+//! use rand::rngs::OsRng;
+//!
+//! let mut csprng = OsRng {};
+//! let key_pair = Keypair::generate(&mut csprng);
+//!
+//! let keystore =
+//!     Keystore::encrypt(&key_pair, b"hunter2", KdfParams::default(), &mut csprng).unwrap();
+//! let bytes = keystore.to_bytes();
+//!
+//! let keystore = Keystore::from_bytes(&bytes).unwrap();
+//! assert_eq!(keystore.public_key(), key_pair.public);
+//! assert_eq!(keystore.decrypt(b"hunter2").unwrap().public, key_pair.public);
+//! assert!(keystore.decrypt(b"hunter3").is_err());
+//! ```
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{Key, Tag, XChaCha20Poly1305, XNonce};
+use core::convert::TryInto;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH};
+use rand_core::{CryptoRng, RngCore};
+use snafu::ensure;
+use zeroize::Zeroizing;
+
+pub mod error;
+pub use error::*;
+
+/// The first bytes of every keystore.
+pub const KEYSTORE_MAGIC: [u8; 4] = *b"BKS\0";
+/// The version of the keystore format this module reads and writes.
+pub const KEYSTORE_VERSION: u8 = 1;
+/// The number of bytes in an argon2id salt.
+pub const SALT_SIZE: usize = 16;
+/// The number of bytes in an XChaCha20-Poly1305 nonce.
+pub const NONCE_SIZE: usize = 24;
+/// The number of bytes in a Poly1305 tag.
+pub const TAG_SIZE: usize = 16;
+/// The most memory a keystore can ask argon2id to use, in KiB.
+///
+/// Keystores are read from files that may not be trusted, so this and [MAX_ITERATIONS] bound
+/// how much work loading one can cause.
+pub const MAX_MEMORY_KIB: u32 = 256 * 1024;
+/// The most argon2id passes a keystore can ask for.
+pub const MAX_ITERATIONS: u32 = 16;
+
+const PARAMS_OFFSET: usize = KEYSTORE_MAGIC.len() + 1;
+const SALT_OFFSET: usize = PARAMS_OFFSET + 12;
+const NONCE_OFFSET: usize = SALT_OFFSET + SALT_SIZE;
+const PUBLIC_KEY_OFFSET: usize = NONCE_OFFSET + NONCE_SIZE;
+const SECRET_KEY_OFFSET: usize = PUBLIC_KEY_OFFSET + PUBLIC_KEY_LENGTH;
+const TAG_OFFSET: usize = SECRET_KEY_OFFSET + SECRET_KEY_LENGTH;
+
+/// The number of bytes in a keystore.
+pub const KEYSTORE_SIZE: usize = TAG_OFFSET + TAG_SIZE;
+
+/// The cost of deriving the encryption key from the password with argon2id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB. At least `8 * parallelism` and at most [MAX_MEMORY_KIB].
+    pub memory_kib: u32,
+    /// Number of passes. At least 1 and at most [MAX_ITERATIONS].
+    pub iterations: u32,
+    /// Degree of parallelism. At least 1.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// The argon2 crate's recommended params, 19 MiB of memory and 2 iterations.
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    fn to_argon2(self) -> Result<Argon2<'static>> {
+        ensure!(
+            self.memory_kib <= MAX_MEMORY_KIB && self.iterations <= MAX_ITERATIONS,
+            InvalidKdfParams
+        );
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(SECRET_KEY_LENGTH),
+        )
+        .map_err(|_| Error::InvalidKdfParams)?;
+
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// An ed25519 key pair with the secret key encrypted by a password.
+///
+/// The decrypted [Keypair] zeroes its secret key when it's dropped, as do the intermediate keys
+/// used to encrypt and decrypt it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    bytes: [u8; KEYSTORE_SIZE],
+}
+
+impl Keystore {
+    /// Encrypt `key_pair` with `password`, using `csprng` for the salt and nonce.
+    pub fn encrypt<R>(
+        key_pair: &Keypair,
+        password: &[u8],
+        params: KdfParams,
+        csprng: &mut R,
+    ) -> Result<Self>
+    where
+        R: CryptoRng + RngCore,
+    {
+        let mut bytes = [0u8; KEYSTORE_SIZE];
+        bytes[..PARAMS_OFFSET - 1].copy_from_slice(&KEYSTORE_MAGIC);
+        bytes[PARAMS_OFFSET - 1] = KEYSTORE_VERSION;
+        bytes[PARAMS_OFFSET..PARAMS_OFFSET + 4].copy_from_slice(&params.memory_kib.to_le_bytes());
+        bytes[PARAMS_OFFSET + 4..PARAMS_OFFSET + 8]
+            .copy_from_slice(&params.iterations.to_le_bytes());
+        bytes[PARAMS_OFFSET + 8..SALT_OFFSET].copy_from_slice(&params.parallelism.to_le_bytes());
+        csprng.fill_bytes(&mut bytes[SALT_OFFSET..PUBLIC_KEY_OFFSET]);
+        bytes[PUBLIC_KEY_OFFSET..SECRET_KEY_OFFSET].copy_from_slice(key_pair.public.as_bytes());
+
+        let cipher = cipher(&bytes, password)?;
+        let (header, rest) = bytes.split_at_mut(SECRET_KEY_OFFSET);
+        let (secret_key, tag) = rest.split_at_mut(SECRET_KEY_LENGTH);
+        secret_key.copy_from_slice(key_pair.secret.as_bytes());
+
+        let nonce = XNonce::from_slice(&header[NONCE_OFFSET..PUBLIC_KEY_OFFSET]);
+        let computed_tag = cipher
+            .encrypt_in_place_detached(nonce, header, secret_key)
+            .map_err(|_| Error::EncryptSecretKey)?;
+        tag.copy_from_slice(&computed_tag);
+
+        Ok(Keystore { bytes })
+    }
+
+    /// Decrypt the key pair with `password`.
+    pub fn decrypt(&self, password: &[u8]) -> Result<Keypair> {
+        let public = self.public_key();
+        let cipher = cipher(&self.bytes, password)?;
+
+        let mut secret_key = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+        secret_key.copy_from_slice(&self.bytes[SECRET_KEY_OFFSET..TAG_OFFSET]);
+        cipher
+            .decrypt_in_place_detached(
+                XNonce::from_slice(&self.bytes[NONCE_OFFSET..PUBLIC_KEY_OFFSET]),
+                &self.bytes[..SECRET_KEY_OFFSET],
+                &mut secret_key[..],
+                Tag::from_slice(&self.bytes[TAG_OFFSET..]),
+            )
+            .map_err(|_| Error::DecryptSecretKey)?;
+
+        let secret = SecretKey::from_bytes(&secret_key[..]).map_err(|_| Error::DecryptSecretKey)?;
+        ensure!(PublicKey::from(&secret) == public, KeystoreKeysDoNotMatch);
+
+        Ok(Keypair { secret, public })
+    }
+
+    /// The public key, which can be read without the password.
+    pub fn public_key(&self) -> PublicKey {
+        // The public key was checked when the keystore was created.
+        PublicKey::from_bytes(&self.bytes[PUBLIC_KEY_OFFSET..SECRET_KEY_OFFSET])
+            .expect("keystore public key is valid")
+    }
+
+    /// The params used to derive the encryption key.
+    pub fn kdf_params(&self) -> KdfParams {
+        read_kdf_params(&self.bytes)
+    }
+
+    /// Read a keystore, checking its header but not the password.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        ensure!(
+            bytes.len() == KEYSTORE_SIZE,
+            InvalidKeystoreLength {
+                expected: KEYSTORE_SIZE,
+                actual: bytes.len()
+            }
+        );
+        ensure!(
+            bytes[..PARAMS_OFFSET - 1] == KEYSTORE_MAGIC,
+            InvalidKeystoreMagic
+        );
+        let version = bytes[PARAMS_OFFSET - 1];
+        ensure!(
+            version == KEYSTORE_VERSION,
+            UnsupportedKeystoreVersion { version }
+        );
+        PublicKey::from_bytes(&bytes[PUBLIC_KEY_OFFSET..SECRET_KEY_OFFSET])
+            .map_err(|_| Error::InvalidKeystorePublicKey)?;
+
+        read_kdf_params(bytes).to_argon2()?;
+
+        let mut keystore = Keystore {
+            bytes: [0u8; KEYSTORE_SIZE],
+        };
+        keystore.bytes.copy_from_slice(bytes);
+        Ok(keystore)
+    }
+
+    /// The encoded keystore.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Copy the encoded keystore.
+    pub fn to_bytes(&self) -> [u8; KEYSTORE_SIZE] {
+        self.bytes
+    }
+}
+
+impl AsRef<[u8]> for Keystore {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+fn read_kdf_params(bytes: &[u8]) -> KdfParams {
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes(
+            bytes[offset..offset + 4]
+                .try_into()
+                .expect("slice is 4 bytes"),
+        )
+    };
+
+    KdfParams {
+        memory_kib: read_u32(PARAMS_OFFSET),
+        iterations: read_u32(PARAMS_OFFSET + 4),
+        parallelism: read_u32(PARAMS_OFFSET + 8),
+    }
+}
+
+/// Derive the encryption key of the keystore in `bytes`, whose header must already be written.
+fn cipher(bytes: &[u8], password: &[u8]) -> Result<XChaCha20Poly1305> {
+    let argon2 = read_kdf_params(bytes).to_argon2()?;
+
+    let mut key = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+    argon2
+        .hash_password_into(password, &bytes[SALT_OFFSET..NONCE_OFFSET], &mut key[..])
+        .map_err(|_| Error::DeriveKeystoreKey)?;
+
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key[..])))
+}
@@ -0,0 +1,53 @@
+use crate::entry::publish::Error as PublishError;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(super)")]
+pub enum Error {
+    #[snafu(display("Bytes to decode had length of 0"))]
+    LegacyDecodeInputIsLengthZero,
+    #[snafu(display("Could not decode legacy author public key from bytes"))]
+    LegacyDecodeAuthor,
+    #[snafu(display("Could not decode legacy sequence number, error with varu64 encoding"))]
+    LegacyDecodeSeqNum,
+    #[snafu(display("Legacy entry sequence must be larger than 0"))]
+    LegacyDecodeSeqIsZero,
+    #[snafu(display("Could not decode legacy {} as a yamf blake2b hash", field))]
+    LegacyDecodeHash { field: &'static str },
+    #[snafu(display("Could not decode legacy payload size, error with varu64 encoding"))]
+    LegacyDecodePayloadSize,
+    #[snafu(display("Could not decode legacy signature"))]
+    LegacyDecodeSig,
+    #[snafu(display("Legacy entry signature is invalid"))]
+    LegacyInvalidSignature,
+    #[snafu(display(
+        "The payload hash encoded in the legacy entry does not match the payload provided"
+    ))]
+    LegacyPayloadHashDidNotMatch,
+    #[snafu(display("The payload length encoded in the legacy entry (expected) does not match the payload provided (actual). Expected: {}, actual: {}", expected, actual))]
+    LegacyPayloadLengthDidNotMatch { actual: usize, expected: u64 },
+    #[snafu(display(
+        "The lipmaa hash encoded in the legacy entry does not match the lipmaa entry provided"
+    ))]
+    LegacyLipmaaHashDoesNotMatch,
+    #[snafu(display("Lipmaa link required but not provided"))]
+    LegacyLipmaaLinkRequired,
+    #[snafu(display(
+        "The backlink hash encoded in the legacy entry does not match the backlink entry provided"
+    ))]
+    LegacyBacklinkHashDoesNotMatch,
+    #[snafu(display("Legacy linked entry author does not match the entry"))]
+    LegacyLinkFeedDoesNotMatch,
+    #[snafu(display("Legacy entry was published after the feed was declared ended"))]
+    LegacyPublishedAfterEndOfFeed,
+    #[snafu(display(
+        "Legacy entries must be migrated in order, expected seq_num {} but got {}",
+        expected,
+        actual
+    ))]
+    MigrateSeqNumNotInOrder { expected: u64, actual: u64 },
+    #[snafu(display("Could not publish the migrated entry: {}", source))]
+    MigratePublish { source: PublishError },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,159 @@
+use ed25519_dalek::{Keypair, PublicKey};
+use snafu::{ensure, ResultExt};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use super::error::*;
+use super::{decode, legacy_hash, verify_payload, LegacyHash};
+use crate::entry::publish::Publisher;
+#[cfg(feature = "alloc")]
+use crate::entry::MAX_ENTRY_SIZE;
+use crate::hash::{HashAlgorithm, OwnedYasmfHash};
+
+/// The hash a legacy entry had and the hash of the entry it was migrated to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashMapping {
+    pub seq_num: u64,
+    pub legacy_hash: LegacyHash,
+    pub hash: OwnedYasmfHash,
+}
+
+/// Re-publishes a legacy feed as a yasmf/blake3 feed, one entry at a time.
+///
+/// Each legacy entry is verified against its payload and the previous legacy entry before it's
+/// published, so entries must be migrated in order starting from seq_num 1. The new entries have
+/// the same seq_num, payload and `is_end_of_feed` as the legacy entries they replace but are
+/// signed by the migrator's key pair.
+#[derive(Debug)]
+pub struct LegacyMigrator {
+    publisher: Publisher,
+    previous: Option<Previous>,
+}
+
+#[derive(Debug)]
+struct Previous {
+    seq_num: u64,
+    hash: LegacyHash,
+    author: PublicKey,
+}
+
+impl LegacyMigrator {
+    /// Migrate into a new log with `log_id`.
+    pub fn new(key_pair: Keypair, log_id: u64) -> Self {
+        LegacyMigrator {
+            publisher: Publisher::new(key_pair, log_id),
+            previous: None,
+        }
+    }
+
+    /// Verify the next legacy entry and publish it to `out`.
+    ///
+    /// - `out`: A buffer to encode the new entry into. Must be >= MAX_ENTRY_SIZE.
+    /// - `legacy_entry`: The bytes of the next legacy entry.
+    /// - `payload`: The payload of the legacy entry.
+    ///
+    /// Returns the size of the new entry and how the hash of the entry changed.
+    pub fn migrate_entry(
+        &mut self,
+        out: &mut [u8],
+        legacy_entry: &[u8],
+        payload: &[u8],
+    ) -> Result<(usize, HashMapping)> {
+        let entry = decode(legacy_entry)?;
+        let expected = self
+            .previous
+            .as_ref()
+            .map_or(1, |previous| previous.seq_num + 1);
+
+        ensure!(
+            entry.seq_num == expected,
+            MigrateSeqNumNotInOrder {
+                expected,
+                actual: entry.seq_num
+            }
+        );
+
+        match (self.previous.as_ref(), entry.backlink) {
+            (Some(previous), Some(backlink)) => {
+                ensure!(entry.author == previous.author, LegacyLinkFeedDoesNotMatch);
+                ensure!(
+                    previous.hash[..] == *backlink,
+                    LegacyBacklinkHashDoesNotMatch
+                );
+            }
+            (None, None) => {}
+            _ => return LegacyBacklinkHashDoesNotMatch.fail(),
+        }
+
+        // The backlinks chain every entry back to the first, so the lipmaa links aren't needed.
+        verify_payload(&entry, payload)?;
+        entry.verify_signature()?;
+
+        let size = self
+            .publisher
+            .publish(out, payload, entry.is_end_of_feed)
+            .context(MigratePublish)?;
+
+        let hash = legacy_hash(legacy_entry);
+        self.previous = Some(Previous {
+            seq_num: entry.seq_num,
+            hash,
+            author: entry.author,
+        });
+
+        let mapping = HashMapping {
+            seq_num: entry.seq_num,
+            legacy_hash: hash,
+            hash: HashAlgorithm::default().hash(&out[..size]),
+        };
+
+        Ok((size, mapping))
+    }
+
+    /// The publisher for the new log, to carry on publishing after migrating.
+    pub fn into_publisher(self) -> Publisher {
+        self.publisher
+    }
+}
+
+/// A migrated feed.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// The new entries, in seq_num order.
+    pub entries: Vec<Vec<u8>>,
+    /// The hash mapping of each entry, in seq_num order.
+    pub hash_mappings: Vec<HashMapping>,
+}
+
+/// Migrate a whole legacy feed with a [LegacyMigrator].
+///
+/// `entries_and_payloads` must start from seq_num 1 and be in order.
+#[cfg(feature = "alloc")]
+pub fn migrate_feed<I, E, P>(
+    key_pair: Keypair,
+    log_id: u64,
+    entries_and_payloads: I,
+) -> Result<Migration>
+where
+    I: IntoIterator<Item = (E, P)>,
+    E: AsRef<[u8]>,
+    P: AsRef<[u8]>,
+{
+    let mut migrator = LegacyMigrator::new(key_pair, log_id);
+    let mut migration = Migration {
+        entries: Vec::new(),
+        hash_mappings: Vec::new(),
+    };
+    let mut out = [0u8; MAX_ENTRY_SIZE];
+
+    for (legacy_entry, payload) in entries_and_payloads {
+        let (size, mapping) =
+            migrator.migrate_entry(&mut out, legacy_entry.as_ref(), payload.as_ref())?;
+        migration.entries.push(out[..size].to_vec());
+        migration.hash_mappings.push(mapping);
+    }
+
+    Ok(migration)
+}
@@ -0,0 +1,240 @@
+//! Read feeds published by older versions of bamboo-rs and migrate them to yasmf feeds.
+//!
+//! Legacy entries are the entries of bamboo-rs 0.1.0-pre-25 and earlier, from before log_ids were
+//! added, like the example entry in `test_vectors/test_vectors.md`. They're the concatenation of:
+//!
+//! - the tag byte, `1` for an end of feed entry.
+//! - the payload hash, as a yamf hash: the varu64 id `1`, the varu64 digest length `64` and a 64
+//!   byte blake2b digest.
+//! - the payload size as a varu64.
+//! - the author, as a yamf signatory: the varu64 id `1`, the varu64 length `32` and an ed25519
+//!   public key.
+//! - the seq_num as a varu64.
+//! - the lipmaa link and backlink as yamf hashes, when the seq_num is larger than 1. The lipmaa
+//!   link is left out when it would duplicate the backlink.
+//! - the ed25519 signature of everything before it, prefixed with its varu64 length `64`.
+//!
+//! The example entry only covers the first entry of a feed, so the links are encoded as current
+//! entries encode them.
+//!
+//! Legacy entries can be decoded and verified but not published. Use [LegacyMigrator] to
+//! re-publish a legacy feed as a yasmf/blake3 feed.
+use blake2b_simd::blake2b;
+use core::convert::TryFrom;
+use ed25519_dalek::{PublicKey, Signature as DalekSignature, Verifier, PUBLIC_KEY_LENGTH};
+use snafu::{ensure, OptionExt};
+use varu64::decode as varu64_decode;
+
+use crate::entry::{is_lipmaa_required, MAX_VARU64_SIZE, TAG_BYTE_LENGTH};
+use crate::signature::ED25519_SIGNATURE_SIZE;
+
+pub mod error;
+pub mod migrate;
+pub use error::*;
+#[cfg(feature = "alloc")]
+pub use migrate::{migrate_feed, Migration};
+pub use migrate::{HashMapping, LegacyMigrator};
+
+/// The yamf hash id of blake2b.
+pub const LEGACY_HASH_ID: u64 = 1;
+/// The number of bytes in a legacy blake2b digest.
+pub const LEGACY_HASH_SIZE: usize = 64;
+/// The number of bytes a legacy yamf hash encodes to.
+pub const LEGACY_YAMF_HASH_SIZE: usize = LEGACY_HASH_SIZE + 2;
+/// The yamf signatory id of ed25519.
+pub const LEGACY_SIGNATORY_ID: u64 = 1;
+/// The number of bytes a legacy yamf signatory encodes to.
+pub const LEGACY_YAMF_SIGNATORY_SIZE: usize = PUBLIC_KEY_LENGTH + 2;
+
+/// The largest a legacy entry can be.
+pub const MAX_LEGACY_ENTRY_SIZE: usize = TAG_BYTE_LENGTH
+    + (LEGACY_YAMF_HASH_SIZE * 3)
+    + LEGACY_YAMF_SIGNATORY_SIZE
+    + (MAX_VARU64_SIZE * 2)
+    + 1
+    + ED25519_SIGNATURE_SIZE;
+
+/// A legacy blake2b digest.
+pub type LegacyHash = [u8; LEGACY_HASH_SIZE];
+
+/// The blake2b hash of `bytes`, as used by legacy entries.
+pub fn legacy_hash(bytes: &[u8]) -> LegacyHash {
+    let mut hash = [0u8; LEGACY_HASH_SIZE];
+    hash.copy_from_slice(blake2b(bytes).as_bytes());
+    hash
+}
+
+/// A decoded legacy entry, referencing the bytes it was decoded from.
+///
+/// Hashes are the 64 byte blake2b digests, without the yamf id and length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyEntry<'a> {
+    pub is_end_of_feed: bool,
+    pub payload_hash: &'a [u8],
+    pub payload_size: u64,
+    pub author: PublicKey,
+    pub seq_num: u64,
+    pub lipmaa_link: Option<&'a [u8]>,
+    pub backlink: Option<&'a [u8]>,
+    pub sig: &'a [u8],
+    signed_bytes: &'a [u8],
+}
+
+impl<'a> LegacyEntry<'a> {
+    /// Verify the signature of the entry is valid.
+    pub fn verify_signature(&self) -> Result<()> {
+        let sig = DalekSignature::try_from(self.sig).map_err(|_| Error::LegacyDecodeSig)?;
+
+        self.author
+            .verify(self.signed_bytes, &sig)
+            .map_err(|_| Error::LegacyInvalidSignature)
+    }
+}
+
+/// Try and decode `bytes` as a legacy entry. Any bytes after the entry are ignored.
+pub fn decode(bytes: &[u8]) -> Result<LegacyEntry<'_>> {
+    decode_with_remainder(bytes).map(|(entry, _)| entry)
+}
+
+/// Try and decode a legacy entry from the start of `bytes`, returning it and the bytes that
+/// follow it.
+pub fn decode_with_remainder(bytes: &[u8]) -> Result<(LegacyEntry<'_>, &[u8])> {
+    ensure!(!bytes.is_empty(), LegacyDecodeInputIsLengthZero);
+
+    let is_end_of_feed = bytes[0] == 1;
+
+    let (payload_hash, remaining_bytes) = decode_hash(&bytes[1..], "payload hash")?;
+    let (payload_size, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::LegacyDecodePayloadSize)?;
+
+    let (signatory_id, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::LegacyDecodeAuthor)?;
+    let (signatory_size, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::LegacyDecodeAuthor)?;
+    ensure!(
+        signatory_id == LEGACY_SIGNATORY_ID
+            && signatory_size == PUBLIC_KEY_LENGTH as u64
+            && remaining_bytes.len() >= PUBLIC_KEY_LENGTH,
+        LegacyDecodeAuthor
+    );
+    let (author, remaining_bytes) = remaining_bytes.split_at(PUBLIC_KEY_LENGTH);
+    let author = PublicKey::from_bytes(author).map_err(|_| Error::LegacyDecodeAuthor)?;
+
+    let (seq_num, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::LegacyDecodeSeqNum)?;
+    ensure!(seq_num > 0, LegacyDecodeSeqIsZero);
+
+    let (backlink, lipmaa_link, remaining_bytes) = match (seq_num, is_lipmaa_required(seq_num)) {
+        (1, _) => (None, None, remaining_bytes),
+        (_, true) => {
+            let (lipmaa_link, remaining_bytes) = decode_hash(remaining_bytes, "lipmaa link")?;
+            let (backlink, remaining_bytes) = decode_hash(remaining_bytes, "backlink")?;
+            (Some(backlink), Some(lipmaa_link), remaining_bytes)
+        }
+        (_, false) => {
+            let (backlink, remaining_bytes) = decode_hash(remaining_bytes, "backlink")?;
+            (Some(backlink), None, remaining_bytes)
+        }
+    };
+
+    let signed_bytes = &bytes[..bytes.len() - remaining_bytes.len()];
+    let (sig_size, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::LegacyDecodeSig)?;
+    ensure!(
+        sig_size == ED25519_SIGNATURE_SIZE as u64
+            && remaining_bytes.len() >= ED25519_SIGNATURE_SIZE,
+        LegacyDecodeSig
+    );
+    let (sig, remaining_bytes) = remaining_bytes.split_at(ED25519_SIGNATURE_SIZE);
+
+    let entry = LegacyEntry {
+        is_end_of_feed,
+        payload_hash,
+        payload_size,
+        author,
+        seq_num,
+        lipmaa_link,
+        backlink,
+        sig,
+        signed_bytes,
+    };
+
+    Ok((entry, remaining_bytes))
+}
+
+/// Verify `entry_bytes` is a valid legacy entry.
+///
+/// Checks the same things as [verify](crate::verify), using legacy blake2b hashes. The
+/// `payload`, `lipmaa_link` and `backlink` can be omitted in the same cases.
+pub fn verify(
+    entry_bytes: &[u8],
+    payload: Option<&[u8]>,
+    lipmaa_link: Option<&[u8]>,
+    backlink: Option<&[u8]>,
+) -> Result<()> {
+    let entry = decode(entry_bytes)?;
+
+    if let Some(payload) = payload {
+        verify_payload(&entry, payload)?;
+    }
+
+    if let Some(entry_lipmaa) = entry.lipmaa_link {
+        let lipmaa_link = lipmaa_link.context(LegacyLipmaaLinkRequired)?;
+        ensure!(
+            legacy_hash(lipmaa_link)[..] == *entry_lipmaa,
+            LegacyLipmaaHashDoesNotMatch
+        );
+        verify_same_feed(&entry, &decode(lipmaa_link)?)?;
+    }
+
+    if let (Some(entry_backlink), Some(backlink)) = (entry.backlink, backlink) {
+        let backlink_entry = decode(backlink)?;
+        verify_same_feed(&entry, &backlink_entry)?;
+        ensure!(
+            !backlink_entry.is_end_of_feed,
+            LegacyPublishedAfterEndOfFeed
+        );
+        ensure!(
+            legacy_hash(backlink)[..] == *entry_backlink,
+            LegacyBacklinkHashDoesNotMatch
+        );
+    }
+
+    entry.verify_signature()
+}
+
+fn verify_payload(entry: &LegacyEntry, payload: &[u8]) -> Result<()> {
+    ensure!(
+        legacy_hash(payload)[..] == *entry.payload_hash,
+        LegacyPayloadHashDidNotMatch
+    );
+    ensure!(
+        payload.len() as u64 == entry.payload_size,
+        LegacyPayloadLengthDidNotMatch {
+            actual: payload.len(),
+            expected: entry.payload_size
+        }
+    );
+    Ok(())
+}
+
+fn verify_same_feed(entry: &LegacyEntry, link: &LegacyEntry) -> Result<()> {
+    ensure!(entry.author == link.author, LegacyLinkFeedDoesNotMatch);
+    Ok(())
+}
+
+fn decode_hash<'a>(bytes: &'a [u8], field: &'static str) -> Result<(&'a [u8], &'a [u8])> {
+    let (id, remaining_bytes) =
+        varu64_decode(bytes).map_err(|_| Error::LegacyDecodeHash { field })?;
+    let (size, remaining_bytes) =
+        varu64_decode(remaining_bytes).map_err(|_| Error::LegacyDecodeHash { field })?;
+
+    ensure!(
+        id == LEGACY_HASH_ID
+            && size == LEGACY_HASH_SIZE as u64
+            && remaining_bytes.len() >= LEGACY_HASH_SIZE,
+        LegacyDecodeHash { field }
+    );
+
+    Ok(remaining_bytes.split_at(LEGACY_HASH_SIZE))
+}
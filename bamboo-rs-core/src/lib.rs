@@ -12,6 +12,19 @@
 //!
 //! `bamboo-rs-core` exposes low level functions and types which can be built with `no_std`.
 //!
+//! ## Features
+//!
+//! - `alloc`: for `no_std` targets with an allocator. Adds [verify_batch] and the other batch
+//!   verification functions, using dalek's deterministic signature batch verification, and serde
+//!   support for [Entry] and [Signature].
+//! - `std` (default): implies `alloc`. Batch verification also uses rayon, and adds the
+//!   `io::Write` based encoders.
+//! - `tokio-codec`: implies `std`. Adds a `tokio_util::codec` adapter for [framing].
+//! - `keys`: adds the `keys` module, to back up keys as a seed phrase and derive a key pair per
+//!   log from it.
+//! - `keystore`: implies `alloc`. Adds the `keystore` module, to store a key pair in a file
+//!   encrypted with a password.
+//!
 //! ## Example
 //!
 //! [publish](publish()), [verify](verify()) and [decode](decode()) the first [Entry] in a bamboo log.
@@ -57,21 +70,41 @@
 //! ```
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
 extern crate static_assertions;
 
 pub mod entry;
+#[cfg(feature = "alloc")]
+pub mod framing;
+pub mod hash;
+pub mod id;
+#[cfg(feature = "keys")]
+pub mod keys;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+pub mod legacy;
 pub mod signature;
 pub mod yasmf_hash;
 
 mod util;
 
-pub use crate::yasmf_hash::{YasmfHash, BLAKE3_OUT_LEN, BLAKE3_HASH_SIZE};
+pub use crate::hash::HashAlgorithm;
+pub use crate::yasmf_hash::{YasmfHash, BLAKE3_HASH_SIZE, BLAKE3_OUT_LEN};
 pub use ed25519_dalek::{Keypair, PublicKey, SecretKey, SignatureError};
-#[cfg(feature = "std")]
-pub use entry::verify::verify_batch;
-pub use entry::{decode, publish, verify, Entry};
+#[cfg(feature = "alloc")]
+pub use entry::publish::{publish_batch, PublishState};
+pub use entry::publish::{Publisher, PublisherState};
+#[cfg(feature = "alloc")]
+pub use entry::verify::{
+    verify_batch, verify_batch_iter, verify_batch_iter_with_policy,
+    verify_batch_with_linked_entries, verify_batch_with_policy,
+};
+pub use entry::{decode, publish, verify, verify_with_policy, Entry, OwnedEntry, VerifyPolicy};
+pub use id::{EntryId, FeedId};
 pub use lipmaa_link::lipmaa;
 pub use signature::{Signature, ED25519_SIGNATURE_SIZE};
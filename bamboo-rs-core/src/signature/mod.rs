@@ -10,8 +10,10 @@ const_assert_eq!(ed25519_sig_size; ED25519_SIGNATURE_SIZE, SIGNATURE_LENGTH);
 /// The maximum number of bytes this will use.
 pub const MAX_SIGNATURE_SIZE: usize = ED25519_SIGNATURE_SIZE;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 use crate::util::hex_serde::{hex_from_bytes, vec_from_hex};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use snafu::ensure;
 
@@ -21,10 +23,10 @@ pub use error::*;
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Signature<B: Borrow<[u8]>>(
     #[cfg_attr(
-        feature = "std",
+        feature = "alloc",
         serde(serialize_with = "hex_from_bytes", deserialize_with = "vec_from_hex")
     )]
-    #[cfg_attr(feature = "std", serde(bound(deserialize = "B: From<Vec<u8>>")))]
+    #[cfg_attr(feature = "alloc", serde(bound(deserialize = "B: From<Vec<u8>>")))]
     pub B,
 );
 
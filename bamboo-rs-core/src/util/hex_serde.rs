@@ -1,9 +1,12 @@
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use ed25519_dalek::PublicKey as DalekPublicKey;
 use serde::de::Error;
-use serde::{Deserialize, Deserializer, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::yasmf_hash::YasmfHash;
 
-#[cfg(feature = "std")]
 pub fn serialize_pub_key<S>(public_key: &DalekPublicKey, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -16,7 +19,6 @@ where
     }
 }
 
-#[cfg(feature = "std")]
 pub fn deserialize_pub_key<'de, D>(deserializer: D) -> Result<DalekPublicKey, D::Error>
 where
     D: Deserializer<'de>,
@@ -59,3 +61,53 @@ where
         serializer.serialize_bytes(bytes.borrow())
     }
 }
+
+/// Serde definition for [YasmfHash] that doesn't depend on the `std` feature of `yasmf-hash`.
+///
+/// Produces the same encoding as `yasmf-hash` does with `std`: the hash bytes are hex encoded
+/// for human readable formats.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "YasmfHash")]
+pub enum YasmfHashDef<T: Borrow<[u8]> + Clone> {
+    #[serde(serialize_with = "hex_from_bytes", deserialize_with = "vec_from_hex")]
+    #[serde(bound(deserialize = "T: From<Vec<u8>>"))]
+    Blake3(T),
+}
+
+struct SerializeYasmfHash<'a, T: Borrow<[u8]> + Clone>(&'a YasmfHash<T>);
+
+impl<'a, T: Borrow<[u8]> + Clone> Serialize for SerializeYasmfHash<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        YasmfHashDef::serialize(self.0, serializer)
+    }
+}
+
+#[derive(Deserialize)]
+struct DeserializeYasmfHash<T: Borrow<[u8]> + Clone>(
+    #[serde(with = "YasmfHashDef", bound(deserialize = "T: From<Vec<u8>>"))] YasmfHash<T>,
+);
+
+pub fn serialize_option_yasmf_hash<S, T>(
+    hash: &Option<YasmfHash<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Borrow<[u8]> + Clone,
+{
+    match hash {
+        Some(hash) => serializer.serialize_some(&SerializeYasmfHash(hash)),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_option_yasmf_hash<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<YasmfHash<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Borrow<[u8]> + Clone + From<Vec<u8>>,
+{
+    let hash: Option<DeserializeYasmfHash<T>> = Deserialize::deserialize(deserializer)?;
+    Ok(hash.map(|DeserializeYasmfHash(hash)| hash))
+}
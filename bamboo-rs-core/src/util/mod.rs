@@ -1,2 +1,2 @@
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 pub mod hex_serde;
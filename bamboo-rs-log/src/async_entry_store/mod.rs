@@ -0,0 +1,108 @@
+pub mod sync_entry_store;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::future::Future;
+use snafu::AsErrorSource;
+pub use sync_entry_store::SyncEntryStore;
+
+/// An [EntryStore](crate::EntryStore) whose methods return futures, for stores that do I/O.
+///
+/// Entries are returned as owned bytes rather than borrowed from the store. The futures don't
+/// depend on any particular runtime, and [SyncEntryStore] turns any sync store into one. They're
+/// `Send`, so they can be spawned onto multi threaded executors.
+pub trait AsyncEntryStore: Send + Sync {
+    type Error: Display + Debug + AsErrorSource + Send;
+
+    fn get_last_seq(&self) -> impl Future<Output = Option<u64>> + Send;
+    fn get_entry(
+        &self,
+        seq_num: u64,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Self::Error>> + Send;
+    fn get_last_entry(&self) -> impl Future<Output = Result<Option<Vec<u8>>, Self::Error>> + Send {
+        async move {
+            match self.get_last_seq().await {
+                Some(seq_num) => self.get_entry(seq_num).await,
+                None => Ok(None),
+            }
+        }
+    }
+    fn add_entry(
+        &mut self,
+        entry: &[u8],
+        seq_num: u64,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+    /// Add `(seq_num, entry)` pairs in order. Stores that can add them all or none of them.
+    fn add_entries(
+        &mut self,
+        entries: &[(u64, &[u8])],
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async move {
+            for (seq_num, entry) in entries {
+                self.add_entry(entry, *seq_num).await?;
+            }
+            Ok(())
+        }
+    }
+    /// Remove the entry at `seq_num`. Removing an entry that isn't in the store does nothing.
+    fn remove_entry(
+        &mut self,
+        seq_num: u64,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+    /// Remove the entries at every seq_num in `seq_nums`.
+    fn remove_entries(
+        &mut self,
+        seq_nums: &[u64],
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async move {
+            for seq_num in seq_nums {
+                self.remove_entry(*seq_num).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{AsyncEntryStore, SyncEntryStore};
+    use crate::entry_store::MemoryEntryStore;
+    use futures::executor::block_on;
+
+    /// Check the behaviour every [AsyncEntryStore] shares with a [SyncEntryStore] of a
+    /// [MemoryEntryStore].
+    pub(crate) async fn check_async_entry_store<Store: AsyncEntryStore>(mut store: Store) {
+        assert_eq!(store.get_last_seq().await, None);
+        assert_eq!(store.get_last_entry().await.unwrap(), None);
+        assert_eq!(store.get_entry(1).await.unwrap(), None);
+
+        store.add_entry(&[1, 1], 1).await.unwrap();
+        store.add_entry(&[2, 2], 2).await.unwrap();
+        assert_eq!(store.get_last_seq().await, Some(2));
+        assert_eq!(store.get_entry(1).await.unwrap(), Some(vec![1, 1]));
+        assert_eq!(store.get_last_entry().await.unwrap(), Some(vec![2, 2]));
+
+        // Partially replicated logs have gaps.
+        store.add_entry(&[8], 8).await.unwrap();
+        assert_eq!(store.get_last_seq().await, Some(8));
+        assert_eq!(store.get_entry(5).await.unwrap(), None);
+
+        store.remove_entry(2).await.unwrap();
+        assert_eq!(store.get_entry(2).await.unwrap(), None);
+        store.remove_entries(&[1, 8]).await.unwrap();
+        assert_eq!(store.get_last_seq().await, None);
+
+        store
+            .add_entries(&[(10, &[10][..]), (11, &[11, 11][..])])
+            .await
+            .unwrap();
+        assert_eq!(store.get_last_seq().await, Some(11));
+        assert_eq!(store.get_entry(10).await.unwrap(), Some(vec![10]));
+    }
+
+    #[test]
+    fn sync_entry_store_behaves_like_an_async_entry_store() {
+        block_on(check_async_entry_store(SyncEntryStore::new(
+            MemoryEntryStore::new(),
+        )));
+    }
+}
@@ -0,0 +1,72 @@
+use super::AsyncEntryStore;
+use crate::entry_store::EntryStore;
+
+/// An [AsyncEntryStore] that wraps a sync [EntryStore].
+///
+/// Each future runs the sync method as soon as it's polled, so a slow store blocks the executor
+/// just as it would block a thread. The store must be `Send` and `Sync` for its futures to be.
+#[derive(Debug, Default)]
+pub struct SyncEntryStore<Store: EntryStore> {
+    pub store: Store,
+}
+
+impl<Store: EntryStore> SyncEntryStore<Store> {
+    pub fn new(store: Store) -> SyncEntryStore<Store> {
+        SyncEntryStore { store }
+    }
+
+    pub fn into_inner(self) -> Store {
+        self.store
+    }
+}
+
+impl<Store> AsyncEntryStore for SyncEntryStore<Store>
+where
+    Store: EntryStore + Send + Sync,
+    Store::Error: Send,
+{
+    type Error = Store::Error;
+
+    async fn get_last_seq(&self) -> Option<u64> {
+        self.store.get_last_seq()
+    }
+    async fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.store.get_entry(seq_num)
+    }
+    async fn get_last_entry(&self) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.store.get_last_entry()
+    }
+    async fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<(), Self::Error> {
+        self.store.add_entry(entry, seq_num)
+    }
+    async fn add_entries(&mut self, entries: &[(u64, &[u8])]) -> Result<(), Self::Error> {
+        self.store.add_entries(entries)
+    }
+    async fn remove_entry(&mut self, seq_num: u64) -> Result<(), Self::Error> {
+        self.store.remove_entry(seq_num)
+    }
+    async fn remove_entries(&mut self, seq_nums: &[u64]) -> Result<(), Self::Error> {
+        self.store.remove_entries(seq_nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyncEntryStore;
+    use crate::async_entry_store::tests::check_async_entry_store;
+    use crate::entry_store::SegmentEntryStore;
+    use crate::EntryStore;
+    use futures::executor::block_on;
+    use tempfile::tempdir;
+
+    #[test]
+    fn wraps_a_store_that_does_io() {
+        let dir = tempdir().unwrap();
+        block_on(check_async_entry_store(SyncEntryStore::new(
+            SegmentEntryStore::open(dir.path()).unwrap(),
+        )));
+
+        let store = SegmentEntryStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_last_seq(), Some(11));
+    }
+}
@@ -0,0 +1,134 @@
+use super::AsyncLog;
+use crate::async_entry_store::AsyncEntryStore;
+use crate::checks::EntryToAdd;
+use crate::payload_store::PayloadStore;
+use core::fmt::Debug;
+use snafu::{OptionExt, ResultExt};
+
+use super::error::*;
+
+impl<Store: AsyncEntryStore + Debug, Payloads: PayloadStore + Debug> AsyncLog<Store, Payloads> {
+    /// Add a valid entry published by some other author to the log, checking it just like
+    /// [Log::add](crate::Log::add). If the `payload` is given and valid it's added to the payload
+    /// store.
+    ///
+    /// Unlike a `Log` there's no pending buffer, so the entry's lipmaa entry must already be in the
+    /// store. Returns the entry's seq_num.
+    pub async fn add(
+        &mut self,
+        entry_bytes: &[u8],
+        payload: Option<&[u8]>,
+    ) -> Result<u64, Error<Store, Payloads>> {
+        let to_add = EntryToAdd::decode(entry_bytes, &self.public_key, self.log_id)?;
+        let entry = &to_add.entry;
+
+        // Get the lipmaa entry, if the entry links to one.
+        let lipmaa = match to_add.lipmaa_seq_num() {
+            Some(lipmaa_seq_num) => {
+                let lipmaa = self
+                    .store
+                    .get_entry(lipmaa_seq_num)
+                    .await
+                    .context(AddEntryGetLipmaaEntry)?
+                    .context(AddEntryNoLipmaalinkInStore { lipmaa_seq_num })?;
+                Some(lipmaa)
+            }
+            None => None,
+        };
+
+        // Try and get the backlink entry. If we have it, verify checks it is correct.
+        let backlink = self
+            .store
+            .get_entry(entry.seq_num - 1)
+            .await
+            .context(AddEntryGetBacklinkEntry)?;
+
+        to_add.verify(payload, lipmaa.as_deref(), backlink.as_deref())?;
+
+        self.store
+            .add_entry(entry_bytes, entry.seq_num)
+            .await
+            .context(AddEntryFailedToAddEntryToLog)?;
+
+        // Only once the entry is in the log, so a failed append leaves no orphaned payload.
+        if let Some(payload) = payload {
+            self.payload_store
+                .add_payload(entry.payload_hash.clone(), payload)
+                .context(AddEntryAddPayloadFailed)?;
+        }
+        Ok(entry.seq_num)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::async_log::tests::YieldingEntryStore;
+    use crate::async_log::{AsyncLog, Error};
+    use crate::log::tests::{payloads, published_log};
+    use crate::EntryStore;
+    use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use futures::executor::block_on;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn adds_entries_published_by_a_log() {
+        let remote_log = published_log(Keypair::generate(&mut OsRng {}), &payloads(1..=20));
+        let mut log = AsyncLog::new(
+            YieldingEntryStore::default(),
+            remote_log.public_key,
+            None,
+            0,
+        );
+
+        block_on(async {
+            for seq_num in 1..=20 {
+                let entry = remote_log.store.get_entry(seq_num).unwrap().unwrap();
+                let payload = remote_log.get_payload(seq_num).unwrap();
+                assert_eq!(log.add(&entry, payload.as_deref()).await.unwrap(), seq_num);
+            }
+            assert_eq!(
+                log.get_payload(20).await.unwrap(),
+                Some(b"message number 20".to_vec())
+            );
+        });
+        assert_eq!(log.store.store.get_last_seq(), Some(20));
+    }
+
+    #[test]
+    fn add_needs_lipmaa_link_in_store() {
+        let remote_log = published_log(Keypair::generate(&mut OsRng {}), &payloads(1..=13));
+        let mut log = AsyncLog::new(
+            YieldingEntryStore::default(),
+            remote_log.public_key,
+            None,
+            0,
+        );
+
+        let entry = remote_log.store.get_entry(13).unwrap().unwrap();
+        match block_on(log.add(&entry, None)) {
+            Err(Error::AddEntryNoLipmaalinkInStore { lipmaa_seq_num: 4 }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn add_checks_the_payload() {
+        let remote_log = published_log(Keypair::generate(&mut OsRng {}), &payloads(1..=1));
+        let mut log = AsyncLog::new(
+            YieldingEntryStore::default(),
+            remote_log.public_key,
+            None,
+            0,
+        );
+
+        let entry = remote_log.store.get_entry(1).unwrap().unwrap();
+        match block_on(log.add(&entry, Some(b"not the payload"))) {
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::PayloadHashDidNotMatch {},
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+        assert_eq!(log.store.store.get_last_seq(), None);
+    }
+}
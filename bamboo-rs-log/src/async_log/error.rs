@@ -0,0 +1,83 @@
+use crate::async_entry_store::AsyncEntryStore;
+use crate::checks::{AddError, PublishError as CheckPublishError};
+use crate::payload_store::{MemoryPayloadStore, PayloadStore};
+use bamboo_rs_core_ed25519_yasmf::entry::decode::Error as DecodeError;
+use bamboo_rs_core_ed25519_yasmf::entry::publish::Error as PublishError;
+use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
+use core::fmt::Debug;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum Error<ES: AsyncEntryStore + Debug, PS: PayloadStore + Debug = MemoryPayloadStore> {
+    #[snafu(display("Could not decode the entry to add: {}", source))]
+    AddEntryDecodeFailed { source: DecodeError },
+    #[snafu(display("The entry to add was published by a different author than the log's"))]
+    AddEntryAuthorDoesNotMatchLog,
+    #[snafu(display(
+        "The entry to add has log_id {} but the log has log_id {}",
+        actual,
+        expected
+    ))]
+    AddEntryLogIdDoesNotMatchLog { actual: u64, expected: u64 },
+    #[snafu(display("Could not get the lipmaa entry from the store: {}", source))]
+    AddEntryGetLipmaaEntry { source: ES::Error },
+    #[snafu(display(
+        "The lipmaa entry (seq_num {}) of the entry to add is not in the store",
+        lipmaa_seq_num
+    ))]
+    AddEntryNoLipmaalinkInStore { lipmaa_seq_num: u64 },
+    #[snafu(display("Could not get the backlink entry from the store: {}", source))]
+    AddEntryGetBacklinkEntry { source: ES::Error },
+    #[snafu(display("The entry to add failed verification: {}", source))]
+    AddEntryFailedVerification { source: VerifyError },
+    #[snafu(display("Could not add the payload to the payload store: {}", source))]
+    AddEntryAddPayloadFailed { source: PS::Error },
+    #[snafu(display("Could not add the entry to the store: {}", source))]
+    AddEntryFailedToAddEntryToLog { source: ES::Error },
+    #[snafu(display("Could not get the lipmaa entry from the store: {}", source))]
+    PublishEntryGetLipmaaEntry { source: ES::Error },
+    #[snafu(display("Could not get the backlink entry from the store: {}", source))]
+    PublishEntryGetBacklinkEntry { source: ES::Error },
+    #[snafu(display("Could not publish the new entry: {}", source))]
+    PublishNewEntryFailed { source: PublishError },
+    #[snafu(display("Could not add the published payload to the payload store: {}", source))]
+    PublishEntryAddPayloadFailed { source: PS::Error },
+    #[snafu(display("Could not add the published entry to the store: {}", source))]
+    PublishEntryAppendFailed { source: ES::Error },
+    #[snafu(display("Can't publish to a log without a key pair"))]
+    PublishWithoutKeypair,
+    #[snafu(display("The log's key pair does not match its public key"))]
+    PublishKeypairDoesNotMatchLog,
+    #[snafu(display("Could not get the entry from the store: {}", source))]
+    GetPayloadGetEntry { source: ES::Error },
+    #[snafu(display("Could not decode the entry from the store: {}", source))]
+    GetPayloadDecodeEntry { source: DecodeError },
+    #[snafu(display("Could not get the payload from the payload store: {}", source))]
+    GetPayloadFailed { source: PS::Error },
+}
+
+impl<ES: AsyncEntryStore + Debug, PS: PayloadStore + Debug> From<AddError> for Error<ES, PS> {
+    fn from(error: AddError) -> Self {
+        match error {
+            AddError::Decode { source } => Error::AddEntryDecodeFailed { source },
+            AddError::AuthorDoesNotMatchLog { .. } => Error::AddEntryAuthorDoesNotMatchLog,
+            AddError::LogIdDoesNotMatchLog {
+                actual, expected, ..
+            } => Error::AddEntryLogIdDoesNotMatchLog { actual, expected },
+            AddError::Verification { source } => Error::AddEntryFailedVerification { source },
+        }
+    }
+}
+
+impl<ES: AsyncEntryStore + Debug, PS: PayloadStore + Debug> From<CheckPublishError>
+    for Error<ES, PS>
+{
+    fn from(error: CheckPublishError) -> Self {
+        match error {
+            CheckPublishError::WithoutKeypair => Error::PublishWithoutKeypair,
+            CheckPublishError::KeypairDoesNotMatchLog => Error::PublishKeypairDoesNotMatchLog,
+            CheckPublishError::NewEntry { source } => Error::PublishNewEntryFailed { source },
+        }
+    }
+}
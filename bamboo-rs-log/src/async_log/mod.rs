@@ -0,0 +1,116 @@
+pub use crate::async_entry_store::AsyncEntryStore;
+pub use crate::payload_store::{MemoryPayloadStore, PayloadStore};
+use bamboo_rs_core_ed25519_yasmf::{Keypair, PublicKey};
+
+pub mod add;
+pub mod error;
+pub mod payload;
+pub mod publish;
+
+pub use error::*;
+
+/// A single bamboo log kept in an [AsyncEntryStore], the async front end of a [Log](crate::Log).
+///
+/// It publishes and adds entries just like a `Log`, awaiting the store rather than blocking on
+/// it. Payloads are kept in a sync [PayloadStore].
+pub struct AsyncLog<Store: AsyncEntryStore, Payloads: PayloadStore = MemoryPayloadStore> {
+    pub store: Store,
+    pub payload_store: Payloads,
+    pub public_key: PublicKey,
+    key_pair: Option<Keypair>,
+    log_id: u64,
+}
+
+impl<Store: AsyncEntryStore> AsyncLog<Store> {
+    /// Create a log that keeps its payloads in a [MemoryPayloadStore].
+    pub fn new(
+        store: Store,
+        public_key: PublicKey,
+        key_pair: Option<Keypair>,
+        log_id: u64,
+    ) -> AsyncLog<Store> {
+        AsyncLog::with_payload_store(
+            store,
+            MemoryPayloadStore::new(),
+            public_key,
+            key_pair,
+            log_id,
+        )
+    }
+}
+
+impl<Store: AsyncEntryStore, Payloads: PayloadStore> AsyncLog<Store, Payloads> {
+    /// Create a log that keeps its payloads in `payload_store`.
+    pub fn with_payload_store(
+        store: Store,
+        payload_store: Payloads,
+        public_key: PublicKey,
+        key_pair: Option<Keypair>,
+        log_id: u64,
+    ) -> AsyncLog<Store, Payloads> {
+        AsyncLog {
+            store,
+            payload_store,
+            public_key,
+            key_pair,
+            log_id,
+        }
+    }
+
+    /// The `log_id` of this log.
+    pub fn log_id(&self) -> u64 {
+        self.log_id
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::async_entry_store::AsyncEntryStore;
+    use crate::entry_store::{EntryStore, MemoryEntryStore};
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+
+    /// Returns `Pending` once before it's ready, like a future waiting on I/O.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// A [MemoryEntryStore] that yields to the executor on every call.
+    #[derive(Debug, Default)]
+    pub(crate) struct YieldingEntryStore {
+        pub(crate) store: MemoryEntryStore,
+    }
+
+    impl AsyncEntryStore for YieldingEntryStore {
+        type Error = <MemoryEntryStore as EntryStore>::Error;
+
+        async fn get_last_seq(&self) -> Option<u64> {
+            YieldOnce(false).await;
+            self.store.get_last_seq()
+        }
+        async fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+            YieldOnce(false).await;
+            self.store.get_entry(seq_num)
+        }
+        async fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<(), Self::Error> {
+            YieldOnce(false).await;
+            self.store.add_entry(entry, seq_num)
+        }
+        async fn remove_entry(&mut self, seq_num: u64) -> Result<(), Self::Error> {
+            YieldOnce(false).await;
+            self.store.remove_entry(seq_num)
+        }
+    }
+}
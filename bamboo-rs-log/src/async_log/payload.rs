@@ -0,0 +1,34 @@
+use super::AsyncLog;
+use crate::async_entry_store::AsyncEntryStore;
+use crate::payload_store::PayloadStore;
+use bamboo_rs_core_ed25519_yasmf::entry::decode;
+use core::fmt::Debug;
+use snafu::ResultExt;
+
+use super::error::*;
+
+impl<Store: AsyncEntryStore + Debug, Payloads: PayloadStore + Debug> AsyncLog<Store, Payloads> {
+    /// Get the payload of the entry at `seq_num`.
+    ///
+    /// Returns `None` if the entry isn't in the log, or if its payload was never published or
+    /// added.
+    pub async fn get_payload(
+        &self,
+        seq_num: u64,
+    ) -> Result<Option<Vec<u8>>, Error<Store, Payloads>> {
+        let entry_bytes = match self
+            .store
+            .get_entry(seq_num)
+            .await
+            .context(GetPayloadGetEntry)?
+        {
+            Some(entry_bytes) => entry_bytes,
+            None => return Ok(None),
+        };
+        let entry = decode(&entry_bytes).context(GetPayloadDecodeEntry)?;
+
+        self.payload_store
+            .get_payload(entry.payload_hash)
+            .context(GetPayloadFailed)
+    }
+}
@@ -0,0 +1,172 @@
+use core::fmt::Debug;
+use lipmaa_link::lipmaa;
+
+use crate::async_entry_store::AsyncEntryStore;
+use crate::checks::{publish_next, publishing_key_pair};
+use crate::payload_store::PayloadStore;
+use bamboo_rs_core_ed25519_yasmf::entry::MAX_ENTRY_SIZE;
+use snafu::ResultExt;
+
+use super::error::*;
+use super::AsyncLog;
+
+impl<Store: AsyncEntryStore + Debug, Payloads: PayloadStore + Debug> AsyncLog<Store, Payloads> {
+    /// Publish a new entry with `payload` to the end of the log, and add `payload` to the payload
+    /// store.
+    pub async fn publish(
+        &mut self,
+        payload: &[u8],
+        is_end_of_feed: bool,
+    ) -> Result<(), Error<Store, Payloads>> {
+        let mut buff = [0u8; MAX_ENTRY_SIZE];
+
+        let key_pair = publishing_key_pair(self.key_pair.as_ref(), &self.public_key)?;
+
+        let last_seq_num = self.store.get_last_seq().await;
+        let seq_num = last_seq_num.unwrap_or(0) + 1;
+
+        let lipmaa_entry_bytes = self
+            .store
+            .get_entry(lipmaa(seq_num))
+            .await
+            .context(PublishEntryGetLipmaaEntry)?;
+
+        let backlink_bytes = self
+            .store
+            .get_entry(last_seq_num.unwrap_or(0))
+            .await
+            .context(PublishEntryGetBacklinkEntry)?;
+
+        let (length, payload_hash) = publish_next(
+            &mut buff,
+            key_pair,
+            self.log_id,
+            payload,
+            is_end_of_feed,
+            last_seq_num,
+            lipmaa_entry_bytes.as_deref(),
+            backlink_bytes.as_deref(),
+        )?;
+
+        self.store
+            .add_entry(&buff[..length], seq_num)
+            .await
+            .context(PublishEntryAppendFailed)?;
+
+        self.payload_store
+            .add_payload((&payload_hash).into(), payload)
+            .context(PublishEntryAddPayloadFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::async_entry_store::SyncEntryStore;
+    use crate::async_log::tests::YieldingEntryStore;
+    use crate::async_log::{AsyncLog, Error};
+    use crate::entry_store::MemoryEntryStore;
+    use crate::{EntryStore, Log};
+    use bamboo_rs_core_ed25519_yasmf::entry::decode;
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use core::future::Future;
+    use futures::executor::{block_on, LocalPool};
+    use futures::task::LocalSpawnExt;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn publishes_the_same_entries_as_a_log() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let keypair_bytes = keypair.to_bytes();
+
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0);
+        let mut async_log = AsyncLog::new(
+            SyncEntryStore::new(MemoryEntryStore::new()),
+            log.public_key,
+            Some(Keypair::from_bytes(&keypair_bytes).unwrap()),
+            0,
+        );
+        for i in 1..20u8 {
+            log.publish(&[i], false).unwrap();
+            block_on(async_log.publish(&[i], false)).unwrap();
+        }
+
+        assert_eq!(async_log.store.store.store, log.store.store);
+        let entry = decode(async_log.store.store.get_entry_ref(19).unwrap().unwrap()).unwrap();
+        assert!(entry.verify_signature().is_ok());
+    }
+
+    #[test]
+    fn publishes_to_logs_concurrently_on_a_local_pool() {
+        let mut csprng: OsRng = OsRng {};
+        let mut pool = LocalPool::new();
+        let spawner = pool.spawner();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for _ in 0..3 {
+            let keypair: Keypair = Keypair::generate(&mut csprng);
+            let mut log = AsyncLog::new(
+                YieldingEntryStore::default(),
+                keypair.public,
+                Some(keypair),
+                0,
+            );
+            let sender = sender.clone();
+            spawner
+                .spawn_local(async move {
+                    for i in 1..10u8 {
+                        log.publish(&[i], false).await.unwrap();
+                    }
+                    sender.send(log.store.store.get_last_seq()).unwrap();
+                })
+                .unwrap();
+        }
+        drop(sender);
+        pool.run();
+
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![Some(9); 3]);
+    }
+
+    #[test]
+    fn publishes_on_another_thread() {
+        fn spawn<F>(future: F) -> std::thread::JoinHandle<F::Output>
+        where
+            F: Future + Send + 'static,
+            F::Output: Send + 'static,
+        {
+            std::thread::spawn(move || block_on(future))
+        }
+
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let mut log = AsyncLog::new(
+            SyncEntryStore::new(MemoryEntryStore::new()),
+            keypair.public,
+            Some(keypair),
+            0,
+        );
+
+        let log = spawn(async move {
+            for i in 1..10u8 {
+                log.publish(&[i], false).await.unwrap();
+            }
+            log
+        })
+        .join()
+        .unwrap();
+        assert_eq!(log.store.store.get_last_seq(), Some(9));
+    }
+
+    #[test]
+    fn publish_without_secret_key_errors() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut log = AsyncLog::new(YieldingEntryStore::default(), keypair.public, None, 0);
+
+        match block_on(log.publish(&[1, 2, 3], false)) {
+            Err(Error::PublishWithoutKeypair) => {}
+            e => panic!("expected publish to fail with an error, got: {:?}", e),
+        }
+    }
+}
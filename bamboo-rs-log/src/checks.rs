@@ -0,0 +1,127 @@
+//! The checks [Log](crate::Log) and [AsyncLog](crate::AsyncLog) share when adding and publishing
+//! entries. Their errors convert into the errors of both logs.
+use bamboo_rs_core_ed25519_yasmf::entry::decode::Error as DecodeError;
+use bamboo_rs_core_ed25519_yasmf::entry::publish::Error as CorePublishError;
+use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
+use bamboo_rs_core_ed25519_yasmf::entry::{decode, publish, verify};
+use bamboo_rs_core_ed25519_yasmf::hash::OwnedYasmfHash;
+use bamboo_rs_core_ed25519_yasmf::{Entry, HashAlgorithm, Keypair, PublicKey};
+use lipmaa_link::lipmaa;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub(crate) enum AddError {
+    Decode {
+        source: DecodeError,
+    },
+    AuthorDoesNotMatchLog {
+        seq_num: u64,
+    },
+    LogIdDoesNotMatchLog {
+        seq_num: u64,
+        actual: u64,
+        expected: u64,
+    },
+    Verification {
+        source: VerifyError,
+    },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub(crate) enum PublishError {
+    WithoutKeypair,
+    KeypairDoesNotMatchLog,
+    NewEntry { source: CorePublishError },
+}
+
+/// An entry to add to a log, decoded and checked to be from the log's author and log_id.
+pub(crate) struct EntryToAdd<'a> {
+    pub(crate) entry: Entry<&'a [u8], &'a [u8]>,
+    bytes: &'a [u8],
+}
+
+impl<'a> EntryToAdd<'a> {
+    pub(crate) fn decode(
+        bytes: &'a [u8],
+        public_key: &PublicKey,
+        log_id: u64,
+    ) -> Result<EntryToAdd<'a>, AddError> {
+        let entry = decode(bytes).context(Decode)?;
+
+        ensure!(
+            entry.author == *public_key,
+            AuthorDoesNotMatchLog {
+                seq_num: entry.seq_num
+            }
+        );
+        ensure!(
+            entry.log_id == log_id,
+            LogIdDoesNotMatchLog {
+                seq_num: entry.seq_num,
+                actual: entry.log_id,
+                expected: log_id
+            }
+        );
+
+        Ok(EntryToAdd { entry, bytes })
+    }
+
+    /// The seq_num of the lipmaa entry, if the entry links to one.
+    pub(crate) fn lipmaa_seq_num(&self) -> Option<u64> {
+        self.entry
+            .lipmaa_link
+            .as_ref()
+            .map(|_| lipmaa(self.entry.seq_num))
+    }
+
+    /// Verify the entry against its payload and the linked entries from the log.
+    pub(crate) fn verify(
+        &self,
+        payload: Option<&[u8]>,
+        lipmaa: Option<&[u8]>,
+        backlink: Option<&[u8]>,
+    ) -> Result<(), AddError> {
+        verify(self.bytes, payload, lipmaa, backlink).context(Verification)
+    }
+}
+
+/// The key pair to publish to the log of `public_key` with.
+pub(crate) fn publishing_key_pair<'a>(
+    key_pair: Option<&'a Keypair>,
+    public_key: &PublicKey,
+) -> Result<&'a Keypair, PublishError> {
+    let key_pair = key_pair.context(WithoutKeypair)?;
+    ensure!(key_pair.public == *public_key, KeypairDoesNotMatchLog);
+    Ok(key_pair)
+}
+
+/// Publish the entry after `last_seq_num` into `out`, returning its length and the hash of
+/// `payload` to add it to the payload store with.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn publish_next(
+    out: &mut [u8],
+    key_pair: &Keypair,
+    log_id: u64,
+    payload: &[u8],
+    is_end_of_feed: bool,
+    last_seq_num: Option<u64>,
+    lipmaa_entry_bytes: Option<&[u8]>,
+    backlink_bytes: Option<&[u8]>,
+) -> Result<(usize, OwnedYasmfHash), PublishError> {
+    let length = publish(
+        out,
+        key_pair,
+        log_id,
+        payload,
+        is_end_of_feed,
+        last_seq_num,
+        lipmaa_entry_bytes,
+        backlink_bytes,
+    )
+    .context(NewEntry)?;
+
+    // Entries are published with the default hash algorithm.
+    Ok((length, HashAlgorithm::default().hash(payload)))
+}
@@ -8,7 +8,7 @@ pub enum Error {}
 
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct MemoryEntryStore {
     pub store: HashMap<u64, Vec<u8>>,
 }
@@ -28,7 +28,7 @@ impl EntryStore for MemoryEntryStore {
     type Error = Error;
 
     fn get_last_seq(&self) -> Option<u64> {
-        self.store.keys().max().map(|max| *max)
+        self.store.keys().max().copied()
     }
     fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>> {
         if seq_num == 0 {
@@ -37,7 +37,7 @@ impl EntryStore for MemoryEntryStore {
         let result = self.store.get(&seq_num).map(|vec| vec.to_vec());
         Ok(result)
     }
-    fn get_entry_ref<'a>(&'a self, seq_num: u64) -> Result<Option<&'a [u8]>> {
+    fn get_entry_ref(&self, seq_num: u64) -> Result<Option<&[u8]>> {
         if seq_num == 0 {
             return Ok(None);
         }
@@ -50,7 +50,7 @@ impl EntryStore for MemoryEntryStore {
             None => Ok(None),
         }
     }
-    fn get_last_entry_ref<'a>(&'a self) -> Result<Option<&'a [u8]>> {
+    fn get_last_entry_ref(&self) -> Result<Option<&[u8]>> {
         match self.get_last_seq() {
             Some(seq) => self.get_entry_ref(seq),
             None => Ok(None),
@@ -62,4 +62,8 @@ impl EntryStore for MemoryEntryStore {
         self.store.insert(seq_num, vec);
         Ok(())
     }
+    fn remove_entry(&mut self, seq_num: u64) -> Result<()> {
+        self.store.remove(&seq_num);
+        Ok(())
+    }
 }
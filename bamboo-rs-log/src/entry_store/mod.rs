@@ -1,16 +1,128 @@
 pub mod memory_entry_store;
-use snafu::AsErrorSource;
+pub mod segment_entry_store;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_entry_store;
 use core::fmt::Debug;
 use core::fmt::Display;
 pub use memory_entry_store::*;
+pub use segment_entry_store::{SegmentEntryStore, DEFAULT_MAX_SEGMENT_SIZE};
+use snafu::AsErrorSource;
+#[cfg(feature = "sqlite")]
+pub use sqlite_entry_store::SqliteEntryStore;
 
 pub trait EntryStore {
     type Error: Display + Debug + AsErrorSource;
 
     fn get_last_seq(&self) -> Option<u64>;
     fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>, Self::Error>;
-    fn get_entry_ref<'a>(&'a self, seq_num: u64) -> Result<Option<&'a [u8]>, Self::Error>;
+    fn get_entry_ref(&self, seq_num: u64) -> Result<Option<&[u8]>, Self::Error>;
     fn get_last_entry(&self) -> Result<Option<Vec<u8>>, Self::Error>;
-    fn get_last_entry_ref<'a>(&'a self) -> Result<Option<&'a [u8]>, Self::Error>;
+    fn get_last_entry_ref(&self) -> Result<Option<&[u8]>, Self::Error>;
     fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<(), Self::Error>;
+    /// Add `(seq_num, entry)` pairs in order. Stores that can add them all or none of them.
+    fn add_entries(&mut self, entries: &[(u64, &[u8])]) -> Result<(), Self::Error> {
+        entries
+            .iter()
+            .try_for_each(|(seq_num, entry)| self.add_entry(entry, *seq_num))
+    }
+    /// Remove the entry at `seq_num`. Removing an entry that isn't in the store does nothing.
+    fn remove_entry(&mut self, seq_num: u64) -> Result<(), Self::Error>;
+    /// Remove the entries at every seq_num in `seq_nums`.
+    fn remove_entries(&mut self, seq_nums: &[u64]) -> Result<(), Self::Error> {
+        seq_nums
+            .iter()
+            .try_for_each(|seq_num| self.remove_entry(*seq_num))
+    }
+}
+
+/// Lets a [Log](crate::Log) borrow a store, like a feed handed out by a
+/// [FeedStore](crate::FeedStore).
+impl<Store: EntryStore + ?Sized> EntryStore for &mut Store {
+    type Error = Store::Error;
+
+    fn get_last_seq(&self) -> Option<u64> {
+        (**self).get_last_seq()
+    }
+    fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>, Self::Error> {
+        (**self).get_entry(seq_num)
+    }
+    fn get_entry_ref(&self, seq_num: u64) -> Result<Option<&[u8]>, Self::Error> {
+        (**self).get_entry_ref(seq_num)
+    }
+    fn get_last_entry(&self) -> Result<Option<Vec<u8>>, Self::Error> {
+        (**self).get_last_entry()
+    }
+    fn get_last_entry_ref(&self) -> Result<Option<&[u8]>, Self::Error> {
+        (**self).get_last_entry_ref()
+    }
+    fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<(), Self::Error> {
+        (**self).add_entry(entry, seq_num)
+    }
+    fn add_entries(&mut self, entries: &[(u64, &[u8])]) -> Result<(), Self::Error> {
+        (**self).add_entries(entries)
+    }
+    fn remove_entry(&mut self, seq_num: u64) -> Result<(), Self::Error> {
+        (**self).remove_entry(seq_num)
+    }
+    fn remove_entries(&mut self, seq_nums: &[u64]) -> Result<(), Self::Error> {
+        (**self).remove_entries(seq_nums)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{EntryStore, MemoryEntryStore};
+
+    /// Check the behaviour every [EntryStore] shares with [MemoryEntryStore].
+    pub(crate) fn check_entry_store<Store: EntryStore>(mut store: Store) {
+        assert_eq!(store.get_last_seq(), None);
+        assert_eq!(store.get_last_entry().unwrap(), None);
+        assert_eq!(store.get_last_entry_ref().unwrap(), None);
+        assert_eq!(store.get_entry(0).unwrap(), None);
+        assert_eq!(store.get_entry_ref(1).unwrap(), None);
+
+        store.add_entry(&[1, 1], 1).unwrap();
+        store.add_entry(&[2, 2], 2).unwrap();
+        assert_eq!(store.get_last_seq(), Some(2));
+        assert_eq!(store.get_entry(1).unwrap(), Some(vec![1, 1]));
+        assert_eq!(store.get_entry_ref(2).unwrap(), Some(&[2, 2][..]));
+        assert_eq!(store.get_last_entry().unwrap(), Some(vec![2, 2]));
+        assert_eq!(store.get_last_entry_ref().unwrap(), Some(&[2, 2][..]));
+
+        // Partially replicated logs have gaps.
+        store.add_entry(&[8], 8).unwrap();
+        assert_eq!(store.get_last_seq(), Some(8));
+        assert_eq!(store.get_entry(5).unwrap(), None);
+        store.add_entry(&[4], 4).unwrap();
+        assert_eq!(store.get_last_seq(), Some(8));
+        assert_eq!(store.get_entry_ref(4).unwrap(), Some(&[4][..]));
+
+        // Adding an entry again replaces it.
+        store.add_entry(&[3, 3, 3], 2).unwrap();
+        assert_eq!(store.get_entry_ref(2).unwrap(), Some(&[3, 3, 3][..]));
+
+        store.remove_entry(2).unwrap();
+        store.remove_entry(5).unwrap();
+        assert_eq!(store.get_entry(2).unwrap(), None);
+        assert_eq!(store.get_entry_ref(1).unwrap(), Some(&[1, 1][..]));
+
+        store.remove_entries(&[1, 8]).unwrap();
+        assert_eq!(store.get_entry(1).unwrap(), None);
+        assert_eq!(store.get_last_seq(), Some(4));
+        assert_eq!(store.get_last_entry_ref().unwrap(), Some(&[4][..]));
+
+        store.add_entry(&[9], 9).unwrap();
+        assert_eq!(store.get_last_seq(), Some(9));
+
+        store
+            .add_entries(&[(10, &[10][..]), (11, &[11, 11][..])])
+            .unwrap();
+        assert_eq!(store.get_last_seq(), Some(11));
+        assert_eq!(store.get_entry_ref(10).unwrap(), Some(&[10][..]));
+    }
+
+    #[test]
+    fn memory_entry_store_behaves_like_an_entry_store() {
+        check_entry_store(MemoryEntryStore::new());
+    }
 }
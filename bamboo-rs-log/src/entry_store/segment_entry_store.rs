@@ -0,0 +1,694 @@
+use super::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use bamboo_rs_core_ed25519_yasmf::entry::MAX_ENTRY_SIZE;
+use snafu::{ensure, ResultExt, Snafu};
+
+/// Start a new segment once the current one is this many bytes.
+pub const DEFAULT_MAX_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+/// The number of bytes before each entry in a segment: its length and crc32 as little endian u32s,
+/// and its seq_num as a little endian u64.
+pub const RECORD_HEADER_SIZE: usize = 16;
+/// Set in the length of every record of a batch but the last, so a batch is only read back if all
+/// of it was written.
+const CONTINUES_BATCH: u32 = 1 << 31;
+
+const SEGMENT_EXTENSION: &str = "segment";
+const REWRITE_EXTENSION: &str = "rewrite";
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum Error {
+    #[snafu(display("Could not create segment directory {}: {}", path.display(), source))]
+    CreateSegmentDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read segment directory {}: {}", path.display(), source))]
+    ReadSegmentDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not open segment {}: {}", path.display(), source))]
+    OpenSegment {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read segment {}: {}", path.display(), source))]
+    ReadSegment {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write to segment {}: {}", path.display(), source))]
+    WriteSegment {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not truncate torn write in segment {}: {}", path.display(), source))]
+    TruncateSegment {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Segment {} is corrupt at offset {}", path.display(), offset))]
+    CorruptSegment { path: PathBuf, offset: usize },
+    #[snafu(display("Entry is {} bytes, larger than the largest possible entry", length))]
+    EntryTooLarge { length: usize },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// A durable [EntryStore] that appends entries to segment files in a directory.
+///
+/// Each entry is written as a record with its length, a crc32 of its seq_num and bytes, and its
+/// seq_num, and the segment is fsynced before `add_entry` returns. `add_entries` writes all of its
+/// records in one write and one fsync, with every record but the last marked as continuing the
+/// batch. A new segment is started once the current one reaches the max segment size.
+///
+/// Opening a store reads every segment to rebuild the seq_num index. A torn or corrupt record or
+/// an unfinished batch at the end of the last segment, with no valid record after it, is what a
+/// crash during `add_entries` leaves behind, so it's truncated back to the last complete batch. A
+/// corrupt record anywhere else is an error.
+///
+/// Segments are kept in memory as well so entries can be borrowed with `get_entry_ref`. Adding an
+/// entry with a seq_num that's already in the store replaces it, like [MemoryEntryStore].
+///
+/// Removing entries rewrites every segment that holds them without their records, so they're gone
+/// from disk. Each rewritten segment is fsynced and renamed over the old one.
+#[derive(Debug)]
+pub struct SegmentEntryStore {
+    dir: PathBuf,
+    max_segment_size: u64,
+    segments: Vec<Segment>,
+    index: BTreeMap<u64, EntryLocation>,
+}
+
+#[derive(Debug)]
+struct Segment {
+    path: PathBuf,
+    file: File,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EntryLocation {
+    segment: usize,
+    offset: usize,
+    length: usize,
+}
+
+impl SegmentEntryStore {
+    /// Open the store in `dir`, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<SegmentEntryStore> {
+        SegmentEntryStore::open_with_max_segment_size(dir, DEFAULT_MAX_SEGMENT_SIZE)
+    }
+
+    /// Open the store in `dir`, starting new segments once they reach `max_segment_size` bytes.
+    pub fn open_with_max_segment_size<P: AsRef<Path>>(
+        dir: P,
+        max_segment_size: u64,
+    ) -> Result<SegmentEntryStore> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).context(CreateSegmentDir { path: dir.clone() })?;
+
+        let mut paths = fs::read_dir(&dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .context(ReadSegmentDir { path: dir.clone() })?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION));
+        paths.sort();
+
+        let mut store = SegmentEntryStore {
+            dir,
+            max_segment_size,
+            segments: Vec::with_capacity(paths.len()),
+            index: BTreeMap::new(),
+        };
+
+        let last = paths.len().saturating_sub(1);
+        for (segment_num, path) in paths.into_iter().enumerate() {
+            let segment = store.read_segment(path, segment_num, segment_num == last)?;
+            store.segments.push(segment);
+        }
+
+        Ok(store)
+    }
+
+    /// The directory the segments are in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The number of segment files.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Read a segment into memory and index its records. A torn write is truncated if `is_last`
+    /// and no valid record follows it.
+    fn read_segment(
+        &mut self,
+        path: PathBuf,
+        segment_num: usize,
+        is_last: bool,
+    ) -> Result<Segment> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .context(OpenSegment { path: path.clone() })?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .context(ReadSegment { path: path.clone() })?;
+
+        // The end of the last complete batch, and the records of the batch being read.
+        let mut committed = 0;
+        let mut batch = Vec::new();
+
+        let mut offset = 0;
+        while let Some((seq_num, length, continues)) = decode_record(&bytes[offset..]) {
+            batch.push((
+                seq_num,
+                EntryLocation {
+                    segment: segment_num,
+                    offset: offset + RECORD_HEADER_SIZE,
+                    length,
+                },
+            ));
+            offset += RECORD_HEADER_SIZE + length;
+            if !continues {
+                self.index.extend(batch.drain(..));
+                committed = offset;
+            }
+        }
+
+        if committed < bytes.len() {
+            // A torn write only leaves garbage at the very end, so if any complete record follows
+            // the bad one the segment was corrupted some other way.
+            let is_torn_tail = is_last
+                && (offset + 1..bytes.len()).all(|later| decode_record(&bytes[later..]).is_none());
+            ensure!(is_torn_tail, CorruptSegment { path, offset });
+
+            file.set_len(committed as u64)
+                .and_then(|_| file.sync_all())
+                .context(TruncateSegment { path: path.clone() })?;
+            bytes.truncate(committed);
+        }
+
+        Ok(Segment { path, file, bytes })
+    }
+
+    /// The segment to append to, starting a new one if the last is full.
+    fn writable_segment(&mut self) -> Result<usize> {
+        let is_full = self
+            .segments
+            .last()
+            .is_none_or(|segment| segment.bytes.len() as u64 >= self.max_segment_size);
+
+        if is_full {
+            let path = self
+                .dir
+                .join(format!("{:08}.{}", self.segments.len(), SEGMENT_EXTENSION));
+            let file = OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create_new(true)
+                .open(&path)
+                .context(OpenSegment { path: path.clone() })?;
+
+            // Make sure the new segment is still in the directory after a crash.
+            File::open(&self.dir)
+                .and_then(|dir| dir.sync_all())
+                .context(WriteSegment { path: path.clone() })?;
+
+            self.segments.push(Segment {
+                path,
+                file,
+                bytes: Vec::new(),
+            });
+        }
+
+        Ok(self.segments.len() - 1)
+    }
+
+    /// Rewrite a segment without the records of `removed`, or any record that's been replaced.
+    fn rewrite_segment(&mut self, segment_num: usize, removed: &BTreeSet<u64>) -> Result<()> {
+        let segment = &self.segments[segment_num];
+        let mut bytes = Vec::with_capacity(segment.bytes.len());
+        let mut locations = Vec::new();
+
+        let mut offset = 0;
+        while let Some((seq_num, length, _)) = decode_record(&segment.bytes[offset..]) {
+            let entry_offset = offset + RECORD_HEADER_SIZE;
+            let is_current = self.index.get(&seq_num).is_some_and(|location| {
+                location.segment == segment_num && location.offset == entry_offset
+            });
+            if is_current && !removed.contains(&seq_num) {
+                locations.push((
+                    seq_num,
+                    EntryLocation {
+                        segment: segment_num,
+                        offset: bytes.len() + RECORD_HEADER_SIZE,
+                        length,
+                    },
+                ));
+                // Every batch in the segment is complete, so the records no longer need to say
+                // they continue one.
+                encode_record(
+                    seq_num,
+                    &segment.bytes[entry_offset..entry_offset + length],
+                    false,
+                    &mut bytes,
+                );
+            }
+            offset = entry_offset + length;
+        }
+
+        let path = segment.path.clone();
+        let rewrite_path = path.with_extension(REWRITE_EXTENSION);
+        File::create(&rewrite_path)
+            .and_then(|mut file| {
+                file.write_all(&bytes)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&rewrite_path, &path))
+            .and_then(|_| File::open(&self.dir)?.sync_all())
+            .context(WriteSegment { path: path.clone() })?;
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .context(OpenSegment { path: path.clone() })?;
+
+        self.segments[segment_num] = Segment { path, file, bytes };
+        self.index.extend(locations);
+        Ok(())
+    }
+}
+
+impl EntryStore for SegmentEntryStore {
+    type Error = Error;
+
+    fn get_last_seq(&self) -> Option<u64> {
+        self.index.keys().next_back().copied()
+    }
+    fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>> {
+        self.get_entry_ref(seq_num)
+            .map(|entry| entry.map(|entry| entry.to_vec()))
+    }
+    fn get_entry_ref(&self, seq_num: u64) -> Result<Option<&[u8]>> {
+        let result = self.index.get(&seq_num).map(|location| {
+            &self.segments[location.segment].bytes
+                [location.offset..location.offset + location.length]
+        });
+        Ok(result)
+    }
+    fn get_last_entry(&self) -> Result<Option<Vec<u8>>> {
+        match self.get_last_seq() {
+            Some(seq) => self.get_entry(seq),
+            None => Ok(None),
+        }
+    }
+    fn get_last_entry_ref(&self) -> Result<Option<&[u8]>> {
+        match self.get_last_seq() {
+            Some(seq) => self.get_entry_ref(seq),
+            None => Ok(None),
+        }
+    }
+    fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<()> {
+        self.add_entries(&[(seq_num, entry)])
+    }
+    fn add_entries(&mut self, entries: &[(u64, &[u8])]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        for (_, entry) in entries {
+            ensure!(
+                entry.len() <= MAX_ENTRY_SIZE,
+                EntryTooLarge {
+                    length: entry.len()
+                }
+            );
+        }
+
+        let segment_num = self.writable_segment()?;
+        let segment = &mut self.segments[segment_num];
+
+        let mut records = Vec::with_capacity(
+            entries
+                .iter()
+                .map(|(_, entry)| RECORD_HEADER_SIZE + entry.len())
+                .sum(),
+        );
+        let mut locations = Vec::with_capacity(entries.len());
+        for (i, (seq_num, entry)) in entries.iter().enumerate() {
+            locations.push((
+                *seq_num,
+                EntryLocation {
+                    segment: segment_num,
+                    offset: segment.bytes.len() + records.len() + RECORD_HEADER_SIZE,
+                    length: entry.len(),
+                },
+            ));
+            encode_record(*seq_num, entry, i + 1 < entries.len(), &mut records);
+        }
+
+        let written = segment
+            .file
+            .write_all(&records)
+            .and_then(|_| segment.file.sync_data());
+        if written.is_err() {
+            // Don't leave part of the batch for the next write to append after.
+            let _ = segment.file.set_len(segment.bytes.len() as u64);
+        }
+        written.context(WriteSegment {
+            path: segment.path.clone(),
+        })?;
+
+        segment.bytes.extend_from_slice(&records);
+        self.index.extend(locations);
+
+        Ok(())
+    }
+    fn remove_entry(&mut self, seq_num: u64) -> Result<()> {
+        self.remove_entries(&[seq_num])
+    }
+    fn remove_entries(&mut self, seq_nums: &[u64]) -> Result<()> {
+        let removed: BTreeSet<u64> = seq_nums.iter().copied().collect();
+
+        // Replaced records of a removed seq_num can be in any segment, so look in all of them.
+        let segment_nums: Vec<usize> = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| {
+                let mut offset = 0;
+                while let Some((seq_num, length, _)) = decode_record(&segment.bytes[offset..]) {
+                    if removed.contains(&seq_num) {
+                        return true;
+                    }
+                    offset += RECORD_HEADER_SIZE + length;
+                }
+                false
+            })
+            .map(|(segment_num, _)| segment_num)
+            .collect();
+
+        for segment_num in segment_nums {
+            self.rewrite_segment(segment_num, &removed)?;
+        }
+        for seq_num in removed.iter() {
+            self.index.remove(seq_num);
+        }
+        Ok(())
+    }
+}
+
+/// Append the record of `entry` to `out`.
+fn encode_record(seq_num: u64, entry: &[u8], continues: bool, out: &mut Vec<u8>) {
+    let flag = if continues { CONTINUES_BATCH } else { 0 };
+    out.extend_from_slice(&(entry.len() as u32 | flag).to_le_bytes());
+    out.extend_from_slice(&checksum(seq_num, entry, continues).to_le_bytes());
+    out.extend_from_slice(&seq_num.to_le_bytes());
+    out.extend_from_slice(entry);
+}
+
+/// Decode the record at the start of `bytes`, returning its seq_num, its entry length and whether
+/// it continues a batch, or `None` if it's incomplete or its checksum doesn't match.
+fn decode_record(bytes: &[u8]) -> Option<(u64, usize, bool)> {
+    if bytes.len() < RECORD_HEADER_SIZE {
+        return None;
+    }
+
+    let length = u32::from_le_bytes(bytes[0..4].try_into().ok()?);
+    let crc = u32::from_le_bytes(bytes[4..8].try_into().ok()?);
+    let seq_num = u64::from_le_bytes(bytes[8..16].try_into().ok()?);
+
+    let continues = length & CONTINUES_BATCH != 0;
+    let length = (length & !CONTINUES_BATCH) as usize;
+    let entry = bytes.get(RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + length)?;
+    if length > MAX_ENTRY_SIZE || checksum(seq_num, entry, continues) != crc {
+        return None;
+    }
+
+    Some((seq_num, length, continues))
+}
+
+/// The crc32 of a record. Records that continue a batch include a marker byte, so the flag can't
+/// be flipped without the checksum failing.
+fn checksum(seq_num: u64, entry: &[u8], continues: bool) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&seq_num.to_le_bytes());
+    hasher.update(entry);
+    if continues {
+        hasher.update(&[1]);
+    }
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, SegmentEntryStore, RECORD_HEADER_SIZE};
+    use crate::entry_store::tests::check_entry_store;
+    use crate::entry_store::MemoryEntryStore;
+    use crate::{EntryStore, Log};
+    use bamboo_rs_core_ed25519_yasmf::entry::MAX_ENTRY_SIZE;
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use rand::rngs::OsRng;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn segment_paths(store: &SegmentEntryStore) -> Vec<std::path::PathBuf> {
+        let mut paths: Vec<_> = fs::read_dir(store.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn behaves_like_an_entry_store() {
+        let dir = tempdir().unwrap();
+        check_entry_store(SegmentEntryStore::open(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn log_entries_persist_across_reopening() {
+        let dir = tempdir().unwrap();
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public;
+        let keypair_bytes = keypair.to_bytes();
+
+        let mut log = Log::new(
+            SegmentEntryStore::open(dir.path()).unwrap(),
+            public_key,
+            Some(keypair),
+            0,
+        );
+        for i in 1..10u8 {
+            log.publish(&[i], false).unwrap();
+        }
+        let entries: Vec<_> = (1..10)
+            .map(|seq_num| log.store.get_entry(seq_num).unwrap().unwrap())
+            .collect();
+        drop(log);
+
+        let mut log = Log::new(
+            SegmentEntryStore::open(dir.path()).unwrap(),
+            public_key,
+            Some(Keypair::from_bytes(&keypair_bytes).unwrap()),
+            0,
+        );
+        assert_eq!(log.store.get_last_seq(), Some(9));
+        for (seq_num, entry) in (1..10).zip(entries.iter()) {
+            assert_eq!(&log.store.get_entry(seq_num).unwrap().unwrap(), entry);
+        }
+
+        // Publishing after reopening links to the persisted entries.
+        log.publish(&[10], false).unwrap();
+        let entry = log.store.get_entry(10).unwrap().unwrap();
+
+        let mut remote = Log::new(MemoryEntryStore::new(), public_key, None, 0);
+        for entry in entries.iter() {
+            remote.add(entry, None).unwrap();
+        }
+        remote.add(&entry, None).unwrap();
+        assert_eq!(remote.store.get_last_seq(), Some(10));
+    }
+
+    #[test]
+    fn removed_entries_are_gone_from_disk() {
+        let dir = tempdir().unwrap();
+        let mut store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 100).unwrap();
+        for seq_num in 1..=4 {
+            store.add_entry(&[seq_num as u8; 40], seq_num).unwrap();
+        }
+        // Replace entry 2 with a record in a later segment.
+        store.add_entry(&[9; 40], 2).unwrap();
+        store.remove_entries(&[2, 3]).unwrap();
+
+        let on_disk: Vec<u8> = segment_paths(&store)
+            .iter()
+            .flat_map(|path| fs::read(path).unwrap())
+            .collect();
+        assert!(!on_disk
+            .windows(40)
+            .any(|window| window == [2; 40] || window == [3; 40] || window == [9; 40]));
+
+        let mut store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 100).unwrap();
+        assert_eq!(store.get_entry(2).unwrap(), None);
+        assert_eq!(store.get_entry(3).unwrap(), None);
+        assert_eq!(store.get_entry_ref(4).unwrap(), Some(&[4u8; 40][..]));
+
+        store.add_entry(&[5u8; 40], 5).unwrap();
+        let store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 100).unwrap();
+        assert_eq!(store.get_last_entry_ref().unwrap(), Some(&[5u8; 40][..]));
+    }
+
+    #[test]
+    fn starts_new_segments_when_full() {
+        let dir = tempdir().unwrap();
+        let mut store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 100).unwrap();
+
+        for seq_num in 1..=5 {
+            store.add_entry(&[seq_num as u8; 60], seq_num).unwrap();
+        }
+        assert_eq!(store.segment_count(), 3);
+        assert_eq!(segment_paths(&store).len(), 3);
+
+        let store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 100).unwrap();
+        assert_eq!(store.segment_count(), 3);
+        assert_eq!(store.get_entry_ref(4).unwrap(), Some(&[4u8; 60][..]));
+        assert_eq!(store.get_last_seq(), Some(5));
+    }
+
+    #[test]
+    fn truncates_torn_final_write() {
+        let dir = tempdir().unwrap();
+        let mut store = SegmentEntryStore::open(dir.path()).unwrap();
+        store.add_entry(&[1u8; 40], 1).unwrap();
+        store.add_entry(&[2u8; 40], 2).unwrap();
+        let path = segment_paths(&store).pop().unwrap();
+        drop(store);
+
+        // Simulate a crash part way through writing the third record.
+        let good_length = fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[40, 0, 0, 0, 1, 2, 3, 4, 3, 0]).unwrap();
+        drop(file);
+
+        let mut store = SegmentEntryStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_last_seq(), Some(2));
+        assert_eq!(fs::metadata(&path).unwrap().len(), good_length);
+
+        store.add_entry(&[3u8; 40], 3).unwrap();
+        let store = SegmentEntryStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_entry_ref(3).unwrap(), Some(&[3u8; 40][..]));
+    }
+
+    #[test]
+    fn truncates_corrupt_final_record_but_not_earlier_segments() {
+        let dir = tempdir().unwrap();
+        let mut store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 1).unwrap();
+        store.add_entry(&[1u8; 40], 1).unwrap();
+        store.add_entry(&[2u8; 40], 2).unwrap();
+        let paths = segment_paths(&store);
+        drop(store);
+
+        // Flip a bit in the entry of the last record.
+        let mut bytes = fs::read(&paths[1]).unwrap();
+        bytes[RECORD_HEADER_SIZE] ^= 1;
+        fs::write(&paths[1], &bytes).unwrap();
+
+        let store = SegmentEntryStore::open_with_max_segment_size(dir.path(), 1).unwrap();
+        assert_eq!(store.get_last_seq(), Some(1));
+        assert_eq!(fs::metadata(&paths[1]).unwrap().len(), 0);
+        drop(store);
+
+        // Corruption in a segment that isn't the last isn't a torn write.
+        let mut bytes = fs::read(&paths[0]).unwrap();
+        bytes[RECORD_HEADER_SIZE] ^= 1;
+        fs::write(&paths[0], &bytes).unwrap();
+
+        assert!(SegmentEntryStore::open_with_max_segment_size(dir.path(), 1).is_err());
+    }
+
+    #[test]
+    fn corrupt_record_before_the_end_of_the_last_segment_is_an_error() {
+        let dir = tempdir().unwrap();
+        let mut store = SegmentEntryStore::open(dir.path()).unwrap();
+        for seq_num in 1..=3 {
+            store.add_entry(&[seq_num as u8; 40], seq_num).unwrap();
+        }
+        let path = segment_paths(&store).pop().unwrap();
+        drop(store);
+
+        // Flip a bit in the second record, the third is still valid.
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[RECORD_HEADER_SIZE * 2 + 40] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+
+        match SegmentEntryStore::open(dir.path()) {
+            Err(Error::CorruptSegment { offset, .. }) => {
+                assert_eq!(offset, RECORD_HEADER_SIZE + 40)
+            }
+            e => panic!("expected a corrupt segment, got {:?}", e),
+        }
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn adds_all_entries_or_none() {
+        let dir = tempdir().unwrap();
+        let mut store = SegmentEntryStore::open(dir.path()).unwrap();
+        store.add_entry(&[1u8; 40], 1).unwrap();
+        let path = segment_paths(&store).pop().unwrap();
+        let committed_length = fs::metadata(&path).unwrap().len();
+
+        let too_large = vec![3u8; MAX_ENTRY_SIZE + 1];
+        let entries = [(2, &[2u8; 40][..]), (3, &too_large[..])];
+        match store.add_entries(&entries) {
+            Err(Error::EntryTooLarge { .. }) => {}
+            e => panic!("expected the entry to be too large, got {:?}", e),
+        }
+        assert_eq!(store.get_last_seq(), Some(1));
+        assert_eq!(fs::metadata(&path).unwrap().len(), committed_length);
+
+        let entries = [
+            (2, &[2u8; 40][..]),
+            (3, &[3u8; 40][..]),
+            (4, &[4u8; 40][..]),
+        ];
+        store.add_entries(&entries).unwrap();
+        drop(store);
+        let bytes = fs::read(&path).unwrap();
+
+        // A crash part way through the last record, and one just before it, both lose the batch.
+        for torn_length in [bytes.len() - 10, bytes.len() - RECORD_HEADER_SIZE - 40] {
+            fs::write(&path, &bytes[..torn_length]).unwrap();
+            let store = SegmentEntryStore::open(dir.path()).unwrap();
+            assert_eq!(store.get_last_seq(), Some(1));
+            assert_eq!(store.get_entry_ref(2).unwrap(), None);
+            assert_eq!(fs::metadata(&path).unwrap().len(), committed_length);
+        }
+
+        fs::write(&path, &bytes).unwrap();
+        let mut store = SegmentEntryStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_last_seq(), Some(4));
+
+        // Removing the end of a batch doesn't lose the rest of it.
+        store.remove_entry(4).unwrap();
+        let store = SegmentEntryStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_last_seq(), Some(3));
+        assert_eq!(store.get_entry_ref(2).unwrap(), Some(&[2u8; 40][..]));
+    }
+}
@@ -0,0 +1,374 @@
+use super::*;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::rc::Rc;
+
+use bamboo_rs_core_ed25519_yasmf::PublicKey;
+use elsa::FrozenBTreeMap;
+use rusqlite::{params, Connection, OptionalExtension};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+const CREATE_ENTRIES_TABLE: &str = "CREATE TABLE IF NOT EXISTS entries (
+    author BLOB NOT NULL,
+    log_id INTEGER NOT NULL,
+    seq_num INTEGER NOT NULL,
+    entry BLOB NOT NULL,
+    PRIMARY KEY (author, log_id, seq_num)
+) WITHOUT ROWID";
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum Error {
+    #[snafu(display("Could not open sqlite database: {}", source))]
+    OpenDatabase { source: rusqlite::Error },
+    #[snafu(display("Could not create entries table: {}", source))]
+    CreateEntriesTable { source: rusqlite::Error },
+    #[snafu(display("Could not get last seq_num from sqlite: {}", source))]
+    GetLastSeqNum { source: rusqlite::Error },
+    #[snafu(display("Could not get entry {} from sqlite: {}", seq_num, source))]
+    GetEntry {
+        seq_num: u64,
+        source: rusqlite::Error,
+    },
+    #[snafu(display("Could not add entries to sqlite: {}", source))]
+    AddEntries { source: rusqlite::Error },
+    #[snafu(display("Could not remove entries from sqlite: {}", source))]
+    RemoveEntries { source: rusqlite::Error },
+    #[snafu(display("seq_num {} is too large to store in sqlite", seq_num))]
+    SeqNumTooLarge { seq_num: u64 },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// An [EntryStore] for one feed in a sqlite database that can hold many feeds.
+///
+/// Entries are keyed by author, log_id and seq_num. Stores for different feeds can share a
+/// database by sharing the same [Connection]. Entries that are read are cached in the store so
+/// they can be borrowed with `get_entry_ref`, until the store is next written to or
+/// [clear_cache](SqliteEntryStore::clear_cache) is called. Serving a feed, pruning it or
+/// subscribing from an old seq_num reads every entry, so stores that are mostly read from should
+/// call `clear_cache` regularly or the cache grows to hold the whole feed. The last seq_num is
+/// read when the store is created, so each feed should only be written to through one store.
+///
+/// Creating a store turns sqlite's `secure_delete` on, so removed entries are overwritten on disk.
+pub struct SqliteEntryStore {
+    connection: Rc<Connection>,
+    author: [u8; 32],
+    log_id: i64,
+    last_seq_num: Option<u64>,
+    cache: FrozenBTreeMap<u64, Vec<u8>>,
+}
+
+impl SqliteEntryStore {
+    /// Open the feed of `author` and `log_id` in the database at `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        author: PublicKey,
+        log_id: u64,
+    ) -> Result<SqliteEntryStore> {
+        let connection = Connection::open(path).context(OpenDatabase)?;
+        SqliteEntryStore::new(Rc::new(connection), author, log_id)
+    }
+
+    /// Open the feed of `author` and `log_id` in an in memory database.
+    pub fn open_in_memory(author: PublicKey, log_id: u64) -> Result<SqliteEntryStore> {
+        let connection = Connection::open_in_memory().context(OpenDatabase)?;
+        SqliteEntryStore::new(Rc::new(connection), author, log_id)
+    }
+
+    /// Use the feed of `author` and `log_id` in an already open database.
+    pub fn new(
+        connection: Rc<Connection>,
+        author: PublicKey,
+        log_id: u64,
+    ) -> Result<SqliteEntryStore> {
+        connection
+            .pragma_update(None, "secure_delete", true)
+            .context(OpenDatabase)?;
+        connection
+            .execute(CREATE_ENTRIES_TABLE, [])
+            .context(CreateEntriesTable)?;
+
+        let mut store = SqliteEntryStore {
+            connection,
+            author: author.to_bytes(),
+            // Stored as the i64 with the same bits, log_ids are only compared for equality.
+            log_id: log_id as i64,
+            last_seq_num: None,
+            cache: FrozenBTreeMap::new(),
+        };
+        store.last_seq_num = store.query_last_seq_num()?;
+        Ok(store)
+    }
+
+    fn query_last_seq_num(&self) -> Result<Option<u64>> {
+        let last_seq_num: Option<i64> = self
+            .connection
+            .query_row(
+                "SELECT MAX(seq_num) FROM entries WHERE author = ?1 AND log_id = ?2",
+                params![&self.author[..], self.log_id],
+                |row| row.get(0),
+            )
+            .context(GetLastSeqNum)?;
+        Ok(last_seq_num.map(|seq_num| seq_num as u64))
+    }
+
+    /// Drop the entries cached by reads. Writes do this too.
+    pub fn clear_cache(&mut self) {
+        self.cache.as_mut().clear();
+    }
+
+    /// The database connection, to share it with stores for other feeds.
+    pub fn connection(&self) -> &Rc<Connection> {
+        &self.connection
+    }
+}
+
+impl Debug for SqliteEntryStore {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SqliteEntryStore")
+            .field("connection", &self.connection)
+            .field("author", &self.author)
+            .field("log_id", &(self.log_id as u64))
+            .field("last_seq_num", &self.last_seq_num)
+            .finish()
+    }
+}
+
+impl EntryStore for SqliteEntryStore {
+    type Error = Error;
+
+    fn get_last_seq(&self) -> Option<u64> {
+        self.last_seq_num
+    }
+    fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>> {
+        self.get_entry_ref(seq_num)
+            .map(|entry| entry.map(|entry| entry.to_vec()))
+    }
+    fn get_entry_ref(&self, seq_num: u64) -> Result<Option<&[u8]>> {
+        if let Some(entry) = self.cache.get(&seq_num) {
+            return Ok(Some(entry));
+        }
+        let sql_seq_num = match i64::try_from(seq_num) {
+            Ok(sql_seq_num) => sql_seq_num,
+            Err(_) => return Ok(None),
+        };
+
+        let entry: Option<Vec<u8>> = self
+            .connection
+            .prepare_cached(
+                "SELECT entry FROM entries WHERE author = ?1 AND log_id = ?2 AND seq_num = ?3",
+            )
+            .and_then(|mut statement| {
+                statement
+                    .query_row(params![&self.author[..], self.log_id, sql_seq_num], |row| {
+                        row.get(0)
+                    })
+                    .optional()
+            })
+            .context(GetEntry { seq_num })?;
+
+        Ok(entry.map(|entry| self.cache.insert(seq_num, entry)))
+    }
+    fn get_last_entry(&self) -> Result<Option<Vec<u8>>> {
+        match self.get_last_seq() {
+            Some(seq) => self.get_entry(seq),
+            None => Ok(None),
+        }
+    }
+    fn get_last_entry_ref(&self) -> Result<Option<&[u8]>> {
+        match self.get_last_seq() {
+            Some(seq) => self.get_entry_ref(seq),
+            None => Ok(None),
+        }
+    }
+    fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<()> {
+        self.add_entries(&[(seq_num, entry)])
+    }
+    fn add_entries(&mut self, entries: &[(u64, &[u8])]) -> Result<()> {
+        // Nothing can be borrowing from the cache, so empty it to keep it from growing forever.
+        self.clear_cache();
+
+        let transaction = self
+            .connection
+            .unchecked_transaction()
+            .context(AddEntries)?;
+        {
+            let mut statement = transaction
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO entries (author, log_id, seq_num, entry) VALUES (?1, ?2, ?3, ?4)",
+                )
+                .context(AddEntries)?;
+            for (seq_num, entry) in entries {
+                let sql_seq_num = i64::try_from(*seq_num)
+                    .ok()
+                    .context(SeqNumTooLarge { seq_num: *seq_num })?;
+                statement
+                    .execute(params![&self.author[..], self.log_id, sql_seq_num, entry])
+                    .context(AddEntries)?;
+            }
+        }
+        transaction.commit().context(AddEntries)?;
+
+        for (seq_num, _) in entries {
+            self.last_seq_num = self.last_seq_num.max(Some(*seq_num));
+        }
+        Ok(())
+    }
+    fn remove_entry(&mut self, seq_num: u64) -> Result<()> {
+        self.remove_entries(&[seq_num])
+    }
+    fn remove_entries(&mut self, seq_nums: &[u64]) -> Result<()> {
+        self.clear_cache();
+
+        let transaction = self
+            .connection
+            .unchecked_transaction()
+            .context(RemoveEntries)?;
+        {
+            let mut statement = transaction
+                .prepare_cached(
+                    "DELETE FROM entries WHERE author = ?1 AND log_id = ?2 AND seq_num = ?3",
+                )
+                .context(RemoveEntries)?;
+            for seq_num in seq_nums {
+                if let Ok(sql_seq_num) = i64::try_from(*seq_num) {
+                    statement
+                        .execute(params![&self.author[..], self.log_id, sql_seq_num])
+                        .context(RemoveEntries)?;
+                }
+            }
+        }
+        transaction.commit().context(RemoveEntries)?;
+
+        self.last_seq_num = self.query_last_seq_num()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteEntryStore;
+    use crate::entry_store::tests::check_entry_store;
+    use crate::{EntryStore, Log};
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use rand::rngs::OsRng;
+    use std::rc::Rc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn behaves_like_an_entry_store() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        check_entry_store(SqliteEntryStore::open_in_memory(keypair.public, 0).unwrap());
+    }
+
+    #[test]
+    fn adds_all_entries_or_none() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let mut store = SqliteEntryStore::open_in_memory(keypair.public, 0).unwrap();
+
+        let entries = [(1, &[1][..]), (2, &[2][..]), (u64::MAX, &[3][..])];
+        assert!(store.add_entries(&entries).is_err());
+        assert_eq!(store.get_last_seq(), None);
+        assert_eq!(store.get_entry(1).unwrap(), None);
+    }
+
+    #[test]
+    fn only_caches_entries_until_the_next_write() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let mut store = SqliteEntryStore::open_in_memory(keypair.public, 0).unwrap();
+
+        store.add_entries(&[(1, &[1][..]), (2, &[2][..])]).unwrap();
+        assert_eq!(store.cache.len(), 0);
+
+        assert_eq!(store.get_entry_ref(1).unwrap(), Some(&[1][..]));
+        assert_eq!(store.cache.len(), 1);
+
+        store.add_entry(&[3], 3).unwrap();
+        assert_eq!(store.cache.len(), 0);
+
+        store.get_entry_ref(2).unwrap();
+        store.remove_entry(2).unwrap();
+        assert_eq!(store.cache.len(), 0);
+        assert_eq!(store.get_entry_ref(2).unwrap(), None);
+
+        store.get_entry_ref(1).unwrap();
+        store.get_entry_ref(3).unwrap();
+        assert_eq!(store.cache.len(), 2);
+        store.clear_cache();
+        assert_eq!(store.cache.len(), 0);
+        assert_eq!(store.get_entry_ref(3).unwrap(), Some(&[3][..]));
+    }
+
+    #[test]
+    fn stores_many_feeds_in_one_database() {
+        let mut csprng: OsRng = OsRng {};
+        let alice: Keypair = Keypair::generate(&mut csprng);
+        let bob: Keypair = Keypair::generate(&mut csprng);
+        let alice_public = alice.public;
+        let bob_public = bob.public;
+
+        let alice_store = SqliteEntryStore::open_in_memory(alice_public, 0).unwrap();
+        let connection = alice_store.connection().clone();
+        let mut alice_log = Log::new(alice_store, alice_public, Some(alice), 0);
+        let mut bob_log = Log::new(
+            SqliteEntryStore::new(connection.clone(), bob_public, 0).unwrap(),
+            bob_public,
+            Some(bob),
+            0,
+        );
+
+        for i in 1..5u8 {
+            alice_log.publish(&[i], false).unwrap();
+        }
+        bob_log.publish(&[1], false).unwrap();
+
+        let bob_other_log = SqliteEntryStore::new(connection.clone(), bob_public, 1).unwrap();
+        assert_eq!(bob_other_log.get_last_seq(), None);
+
+        let alice_store = SqliteEntryStore::new(connection, alice_public, 0).unwrap();
+        assert_eq!(alice_store.get_last_seq(), Some(4));
+        assert_eq!(
+            alice_store.get_entry_ref(3).unwrap(),
+            alice_log.store.get_entry_ref(3).unwrap()
+        );
+        assert_eq!(bob_log.store.get_last_seq(), Some(1));
+        assert_eq!(bob_log.store.get_entry(2).unwrap(), None);
+    }
+
+    #[test]
+    fn log_entries_persist_across_reopening() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bamboo.sqlite");
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public;
+        let keypair_bytes = keypair.to_bytes();
+
+        let mut log = Log::new(
+            SqliteEntryStore::open(&path, public_key, 0).unwrap(),
+            public_key,
+            Some(keypair),
+            0,
+        );
+        for i in 1..5u8 {
+            log.publish(&[i], false).unwrap();
+        }
+        let entry = log.store.get_entry(4).unwrap().unwrap();
+        drop(log);
+
+        let mut log = Log::new(
+            SqliteEntryStore::open(&path, public_key, 0).unwrap(),
+            public_key,
+            Some(Keypair::from_bytes(&keypair_bytes).unwrap()),
+            0,
+        );
+        assert_eq!(log.store.get_last_entry().unwrap(), Some(entry));
+        log.publish(&[5], false).unwrap();
+        assert_eq!(log.store.get_last_seq(), Some(5));
+        assert_eq!(Rc::strong_count(log.store.connection()), 1);
+    }
+}
@@ -0,0 +1,227 @@
+use super::*;
+use crate::entry_store::memory_entry_store::{Error, MemoryEntryStore};
+use std::collections::BTreeMap;
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+type FeedKey = ([u8; 32], u64);
+
+/// A [FeedStore] that keeps every feed in a [MemoryEntryStore].
+#[derive(Debug, Default)]
+pub struct MemoryFeedStore {
+    pub feeds: BTreeMap<FeedKey, MemoryEntryStore>,
+}
+
+impl MemoryFeedStore {
+    pub fn new() -> MemoryFeedStore {
+        MemoryFeedStore {
+            feeds: BTreeMap::new(),
+        }
+    }
+}
+
+/// A feed of a [MemoryFeedStore], from [feed](FeedStore::feed).
+///
+/// The feed's [MemoryEntryStore] is only created when the first entry is added, and removed again
+/// with its last entry, so looking at feeds never leaves empty ones behind.
+#[derive(Debug)]
+pub struct MemoryFeed<'a> {
+    feeds: &'a mut BTreeMap<FeedKey, MemoryEntryStore>,
+    key: FeedKey,
+}
+
+impl<'a> MemoryFeed<'a> {
+    fn store(&self) -> Option<&MemoryEntryStore> {
+        self.feeds.get(&self.key)
+    }
+}
+
+impl<'a> EntryStore for MemoryFeed<'a> {
+    type Error = Error;
+
+    fn get_last_seq(&self) -> Option<u64> {
+        self.store().and_then(|store| store.get_last_seq())
+    }
+    fn get_entry(&self, seq_num: u64) -> Result<Option<Vec<u8>>> {
+        self.get_entry_ref(seq_num)
+            .map(|entry| entry.map(|entry| entry.to_vec()))
+    }
+    fn get_entry_ref(&self, seq_num: u64) -> Result<Option<&[u8]>> {
+        match self.store() {
+            Some(store) => store.get_entry_ref(seq_num),
+            None => Ok(None),
+        }
+    }
+    fn get_last_entry(&self) -> Result<Option<Vec<u8>>> {
+        match self.get_last_seq() {
+            Some(seq) => self.get_entry(seq),
+            None => Ok(None),
+        }
+    }
+    fn get_last_entry_ref(&self) -> Result<Option<&[u8]>> {
+        match self.get_last_seq() {
+            Some(seq) => self.get_entry_ref(seq),
+            None => Ok(None),
+        }
+    }
+    fn add_entry(&mut self, entry: &[u8], seq_num: u64) -> Result<()> {
+        self.feeds
+            .entry(self.key)
+            .or_default()
+            .add_entry(entry, seq_num)
+    }
+    fn remove_entry(&mut self, seq_num: u64) -> Result<()> {
+        if let Some(store) = self.feeds.get_mut(&self.key) {
+            store.remove_entry(seq_num)?;
+            if store.store.is_empty() {
+                self.feeds.remove(&self.key);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FeedStore for MemoryFeedStore {
+    type Error = Error;
+    type Feed<'a> = MemoryFeed<'a>;
+
+    fn feeds(&self) -> Result<Vec<FeedHead>> {
+        let heads = self
+            .feeds
+            .iter()
+            .filter_map(|((author, log_id), store)| {
+                let last_seq_num = store.get_last_seq()?;
+                // Keys are only ever made from valid public keys.
+                let author = PublicKey::from_bytes(author).expect("feed author is valid");
+                Some(FeedHead {
+                    author,
+                    log_id: *log_id,
+                    last_seq_num,
+                })
+            })
+            .collect();
+        Ok(heads)
+    }
+    fn get_last_seq(&self, author: PublicKey, log_id: u64) -> Result<Option<u64>> {
+        let last_seq = self
+            .feeds
+            .get(&(author.to_bytes(), log_id))
+            .and_then(|store| store.get_last_seq());
+        Ok(last_seq)
+    }
+    fn get_entry_ref(&self, author: PublicKey, log_id: u64, seq_num: u64) -> Result<Option<&[u8]>> {
+        match self.feeds.get(&(author.to_bytes(), log_id)) {
+            Some(store) => store.get_entry_ref(seq_num),
+            None => Ok(None),
+        }
+    }
+    fn feed(&mut self, author: PublicKey, log_id: u64) -> Result<MemoryFeed<'_>> {
+        Ok(MemoryFeed {
+            feeds: &mut self.feeds,
+            key: (author.to_bytes(), log_id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryFeedStore;
+    use crate::entry_store::EntryStore;
+    use crate::feed_store::{FeedHead, FeedStore};
+    use crate::Log;
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn stores_entries_by_author_log_id_and_seq_num() {
+        let mut csprng: OsRng = OsRng {};
+        let alice: Keypair = Keypair::generate(&mut csprng);
+        let bob: Keypair = Keypair::generate(&mut csprng);
+        let alice_public = alice.public;
+        let bob_public = bob.public;
+        let mut feeds = MemoryFeedStore::new();
+
+        let mut alice_log = Log::new(
+            feeds.feed(alice_public, 0).unwrap(),
+            alice_public,
+            Some(alice),
+            0,
+        );
+        for i in 1..4u8 {
+            alice_log.publish(&[i], false).unwrap();
+        }
+        let alice_entry = alice_log.store.get_entry(2).unwrap();
+
+        let mut bob_log = Log::new(feeds.feed(bob_public, 7).unwrap(), bob_public, Some(bob), 7);
+        bob_log.publish(&[1], false).unwrap();
+
+        // Looking at a feed that has no entries doesn't list or keep it.
+        let bob_empty_feed = feeds.feed(bob_public, 0).unwrap();
+        assert_eq!(bob_empty_feed.get_last_seq(), None);
+        assert_eq!(bob_empty_feed.get_entry(1).unwrap(), None);
+        assert_eq!(feeds.feeds.len(), 2);
+
+        assert_eq!(feeds.get_entry(alice_public, 0, 2).unwrap(), alice_entry);
+        assert_eq!(feeds.get_entry(alice_public, 7, 2).unwrap(), None);
+        assert_eq!(feeds.get_last_seq(bob_public, 7).unwrap(), Some(1));
+        assert_eq!(feeds.get_last_seq(bob_public, 0).unwrap(), None);
+
+        let mut heads = feeds.feeds().unwrap();
+        heads.sort_by_key(|head| head.log_id);
+        assert_eq!(
+            heads,
+            vec![
+                FeedHead {
+                    author: alice_public,
+                    log_id: 0,
+                    last_seq_num: 3
+                },
+                FeedHead {
+                    author: bob_public,
+                    log_id: 7,
+                    last_seq_num: 1
+                }
+            ]
+        );
+
+        // Removing the last entry of a feed removes the feed.
+        feeds.feed(bob_public, 7).unwrap().remove_entry(1).unwrap();
+        assert_eq!(feeds.feeds.len(), 1);
+        assert_eq!(feeds.get_last_seq(bob_public, 7).unwrap(), None);
+    }
+
+    #[test]
+    fn a_feed_view_can_be_reopened_as_a_log() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public;
+        let keypair_bytes = keypair.to_bytes();
+        let mut feeds = MemoryFeedStore::new();
+
+        Log::new(
+            feeds.feed(public_key, 0).unwrap(),
+            public_key,
+            Some(keypair),
+            0,
+        )
+        .publish(&[1], false)
+        .unwrap();
+
+        let keypair = Keypair::from_bytes(&keypair_bytes).unwrap();
+        let mut log = Log::new(
+            feeds.feed(public_key, 0).unwrap(),
+            public_key,
+            Some(keypair),
+            0,
+        );
+        log.publish(&[2], false).unwrap();
+
+        let mut remote = MemoryFeedStore::new();
+        let mut remote_log = Log::new(remote.feed(public_key, 0).unwrap(), public_key, None, 0);
+        for seq_num in 1..=2 {
+            let entry = feeds.get_entry(public_key, 0, seq_num).unwrap().unwrap();
+            remote_log.add(&entry, None).unwrap();
+        }
+        assert_eq!(remote.get_last_seq(public_key, 0).unwrap(), Some(2));
+    }
+}
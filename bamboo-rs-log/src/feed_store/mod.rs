@@ -0,0 +1,65 @@
+pub mod memory_feed_store;
+use crate::entry_store::EntryStore;
+use bamboo_rs_core_ed25519_yasmf::PublicKey;
+use core::fmt::Debug;
+use core::fmt::Display;
+pub use memory_feed_store::{MemoryFeed, MemoryFeedStore};
+use snafu::AsErrorSource;
+
+/// A feed held in a [FeedStore] and the seq_num of its latest entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedHead {
+    pub author: PublicKey,
+    pub log_id: u64,
+    pub last_seq_num: u64,
+}
+
+/// Stores the entries of many feeds, keyed by author, log_id and seq_num.
+///
+/// Each feed can be used as an [EntryStore], so it can back a [Log](crate::Log):
+///
+/// ```
+/// use bamboo_rs_core_ed25519_yasmf::Keypair;
+/// use bamboo_rs_log::feed_store::{FeedStore, MemoryFeedStore};
+/// use bamboo_rs_log::Log;
+/// use rand::rngs::OsRng;
+///
+/// let mut csprng = OsRng {};
+/// let key_pair = Keypair::generate(&mut csprng);
+/// let author = key_pair.public;
+///
+/// let mut feeds = MemoryFeedStore::new();
+/// let mut log = Log::new(feeds.feed(author, 0).unwrap(), author, Some(key_pair), 0);
+/// log.publish(b"hello", false).unwrap();
+///
+/// assert_eq!(feeds.get_last_seq(author, 0).unwrap(), Some(1));
+/// assert_eq!(feeds.feeds().unwrap()[0].last_seq_num, 1);
+/// ```
+pub trait FeedStore {
+    type Error: Display + Debug + AsErrorSource;
+    /// The view of a single feed handed out by [feed](FeedStore::feed).
+    type Feed<'a>: EntryStore + Debug
+    where
+        Self: 'a;
+
+    /// Every feed with at least one entry, with its latest seq_num.
+    fn feeds(&self) -> Result<Vec<FeedHead>, Self::Error>;
+    fn get_last_seq(&self, author: PublicKey, log_id: u64) -> Result<Option<u64>, Self::Error>;
+    fn get_entry_ref(
+        &self,
+        author: PublicKey,
+        log_id: u64,
+        seq_num: u64,
+    ) -> Result<Option<&[u8]>, Self::Error>;
+    fn get_entry(
+        &self,
+        author: PublicKey,
+        log_id: u64,
+        seq_num: u64,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.get_entry_ref(author, log_id, seq_num)
+            .map(|entry| entry.map(|entry| entry.to_vec()))
+    }
+    /// The feed of `author` and `log_id`, which is empty if the store doesn't hold it yet.
+    fn feed(&mut self, author: PublicKey, log_id: u64) -> Result<Self::Feed<'_>, Self::Error>;
+}
@@ -1,5 +1,14 @@
+pub mod async_entry_store;
+pub mod async_log;
+mod checks;
 pub mod entry_store;
+pub mod feed_store;
 pub mod log;
+pub mod payload_store;
 
+pub use async_entry_store::AsyncEntryStore;
+pub use async_log::AsyncLog;
 pub use entry_store::EntryStore;
+pub use feed_store::FeedStore;
 pub use log::Log;
+pub use payload_store::PayloadStore;
@@ -1,386 +1,512 @@
-use core::fmt::Debug;
+use super::pending::PendingEntry;
 use super::Log;
+use crate::checks::EntryToAdd;
 use crate::entry_store::EntryStore;
+use crate::payload_store::PayloadStore;
 use bamboo_rs_core_ed25519_yasmf::entry::decode;
-use bamboo_rs_core_ed25519_yasmf::entry::verify;
-use lipmaa_link::lipmaa;
-use snafu::ResultExt;
+use core::fmt::Debug;
+use snafu::{ensure, OptionExt, ResultExt};
+use std::time::Instant;
 
 use super::error::*;
 
-
-impl<Store: EntryStore + Debug> Log<Store> {
-
+impl<Store: EntryStore + Debug, Payloads: PayloadStore + Debug> Log<Store, Payloads> {
     /// Add a valid message to the Log.
     ///
     /// Typically you would use this when you have an entry published by some other author and you
     /// want to add it to your store. This method does a bunch of checking to make sure the entry
-    /// is legit.
+    /// is legit. If the `payload` is given and valid it's added to the payload store.
+    ///
+    /// If the entry's lipmaa entry isn't in the Log yet, the entry's signature is checked and it's
+    /// held in the [pending](Log::pending) buffer until the lipmaa entry is added, so entries can
+    /// arrive in any order. Pending entries that fail verification then are dropped.
+    ///
+    /// Returns the seq_nums appended to the Log: the entry's own, followed by any pending entries
+    /// it let be verified. It's empty if the entry is pending.
     ///
     /// Caveat:
-    /// - the lipmaa link that this message references must already exist in the Log. That means if you
-    /// are doing partial replication, you must sort your messages by sequence number and add them
-    /// from oldest to newest.
-    pub fn add(&mut self, entry_bytes: &[u8], payload: Option<&[u8]>) -> Result<(), Error<Store>> {
-        // Decode the entry that we want to add.
-        let entry = decode(entry_bytes).context(AddEntryDecodeFailed)?;
+    /// - the entry must be published by the log's `public_key` to its `log_id`.
+    /// - if the pending buffer is turned off or the entry doesn't fit in it, the lipmaa entry must
+    ///   already exist in the Log.
+    pub fn add(
+        &mut self,
+        entry_bytes: &[u8],
+        payload: Option<&[u8]>,
+    ) -> Result<Vec<u64>, Error<Store, Payloads>> {
+        self.pending.evict_expired();
+
+        let seq_num = match self.append(entry_bytes, payload) {
+            Err(Error::AddEntryNoLipmaalinkInStore { lipmaa_seq_num }) => {
+                let entry = decode(entry_bytes).context(AddEntryDecodeFailed)?;
+                entry
+                    .verify_signature()
+                    .context(AddEntryFailedVerification)?;
+
+                let pending = PendingEntry {
+                    entry: entry_bytes.to_vec(),
+                    payload: payload.map(|payload| payload.to_vec()),
+                    lipmaa_seq_num,
+                    received: Instant::now(),
+                };
+                ensure!(
+                    self.pending.insert(entry.seq_num, pending),
+                    AddEntryNoLipmaalinkInStore { lipmaa_seq_num }
+                );
+                return Ok(Vec::new());
+            }
+            result => result?,
+        };
+        self.pending.remove(seq_num);
 
-        let lipmaa_seq = match lipmaa(entry.seq_num) {
-            0 => 1,
-            n => n,
+        let mut appended = vec![seq_num];
+        self.append_ready_pending(&mut appended);
+        self.notify_appended(&appended);
+        Ok(appended)
+    }
+
+    /// Append the pending entries whose lipmaa entries are now in the Log, pushing their seq_nums
+    /// to `appended`.
+    pub(crate) fn append_ready_pending(&mut self, appended: &mut Vec<u64>) {
+        loop {
+            let store = &self.store;
+            let ready = self.pending.take_ready(|lipmaa_seq_num| {
+                matches!(store.get_entry_ref(lipmaa_seq_num), Ok(Some(_)))
+            });
+            let (seq_num, pending) = match ready {
+                Some(ready) => ready,
+                None => break,
+            };
+            if self
+                .append(&pending.entry, pending.payload.as_deref())
+                .is_ok()
+            {
+                appended.push(seq_num);
+            }
+        }
+    }
+
+    /// Verify the entry against the Log and append it, returning its seq_num.
+    fn append(
+        &mut self,
+        entry_bytes: &[u8],
+        payload: Option<&[u8]>,
+    ) -> Result<u64, Error<Store, Payloads>> {
+        // Decode the entry that we want to add.
+        let to_add = EntryToAdd::decode(entry_bytes, &self.public_key, self.log_id)?;
+        let entry = &to_add.entry;
+
+        // Get the lipmaa entry, if the entry links to one.
+        let lipmaa = match to_add.lipmaa_seq_num() {
+            Some(lipmaa_seq_num) => {
+                let lipmaa = self
+                    .store
+                    .get_entry_ref(lipmaa_seq_num)
+                    .context(AddEntryGetLipmaaEntry)?
+                    .context(AddEntryNoLipmaalinkInStore { lipmaa_seq_num })?;
+                Some(lipmaa)
+            }
+            None => None,
         };
 
-        // Get the lipmaa entry.
-        let lipmaa = self.store.get_entry_ref(lipmaa_seq)
-            .context(AddEntryGetLipmaaEntry)?;
-        // Try and get the backlink entry. If we have it, hash it and check it is correct.
-        let backlink = self.store.get_entry_ref(entry.seq_num - 1)
+        // Try and get the backlink entry. If we have it, verify checks it is correct.
+        let backlink = self
+            .store
+            .get_entry_ref(entry.seq_num - 1)
             .context(AddEntryGetBacklinkEntry)?;
 
-        verify(entry_bytes, payload, lipmaa, backlink)
-            .context(AddEntryFailedVerification)?;
+        to_add.verify(payload, lipmaa, backlink)?;
 
         //Ok, store it!
         self.store
-            .add_entry(&entry_bytes, entry.seq_num)
-            .context(AddEntryFailedToAddEntryToLog)
+            .add_entry(entry_bytes, entry.seq_num)
+            .context(AddEntryFailedToAddEntryToLog)?;
+
+        // Only once the entry is in the log, so a failed append leaves no orphaned payload.
+        if let Some(payload) = payload {
+            self.payload_store
+                .add_payload(entry.payload_hash.clone(), payload)
+                .context(AddEntryAddPayloadFailed)?;
+        }
+        Ok(entry.seq_num)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::entry_store::MemoryEntryStore;
+    use crate::log::tests::{empty_copy, n_valid_entries};
+    use crate::log::{Error, PendingLimits};
     use crate::{EntryStore, Log};
-    use arrayvec::ArrayVec;
+    use bamboo_rs_core_ed25519_yasmf::entry::decode::Error as DecodeError;
+    use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
     use bamboo_rs_core_ed25519_yasmf::signature::{Signature, ED25519_SIGNATURE_SIZE};
     use bamboo_rs_core_ed25519_yasmf::yasmf_hash::{new_blake3, YasmfHash};
-    use bamboo_rs_core_ed25519_yasmf::yamf_signatory::YamfSignatory;
-    use bamboo_rs_core_ed25519_yasmf::Error;
     use bamboo_rs_core_ed25519_yasmf::{Entry, Keypair};
+    use ed25519_dalek::Signer;
     use rand::rngs::OsRng;
     use std::convert::TryInto;
+    use std::time::Duration;
+
+    /// Sign `entry` with the key pair of `log` and encode it.
+    fn sign(entry: Entry<&[u8], &[u8]>, log: &Log<MemoryEntryStore>) -> Vec<u8> {
+        let mut unsigned_bytes = Vec::new();
+        entry.encode_for_signing_write(&mut unsigned_bytes).unwrap();
+
+        let signature = log.key_pair.as_ref().unwrap().sign(&unsigned_bytes);
+        let sig_bytes = signature.to_bytes();
+        let entry = Entry {
+            sig: Some(Signature(&sig_bytes[..])),
+            ..entry
+        };
 
-    fn n_valid_entries(n: u64) -> Log<MemoryEntryStore> {
-        let mut csprng: OsRng = OsRng::new().unwrap();
-        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let mut entry_bytes = Vec::new();
+        entry.encode_write(&mut entry_bytes).unwrap();
+        entry_bytes
+    }
 
-        let mut log = Log::new(
-            MemoryEntryStore::new(),
-            keypair.public.clone(),
-            Some(keypair),
-        );
+    #[test]
+    fn add_entries_in_order() {
+        let remote_log = n_valid_entries(10);
+        let mut log = empty_copy(&remote_log);
+
+        for seq_num in 1..10 {
+            let entry = remote_log.store.get_entry(seq_num).unwrap().unwrap();
+            let payload = format!("message number {}", seq_num);
+            log.add(&entry, Some(payload.as_bytes())).unwrap();
+        }
 
-        (1..n).into_iter().for_each(|i| {
-            let payload = format!("message number {}", i);
-            log.publish(&payload.as_bytes(), false).unwrap();
-        });
+        assert_eq!(log.store.get_last_seq(), Some(9));
+    }
 
-        log
+    #[test]
+    fn add_checks_entry_is_from_this_log() {
+        let remote_log = n_valid_entries(3);
+        let first_entry = remote_log.store.get_entry(1).unwrap().unwrap();
+
+        let other_author = n_valid_entries(1);
+        let mut log = empty_copy(&other_author);
+        match log.add(&first_entry, None) {
+            Err(Error::AddEntryAuthorDoesNotMatchLog) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 1);
+        match log.add(&first_entry, None) {
+            Err(Error::AddEntryLogIdDoesNotMatchLog {
+                actual: 0,
+                expected: 1,
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
     }
 
     #[test]
     fn add_checks_payload_is_correct_length() {
         let remote_log = n_valid_entries(3);
+        let mut log = empty_copy(&remote_log);
 
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
-
-        let mut first_entry: Entry<&[u8], &[u8], &[u8]> = remote_log
-            .store
-            .get_entry_ref(1)
-            .unwrap()
-            .unwrap()
-            .try_into()
-            .unwrap();
+        let first_entry_bytes = remote_log.store.get_entry(1).unwrap().unwrap();
+        let mut first_entry: Entry<&[u8], &[u8]> = first_entry_bytes[..].try_into().unwrap();
 
         first_entry.payload_size = 1; //Set an invalid payload length. Zero tolerance etc ;)
 
-        let entry_bytes: ArrayVec<_> = first_entry.try_into().unwrap();
+        let entry_bytes = sign(first_entry, &remote_log);
 
         match log.add(&entry_bytes, Some(b"message number 1")) {
-            Err(Error::AddEntryPayloadLengthDidNotMatch) => {}
-            _ => panic!("Expected err"),
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::PayloadLengthDidNotMatch { .. },
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
         }
     }
 
     #[test]
     fn add_checks_payload_is_correct_hash() {
         let remote_log = n_valid_entries(3);
-
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+        let mut log = empty_copy(&remote_log);
 
         let first_entry = remote_log.store.get_entry(1).unwrap().unwrap();
 
         match log.add(&first_entry, Some(&[0, 1])) {
-            Err(Error::AddEntryPayloadHashDidNotMatch) => {}
-            _ => panic!("Expected err"),
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::PayloadHashDidNotMatch { .. },
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
         }
     }
 
     #[test]
     fn add_checks_entry_not_after_end_of_feed() {
-        let mut csprng: OsRng = OsRng::new().unwrap();
+        let mut csprng: OsRng = OsRng {};
         let keypair: Keypair = Keypair::generate(&mut csprng);
 
-        let mut remote_log = Log::new(
-            MemoryEntryStore::new(),
-            keypair.public.clone(),
-            Some(keypair),
-        );
+        let mut remote_log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0);
 
         let payload = format!("message number {}", 1);
-        remote_log.publish(&payload.as_bytes(), true).unwrap();
+        remote_log.publish(payload.as_bytes(), true).unwrap();
 
-        let first_entry = remote_log.store.get_entry_ref(1).unwrap().unwrap();
+        let first_entry = remote_log.store.get_entry(1).unwrap().unwrap();
 
-        let backlink = new_blake3(first_entry);
-        let lipmaa_link = new_blake3(first_entry);
+        let backlink = new_blake3(&first_entry);
+        let payload_hash = new_blake3(payload.as_bytes());
 
-        let mut second_entry = Entry::<_, _, &[u8]> {
+        let second_entry = Entry {
+            log_id: 0,
             is_end_of_feed: false,
-            payload_hash: new_blake3(&payload.as_bytes()),
+            payload_hash: YasmfHash::from(&payload_hash),
             payload_size: payload.len() as u64,
-            author: YamfSignatory::Ed25519(&remote_log.public_key.as_bytes()[..], None),
+            author: remote_log.public_key,
             seq_num: 2,
-            backlink: Some(backlink),
-            lipmaa_link: Some(lipmaa_link),
+            backlink: Some(YasmfHash::from(&backlink)),
+            lipmaa_link: None,
             sig: None,
         };
+        let second_entry_bytes = sign(second_entry, &remote_log);
 
-        let mut second_entry_bytes = Vec::new();
-        second_entry.encode_write(&mut second_entry_bytes).unwrap();
+        let mut log = empty_copy(&remote_log);
 
-        let signature = remote_log.key_pair.unwrap().sign(&second_entry_bytes);
-        let sig_bytes = &signature.to_bytes()[..];
-        let signature = Signature(sig_bytes.into());
+        log.add(&first_entry, None).unwrap();
 
-        second_entry.sig = Some(signature);
+        match log.add(&second_entry_bytes, None) {
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::PublishedAfterEndOfFeed,
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+    }
 
-        let mut second_entry_bytes = Vec::new();
-        second_entry.encode_write(&mut second_entry_bytes).unwrap();
+    #[test]
+    fn add_needs_lipmaa_link_in_store() {
+        let remote_log = n_valid_entries(5);
+        let mut log = empty_copy(&remote_log);
 
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+        log.pending_mut().set_limits(PendingLimits {
+            max_entries: 0,
+            ..PendingLimits::default()
+        });
 
-        log.add(&first_entry, None).unwrap();
+        // Entry 4 links back to entry 1.
+        let fourth_entry = remote_log.store.get_entry(4).unwrap().unwrap();
 
-        match log.add(&second_entry_bytes, None) {
-            Err(Error::AddEntryToFeedThatHasEnded) => {}
-            _ => panic!("Expected err"),
+        match log.add(&fourth_entry, None) {
+            Err(Error::AddEntryNoLipmaalinkInStore { lipmaa_seq_num: 1 }) => {}
+            e => panic!("Expected err, got {:?}", e),
         }
     }
 
     #[test]
-    fn add_needs_lipmaa_link_in_store() {
-        let remote_log = n_valid_entries(3);
+    fn add_holds_entries_until_their_lipmaa_entry_is_added() {
+        let remote_log = n_valid_entries(15);
+        let mut log = empty_copy(&remote_log);
+        let entries: Vec<_> = (1..15)
+            .map(|seq_num| remote_log.store.get_entry(seq_num).unwrap().unwrap())
+            .collect();
+
+        // Entries 8 and 13 link to 4, which links to 1.
+        assert_eq!(log.add(&entries[7], None).unwrap(), Vec::<u64>::new());
+        assert_eq!(log.add(&entries[3], None).unwrap(), Vec::<u64>::new());
+        assert_eq!(log.add(&entries[12], None).unwrap(), Vec::<u64>::new());
+        assert_eq!(
+            log.pending()
+                .iter()
+                .map(|(seq_num, _)| seq_num)
+                .collect::<Vec<_>>(),
+            vec![4, 8, 13]
+        );
+        assert_eq!(log.pending().get(13).unwrap().lipmaa_seq_num, 4);
+        assert_eq!(log.store.get_last_seq(), None);
+
+        assert_eq!(log.add(&entries[0], None).unwrap(), vec![1, 4, 8, 13]);
+        assert!(log.pending().is_empty());
+        assert_eq!(log.store.get_last_seq(), Some(13));
+    }
+
+    #[test]
+    fn add_checks_pending_entries_signature() {
+        let remote_log = n_valid_entries(5);
+        let mut log = empty_copy(&remote_log);
+
+        let mut fourth_entry = remote_log.store.get_entry(4).unwrap().unwrap();
+        let last = fourth_entry.len() - 1;
+        fourth_entry[last] ^= 1;
+
+        match log.add(&fourth_entry, None) {
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::InvalidSignature,
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+        assert!(log.pending().is_empty());
+    }
 
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+    #[test]
+    fn add_drops_pending_entries_that_fail_verification() {
+        let remote_log = n_valid_entries(5);
+        let mut log = empty_copy(&remote_log);
+        let first_entry = remote_log.store.get_entry(1).unwrap().unwrap();
+        let fourth_entry = remote_log.store.get_entry(4).unwrap().unwrap();
+
+        // A payload that doesn't match is only found out once entry 1 arrives.
+        log.add(&fourth_entry, Some(b"not the payload")).unwrap();
+        assert_eq!(log.pending().len(), 1);
+
+        assert_eq!(log.add(&first_entry, None).unwrap(), vec![1]);
+        assert!(log.pending().is_empty());
+        assert_eq!(log.store.get_entry(4).unwrap(), None);
+    }
 
-        let second_entry = remote_log.store.get_entry(2).unwrap().unwrap();
+    #[test]
+    fn pending_entries_are_evicted_by_size_and_age() {
+        let remote_log = n_valid_entries(20);
+        let mut log = empty_copy(&remote_log);
+        log.pending_mut().set_limits(PendingLimits {
+            max_entries: 2,
+            ..PendingLimits::default()
+        });
 
-        match log.add(&second_entry, None) {
-            Err(Error::AddEntryNoLipmaalinkInStore) => {}
-            _ => panic!("Expected err"),
+        for seq_num in [4, 8, 13].iter() {
+            let entry = remote_log.store.get_entry(*seq_num).unwrap().unwrap();
+            log.add(&entry, None).unwrap();
         }
+        assert_eq!(
+            log.pending()
+                .iter()
+                .map(|(seq_num, _)| seq_num)
+                .collect::<Vec<_>>(),
+            vec![8, 13]
+        );
+
+        let bytes = log.pending().bytes();
+        log.pending_mut().set_limits(PendingLimits {
+            max_bytes: bytes - 1,
+            ..PendingLimits::default()
+        });
+        assert_eq!(log.pending().len(), 1);
+        assert!(log.pending().get(13).is_some());
+
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(
+            log.pending_mut().evict_older_than(Duration::from_secs(0)),
+            1
+        );
+        assert!(log.pending().is_empty());
     }
 
     #[test]
     fn add_needs_valid_signature() {
         let remote_log = n_valid_entries(3);
+        let mut log = empty_copy(&remote_log);
 
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
-
-        let mut first_entry: Entry<&[u8], &[u8], &[u8]> = remote_log
-            .store
-            .get_entry_ref(1)
-            .unwrap()
-            .unwrap()
-            .try_into()
-            .unwrap();
+        let first_entry_bytes = remote_log.store.get_entry(1).unwrap().unwrap();
+        let mut first_entry: Entry<&[u8], &[u8]> = first_entry_bytes[..].try_into().unwrap();
 
         let incorrect_sig_bytes = [0u8; ED25519_SIGNATURE_SIZE];
-        first_entry.sig = match first_entry.sig {
-            Some(Signature(_)) => Some(Signature(&incorrect_sig_bytes)),
-            link => link,
-        };
+        first_entry.sig = Some(Signature(&incorrect_sig_bytes[..]));
 
-        let entry_bytes: ArrayVec<_> = first_entry.try_into().unwrap();
+        let mut entry_bytes = Vec::new();
+        first_entry.encode_write(&mut entry_bytes).unwrap();
 
         match log.add(&entry_bytes, None) {
-            Err(Error::AddEntryWithInvalidSignature) => {}
-            _ => panic!("Expected err"),
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::InvalidSignature,
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
         }
     }
 
     #[test]
     fn add_checks_lipmaa_link_is_valid() {
-        let remote_log = n_valid_entries(3);
-
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+        let remote_log = n_valid_entries(5);
+        let mut log = empty_copy(&remote_log);
 
-        let first_entry_bytes = remote_log.store.get_entry(1).unwrap().unwrap();
-        let mut second_entry: Entry<&[u8], &[u8], &[u8]> = remote_log
-            .store
-            .get_entry_ref(2)
-            .unwrap()
-            .unwrap()
-            .try_into()
-            .unwrap();
+        for seq_num in 1..4 {
+            let entry = remote_log.store.get_entry(seq_num).unwrap().unwrap();
+            log.add(&entry, None)
+                .expect("error adding entry, this is not normal");
+        }
 
-        log.add(&first_entry_bytes, None)
-            .expect("error adding first entry, this is not normal");
+        let fourth_entry_bytes = remote_log.store.get_entry(4).unwrap().unwrap();
+        let mut fourth_entry: Entry<&[u8], &[u8]> = fourth_entry_bytes[..].try_into().unwrap();
 
         let incorrect_lipmaa = new_blake3(b"noooo");
+        fourth_entry.lipmaa_link = Some(YasmfHash::from(&incorrect_lipmaa));
 
-        second_entry.lipmaa_link = match second_entry.lipmaa_link {
-            Some(YasmfHash::Blake3(_)) => Some(YasmfHash::from(&incorrect_lipmaa)),
-            link => link,
-        }; //set the lipmaa link to be zero
-
-        let entry_bytes: ArrayVec<_> = second_entry.try_into().unwrap();
+        let entry_bytes = sign(fourth_entry, &remote_log);
 
         match log.add(&entry_bytes, None) {
-            Err(Error::AddEntryLipmaaHashDidNotMatch) => {}
-            _ => panic!("Expected err"),
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::LipmaaHashDoesNotMatch { .. },
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
         }
     }
 
     #[test]
     fn add_checks_backlink_is_valid() {
         let remote_log = n_valid_entries(3);
-
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+        let mut log = empty_copy(&remote_log);
 
         let first_entry_bytes = remote_log.store.get_entry(1).unwrap().unwrap();
-
-        let mut second_entry: Entry<_, _, _> = remote_log
-            .store
-            .get_entry_ref(2)
-            .unwrap()
-            .unwrap()
-            .try_into()
-            .unwrap();
+        let second_entry_bytes = remote_log.store.get_entry(2).unwrap().unwrap();
+        let mut second_entry: Entry<&[u8], &[u8]> = second_entry_bytes[..].try_into().unwrap();
 
         log.add(&first_entry_bytes, None)
             .expect("error adding first entry, this is not normal");
 
         let incorrect_backlink = new_blake3(b"noooo");
-        second_entry.backlink = match second_entry.backlink {
-            Some(YasmfHash::Blake3(_)) => Some(YasmfHash::from(&incorrect_backlink)),
-            link => link,
-        }; //set the lipmaa link to be zero
+        second_entry.backlink = Some(YasmfHash::from(&incorrect_backlink));
 
-        let entry_bytes: ArrayVec<_> = second_entry.try_into().unwrap();
+        let entry_bytes = sign(second_entry, &remote_log);
 
         match log.add(&entry_bytes, None) {
-            Err(Error::AddEntryBacklinkHashDidNotMatch) => {}
-            _ => panic!("Expected err"),
+            Err(Error::AddEntryFailedVerification {
+                source: VerifyError::BacklinkHashDoesNotMatch { .. },
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
         }
     }
+
     #[test]
     fn add_checks_lipmaa_link_is_present() {
-        let mut csprng: OsRng = OsRng::new().unwrap();
-        let keypair: Keypair = Keypair::generate(&mut csprng);
-
-        let mut remote_log = Log::new(
-            MemoryEntryStore::new(),
-            keypair.public.clone(),
-            Some(keypair),
-        );
-
-        let payload = format!("message number {}", 1);
-        remote_log.publish(&payload.as_bytes(), false).unwrap();
-
-        let first_entry = remote_log.store.get_entry_ref(1).unwrap().unwrap();
-
-        let backlink = new_blake3(first_entry);
-
-        let mut second_entry = Entry::<_, _, &[u8]> {
-            is_end_of_feed: false,
-            payload_hash: new_blake3(&payload.as_bytes()),
-            payload_size: payload.len() as u64,
-            author: YamfSignatory::Ed25519(&remote_log.public_key.as_bytes()[..], None),
-            seq_num: 2,
-            backlink: Some(backlink),
-            lipmaa_link: None,
-            sig: None,
-        };
-
-        let mut second_entry_bytes = Vec::new();
-        second_entry.encode_write(&mut second_entry_bytes).unwrap();
-
-        let signature = remote_log.key_pair.unwrap().sign(&second_entry_bytes);
-        let sig_bytes = &signature.to_bytes()[..];
-        let signature = Signature(sig_bytes.into());
+        let remote_log = n_valid_entries(5);
+        let mut log = empty_copy(&remote_log);
 
-        second_entry.sig = Some(signature);
-
-        let mut second_entry_bytes = Vec::new();
-        second_entry.encode_write(&mut second_entry_bytes).unwrap();
+        for seq_num in 1..4 {
+            let entry = remote_log.store.get_entry(seq_num).unwrap().unwrap();
+            log.add(&entry, None).unwrap();
+        }
 
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+        // Entry 4 requires a lipmaa link to entry 1.
+        let fourth_entry_bytes = remote_log.store.get_entry(4).unwrap().unwrap();
+        let mut fourth_entry: Entry<&[u8], &[u8]> = fourth_entry_bytes[..].try_into().unwrap();
+        fourth_entry.lipmaa_link = None;
 
-        log.add(&first_entry, None).unwrap();
+        let entry_bytes = sign(fourth_entry, &remote_log);
 
-        match log.add(&second_entry_bytes, None) {
-            Err(Error::AddEntryDecodeFailed) => {}
+        match log.add(&entry_bytes, None) {
+            Err(Error::AddEntryDecodeFailed { .. }) => {}
             e => panic!("Expected err, {:?}", e),
         }
     }
+
     #[test]
     fn add_checks_back_link_is_present() {
-        let mut csprng: OsRng = OsRng::new().unwrap();
-        let keypair: Keypair = Keypair::generate(&mut csprng);
-
-        let mut remote_log = Log::new(
-            MemoryEntryStore::new(),
-            keypair.public.clone(),
-            Some(keypair),
-        );
-
-        let payload = format!("message number {}", 1);
-        remote_log.publish(&payload.as_bytes(), false).unwrap();
-
-        let first_entry = remote_log.store.get_entry_ref(1).unwrap().unwrap();
-
-        let lipmaa_link = new_blake3(first_entry);
-
-        let mut second_entry = Entry::<_, _, &[u8]> {
-            is_end_of_feed: false,
-            payload_hash: new_blake3(&payload.as_bytes()),
-            payload_size: payload.len() as u64,
-            author: YamfSignatory::Ed25519(&remote_log.public_key.as_ref()[..], None),
-            seq_num: 2,
-            backlink: None,
-            lipmaa_link: Some(lipmaa_link),
-            sig: None,
-        };
-
-        let mut second_entry_bytes = Vec::new();
-        second_entry.encode_write(&mut second_entry_bytes).unwrap();
-
-        let signature = remote_log.key_pair.unwrap().sign(&second_entry_bytes);
-        let sig_bytes = &signature.to_bytes()[..];
-        let signature = Signature(sig_bytes.into());
-
-        second_entry.sig = Some(signature);
+        let remote_log = n_valid_entries(3);
+        let mut log = empty_copy(&remote_log);
 
-        let mut second_entry_bytes = Vec::new();
-        second_entry.encode_write(&mut second_entry_bytes).unwrap();
+        let first_entry = remote_log.store.get_entry(1).unwrap().unwrap();
+        log.add(&first_entry, None).unwrap();
 
-        let mut log: Log<MemoryEntryStore> =
-            Log::new(MemoryEntryStore::new(), remote_log.public_key, None);
+        let second_entry_bytes = remote_log.store.get_entry(2).unwrap().unwrap();
+        let mut second_entry: Entry<&[u8], &[u8]> = second_entry_bytes[..].try_into().unwrap();
+        second_entry.backlink = None;
 
-        log.add(&first_entry, None).unwrap();
+        let entry_bytes = sign(second_entry, &remote_log);
 
-        match log.add(&second_entry_bytes, None) {
-            Err(Error::AddEntryDecodeFailed) => {}
+        match log.add(&entry_bytes, None) {
+            Err(Error::AddEntryDecodeFailed {
+                source: DecodeError::DecodeBacklinkError { .. },
+            }) => {}
             e => panic!("Expected err, {:?}", e),
         }
     }
@@ -0,0 +1,292 @@
+use super::Log;
+use crate::checks::{AddError, EntryToAdd};
+use crate::entry_store::EntryStore;
+use crate::payload_store::PayloadStore;
+use bamboo_rs_core_ed25519_yasmf::verify_batch_with_linked_entries;
+use core::fmt::Debug;
+use lipmaa_link::lipmaa;
+use snafu::{OptionExt, ResultExt};
+use std::collections::BTreeSet;
+
+use super::error::*;
+
+impl<Store: EntryStore + Debug, Payloads: PayloadStore + Debug> Log<Store, Payloads> {
+    /// Add a batch of entries, verifying them all in parallel with
+    /// [verify_batch](bamboo_rs_core_ed25519_yasmf::verify_batch).
+    ///
+    /// The entries can be in any order. Links to entries that aren't in the batch are checked
+    /// against the entries in the store, and like [add](Log::add), the lipmaa entry of every entry
+    /// must be in the batch or the store. The entries are appended in seq_num order with
+    /// [add_entries](EntryStore::add_entries), and only once the whole batch is verified. Any
+    /// payloads given are then added to the payload store, so a failed append leaves no payloads
+    /// without entries.
+    ///
+    /// Returns the seq_nums appended to the Log: the batch's, followed by any pending entries it
+    /// let be verified.
+    pub fn add_batch<E, P>(
+        &mut self,
+        entries_and_payloads: &[(E, Option<P>)],
+    ) -> Result<Vec<u64>, Error<Store, Payloads>>
+    where
+        E: AsRef<[u8]> + Sync,
+        P: AsRef<[u8]> + Sync,
+    {
+        let mut batch = entries_and_payloads
+            .iter()
+            .map(|(entry_bytes, payload)| {
+                let to_add =
+                    EntryToAdd::decode(entry_bytes.as_ref(), &self.public_key, self.log_id)
+                        .map_err(batch_error)?;
+                Ok((
+                    to_add.entry,
+                    entry_bytes.as_ref(),
+                    payload.as_ref().map(|payload| payload.as_ref()),
+                ))
+            })
+            .collect::<Result<Vec<_>, Error<Store, Payloads>>>()?;
+        batch.sort_by_key(|(entry, _, _)| entry.seq_num);
+
+        let seq_nums: BTreeSet<u64> = batch.iter().map(|(entry, _, _)| entry.seq_num).collect();
+        if seq_nums.len() < batch.len() {
+            let duplicate = batch
+                .windows(2)
+                .find(|pair| pair[0].0.seq_num == pair[1].0.seq_num)
+                .map(|pair| pair[0].0.seq_num)
+                .expect("batch has a duplicate seq_num");
+            return AddBatchDuplicateSeqNum { seq_num: duplicate }.fail();
+        }
+
+        // The entries in the store that the batch links to, to check the links against.
+        let mut linked = BTreeSet::new();
+        for (entry, _, _) in batch.iter() {
+            let lipmaa_seq_num = lipmaa(entry.seq_num);
+            if entry.lipmaa_link.is_some() && !seq_nums.contains(&lipmaa_seq_num) {
+                self.store
+                    .get_entry_ref(lipmaa_seq_num)
+                    .context(AddBatchGetLinkedEntry)?
+                    .context(AddBatchNoLipmaalinkInStore {
+                        seq_num: entry.seq_num,
+                        lipmaa_seq_num,
+                    })?;
+                linked.insert(lipmaa_seq_num);
+            }
+
+            let backlink_seq_num = entry.seq_num - 1;
+            if backlink_seq_num > 0
+                && !seq_nums.contains(&backlink_seq_num)
+                && self
+                    .store
+                    .get_entry_ref(backlink_seq_num)
+                    .context(AddBatchGetLinkedEntry)?
+                    .is_some()
+            {
+                linked.insert(backlink_seq_num);
+            }
+        }
+
+        let mut linked_entries = Vec::with_capacity(linked.len());
+        for seq_num in linked {
+            if let Some(entry_bytes) = self
+                .store
+                .get_entry_ref(seq_num)
+                .context(AddBatchGetLinkedEntry)?
+            {
+                linked_entries.push(entry_bytes);
+            }
+        }
+        let to_verify: Vec<(&[u8], Option<&[u8]>)> = batch
+            .iter()
+            .map(|(_, entry_bytes, payload)| (*entry_bytes, *payload))
+            .collect();
+        verify_batch_with_linked_entries(&to_verify, &linked_entries)
+            .context(AddBatchFailedVerification)?;
+
+        let entries: Vec<(u64, &[u8])> = batch
+            .iter()
+            .map(|(entry, entry_bytes, _)| (entry.seq_num, *entry_bytes))
+            .collect();
+        self.store
+            .add_entries(&entries)
+            .context(AddBatchFailedToAddEntriesToLog)?;
+
+        for (entry, _, payload) in batch.iter() {
+            if let Some(payload) = payload {
+                self.payload_store
+                    .add_payload(entry.payload_hash.clone(), payload)
+                    .context(AddBatchAddPayloadFailed)?;
+            }
+        }
+
+        let mut appended: Vec<u64> = seq_nums.into_iter().collect();
+        for seq_num in appended.iter() {
+            self.pending.remove(*seq_num);
+        }
+        self.append_ready_pending(&mut appended);
+        self.notify_appended(&appended);
+        Ok(appended)
+    }
+}
+
+/// Convert an error from [EntryToAdd::decode] into the error for the batch entry it came from.
+fn batch_error<ES: EntryStore + Debug, PS: PayloadStore + Debug>(error: AddError) -> Error<ES, PS> {
+    match error {
+        AddError::Decode { source } => Error::AddBatchDecodeFailed { source },
+        AddError::AuthorDoesNotMatchLog { seq_num } => {
+            Error::AddBatchAuthorDoesNotMatchLog { seq_num }
+        }
+        AddError::LogIdDoesNotMatchLog {
+            seq_num,
+            actual,
+            expected,
+        } => Error::AddBatchLogIdDoesNotMatchLog {
+            seq_num,
+            actual,
+            expected,
+        },
+        AddError::Verification { source } => Error::AddBatchFailedVerification { source },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entry_store::MemoryEntryStore;
+    use crate::log::tests::{entries_and_payloads, payloads, published_log};
+    use crate::log::Error;
+    use crate::{EntryStore, Log};
+    use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn add_batch_appends_in_seq_num_order() {
+        let mut csprng: OsRng = OsRng {};
+        let remote_log = published_log(Keypair::generate(&mut csprng), &payloads(1..100));
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+
+        let mut batch = entries_and_payloads(&remote_log, 1..100);
+        batch.reverse();
+        assert_eq!(
+            log.add_batch(&batch).unwrap(),
+            (1..100).collect::<Vec<u64>>()
+        );
+        assert_eq!(log.store.get_last_seq(), Some(99));
+        assert_eq!(
+            log.get_payload(42).unwrap(),
+            Some(b"message number 42".to_vec())
+        );
+    }
+
+    #[test]
+    fn add_batch_checks_links_into_the_store() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair = Keypair::generate(&mut csprng);
+        let keypair_bytes = keypair.to_bytes();
+        let remote_log = published_log(keypair, &payloads(1..20));
+        // The same author forks the log after entry 9.
+        let mut fork_payloads = payloads(1..10);
+        fork_payloads.extend(payloads(100..110));
+        let fork = published_log(Keypair::from_bytes(&keypair_bytes).unwrap(), &fork_payloads);
+
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+        log.add_batch(&entries_and_payloads(&remote_log, 1..11))
+            .unwrap();
+
+        match log.add_batch(&entries_and_payloads(&fork, 11..20)) {
+            Err(Error::AddBatchFailedVerification {
+                source: VerifyError::BacklinkHashDoesNotMatch { .. },
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+        assert_eq!(log.store.get_last_seq(), Some(10));
+
+        assert_eq!(
+            log.add_batch(&entries_and_payloads(&remote_log, 11..20))
+                .unwrap(),
+            (11..20).collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    fn add_batch_appends_nothing_if_an_entry_is_invalid() {
+        let mut csprng: OsRng = OsRng {};
+        let remote_log = published_log(Keypair::generate(&mut csprng), &payloads(1..10));
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+
+        let mut batch = entries_and_payloads(&remote_log, 1..10);
+        batch[5].1 = Some(b"not the payload".to_vec());
+
+        match log.add_batch(&batch) {
+            Err(Error::AddBatchFailedVerification { .. }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+        assert_eq!(log.store.get_last_seq(), None);
+        assert!(log.payload_store.store.is_empty());
+    }
+
+    #[test]
+    fn add_batch_needs_lipmaa_links_in_the_batch_or_store() {
+        let mut csprng: OsRng = OsRng {};
+        let remote_log = published_log(Keypair::generate(&mut csprng), &payloads(1..10));
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+
+        // Entry 8 links to 4.
+        match log.add_batch(&entries_and_payloads(&remote_log, 5..9)) {
+            Err(Error::AddBatchNoLipmaalinkInStore {
+                seq_num: 8,
+                lipmaa_seq_num: 4,
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn add_batch_checks_entries_are_from_this_log() {
+        let mut csprng: OsRng = OsRng {};
+        let remote_log = published_log(Keypair::generate(&mut csprng), &payloads(1..3));
+        let other_log = published_log(Keypair::generate(&mut csprng), &payloads(1..3));
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+
+        let mut batch = entries_and_payloads(&remote_log, 1..2);
+        batch.extend(entries_and_payloads(&other_log, 2..3));
+        match log.add_batch(&batch) {
+            Err(Error::AddBatchAuthorDoesNotMatchLog { seq_num: 2 }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+
+        let mut batch = entries_and_payloads(&remote_log, 1..3);
+        batch.extend(entries_and_payloads(&remote_log, 2..3));
+        match log.add_batch(&batch) {
+            Err(Error::AddBatchDuplicateSeqNum { seq_num: 2 }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 1);
+        match log.add_batch(&entries_and_payloads(&remote_log, 1..3)) {
+            Err(Error::AddBatchLogIdDoesNotMatchLog {
+                seq_num: 1,
+                actual: 0,
+                expected: 1,
+            }) => {}
+            e => panic!("Expected err, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn add_batch_appends_pending_entries_it_lets_be_verified() {
+        let mut csprng: OsRng = OsRng {};
+        let remote_log = published_log(Keypair::generate(&mut csprng), &payloads(1..15));
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+
+        // Entry 13 links to 4.
+        let thirteenth_entry = remote_log.store.get_entry(13).unwrap().unwrap();
+        log.add(&thirteenth_entry, None).unwrap();
+        assert_eq!(log.pending().len(), 1);
+
+        assert_eq!(
+            log.add_batch(&entries_and_payloads(&remote_log, 1..5))
+                .unwrap(),
+            vec![1, 2, 3, 4, 13]
+        );
+        assert!(log.pending().is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use super::Log;
+use crate::entry_store::EntryStore;
+use crate::payload_store::{hash_key, PayloadStore};
+use bamboo_rs_core_ed25519_yasmf::entry::decode;
+use core::fmt::Debug;
+use lipmaa_link::lipmaa;
+use snafu::ResultExt;
+use std::collections::BTreeSet;
+
+use super::error::*;
+
+/// The seq_nums a log must keep so its latest entry, `last_seq_num`, can still be verified and new
+/// entries can still be published or added after it.
+///
+/// That's the lipmaa links from `last_seq_num` back to the first entry, which certify it, and the
+/// lipmaa links from `last_seq_num + 1` back to the first entry, which every later lipmaa link
+/// points into. Both are logarithmic in `last_seq_num`.
+pub fn certificate_pool(last_seq_num: u64) -> BTreeSet<u64> {
+    let mut pool = BTreeSet::new();
+    let mut push_path = |mut seq_num: u64| {
+        while seq_num > 0 && pool.insert(seq_num) {
+            seq_num = lipmaa(seq_num);
+        }
+    };
+
+    push_path(last_seq_num);
+    if last_seq_num > 0 {
+        push_path(lipmaa(last_seq_num + 1));
+    }
+    pool
+}
+
+impl<Store: EntryStore + Debug, Payloads: PayloadStore + Debug> Log<Store, Payloads> {
+    /// Delete the payload of the entry at `seq_num`, keeping the entry.
+    ///
+    /// Payloads are stored by hash, so this also deletes the payload of any other entry with the
+    /// same payload.
+    pub fn delete_payload(&mut self, seq_num: u64) -> Result<(), Error<Store, Payloads>> {
+        let entry_bytes = match self
+            .store
+            .get_entry_ref(seq_num)
+            .context(DeletePayloadGetEntry)?
+        {
+            Some(entry_bytes) => entry_bytes,
+            None => return Ok(()),
+        };
+        let entry = decode(entry_bytes).context(DeletePayloadDecodeEntry)?;
+
+        self.payload_store
+            .delete_payload(entry.payload_hash)
+            .context(DeletePayloadFailed)
+    }
+
+    /// Remove every entry that isn't in the [certificate_pool] of the latest entry, along with
+    /// their payloads.
+    ///
+    /// The log can still be published to and added to afterwards, and its latest entry can still
+    /// be verified from the first entry. Older entries that were removed can't be added back
+    /// unless their lipmaa entries are still in the log. Payloads shared with entries that are
+    /// kept are not deleted.
+    pub fn prune(&mut self) -> Result<(), Error<Store, Payloads>> {
+        let last_seq_num = match self.store.get_last_seq() {
+            Some(last_seq_num) => last_seq_num,
+            None => return Ok(()),
+        };
+        let pool = certificate_pool(last_seq_num);
+
+        let mut kept_payloads = BTreeSet::new();
+        let mut pruned = Vec::new();
+        for seq_num in 1..=last_seq_num {
+            let entry_bytes = match self.store.get_entry_ref(seq_num).context(PruneGetEntry)? {
+                Some(entry_bytes) => entry_bytes,
+                None => continue,
+            };
+            let entry = decode(entry_bytes).context(PruneDecodeEntry)?;
+
+            if pool.contains(&seq_num) {
+                kept_payloads.insert(hash_key(&entry.payload_hash));
+            } else {
+                pruned.push((seq_num, entry.payload_hash));
+            }
+        }
+
+        for (_, payload_hash) in pruned.iter() {
+            if !kept_payloads.contains(&hash_key(payload_hash)) {
+                self.payload_store
+                    .delete_payload(payload_hash.clone())
+                    .context(PruneDeletePayload)?;
+            }
+        }
+
+        let pruned: Vec<u64> = pruned.into_iter().map(|(seq_num, _)| seq_num).collect();
+        self.store
+            .remove_entries(&pruned)
+            .context(PruneRemoveEntries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::certificate_pool;
+    use crate::entry_store::{MemoryEntryStore, SegmentEntryStore};
+    use crate::{EntryStore, Log};
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use lipmaa_link::lipmaa;
+    use rand::rngs::OsRng;
+    use tempfile::tempdir;
+
+    #[test]
+    fn certificate_pool_holds_every_later_lipmaa_link() {
+        assert_eq!(certificate_pool(0).len(), 0);
+        for last_seq_num in 1..500 {
+            let pool = certificate_pool(last_seq_num);
+            assert!(pool.contains(&last_seq_num));
+            assert!(pool.contains(&1));
+            for seq_num in last_seq_num + 1..3 * last_seq_num + 2 {
+                let lipmaa_seq_num = lipmaa(seq_num);
+                assert!(lipmaa_seq_num > last_seq_num || pool.contains(&lipmaa_seq_num));
+            }
+        }
+    }
+
+    #[test]
+    fn deleting_a_payload_keeps_the_log_usable() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public;
+
+        let mut log = Log::new(MemoryEntryStore::new(), public_key, Some(keypair), 0);
+        for i in 1..4u8 {
+            log.publish(&[i], false).unwrap();
+        }
+        log.delete_payload(2).unwrap();
+        log.delete_payload(7).unwrap();
+
+        assert_eq!(log.get_payload(1).unwrap(), Some(vec![1]));
+        assert_eq!(log.get_payload(2).unwrap(), None);
+        assert!(log.store.get_entry(2).unwrap().is_some());
+
+        log.publish(&[4], false).unwrap();
+        let mut remote_log = Log::new(MemoryEntryStore::new(), public_key, None, 0);
+        for seq_num in 1..=4 {
+            let entry = log.store.get_entry(seq_num).unwrap().unwrap();
+            let payload = log.get_payload(seq_num).unwrap();
+            remote_log.add(&entry, payload.as_deref()).unwrap();
+        }
+        assert_eq!(remote_log.get_payload(2).unwrap(), None);
+        assert_eq!(remote_log.get_payload(4).unwrap(), Some(vec![4]));
+    }
+
+    #[test]
+    fn pruning_keeps_the_certificate_pool_and_the_log_usable() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public;
+
+        let mut log = Log::new(MemoryEntryStore::new(), public_key, Some(keypair), 0);
+        let mut synced_log = Log::new(MemoryEntryStore::new(), public_key, None, 0);
+        for i in 1..=100u64 {
+            log.publish(&i.to_le_bytes(), false).unwrap();
+            let entry = log.store.get_last_entry().unwrap().unwrap();
+            synced_log.add(&entry, None).unwrap();
+        }
+
+        log.prune().unwrap();
+        let pool = certificate_pool(100);
+        let mut kept: Vec<u64> = log.store.store.keys().copied().collect();
+        kept.sort_unstable();
+        assert_eq!(kept, pool.iter().copied().collect::<Vec<_>>());
+        assert_eq!(log.payload_store.store.len(), pool.len());
+        assert_eq!(
+            log.get_payload(100).unwrap(),
+            Some(100u64.to_le_bytes().to_vec())
+        );
+        let pruned_seq_num = (1..100).find(|seq_num| !pool.contains(seq_num)).unwrap();
+        assert_eq!(log.get_payload(pruned_seq_num).unwrap(), None);
+        assert_eq!(log.store.get_entry(pruned_seq_num).unwrap(), None);
+
+        for i in 101..=130u64 {
+            log.publish(&i.to_le_bytes(), false).unwrap();
+        }
+
+        // A peer that only gets the kept entries can verify them and the new ones.
+        let mut remote_log = Log::new(MemoryEntryStore::new(), public_key, None, 0);
+        for seq_num in pool.iter().copied().chain(101..=130) {
+            let entry = log.store.get_entry(seq_num).unwrap().unwrap();
+            remote_log.add(&entry, None).unwrap();
+            if seq_num > 100 {
+                synced_log.add(&entry, None).unwrap();
+            }
+        }
+        assert_eq!(remote_log.store.get_last_seq(), Some(130));
+        assert_eq!(synced_log.store.get_last_seq(), Some(130));
+    }
+
+    #[test]
+    fn pruning_keeps_payloads_shared_with_kept_entries() {
+        let dir = tempdir().unwrap();
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut log = Log::new(
+            SegmentEntryStore::open(dir.path()).unwrap(),
+            keypair.public,
+            Some(keypair),
+            0,
+        );
+        for _ in 0..10 {
+            log.publish(b"same", false).unwrap();
+        }
+        log.prune().unwrap();
+
+        assert_eq!(log.store.get_entry(6).unwrap(), None);
+        assert_eq!(log.get_payload(10).unwrap(), Some(b"same".to_vec()));
+
+        let store = SegmentEntryStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_entry(6).unwrap(), None);
+        assert_eq!(store.get_last_seq(), Some(10));
+    }
+}
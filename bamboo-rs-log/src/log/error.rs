@@ -1,21 +1,134 @@
-use snafu::Snafu;
-use core::fmt::Debug;
+use crate::checks::{AddError, PublishError as CheckPublishError};
+use crate::entry_store::EntryStore;
+use crate::payload_store::{MemoryPayloadStore, PayloadStore};
 use bamboo_rs_core_ed25519_yasmf::entry::decode::Error as DecodeError;
-use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
 use bamboo_rs_core_ed25519_yasmf::entry::publish::Error as PublishError;
-use crate::entry_store::EntryStore;
+use bamboo_rs_core_ed25519_yasmf::entry::verify::Error as VerifyError;
+use core::fmt::Debug;
+use snafu::Snafu;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
-pub enum Error<ES: EntryStore + Debug> {
-    AddEntryDecodeFailed{source: DecodeError},
-    AddEntryGetLipmaaEntry{source: ES::Error},
-    AddEntryGetBacklinkEntry{source: ES::Error},
-    AddEntryFailedVerification{source: VerifyError},
-    AddEntryFailedToAddEntryToLog{source: ES::Error},
-    PublishEntryGetLipmaaEntry{source: ES::Error},
-    PublishEntryGetBacklinkEntry{source: ES::Error},
-    PublishNewEntryFailed{source: PublishError},
-    PublishEntryAppendFailed{source: ES::Error},
+pub enum Error<ES: EntryStore + Debug, PS: PayloadStore + Debug = MemoryPayloadStore> {
+    #[snafu(display("Could not decode the entry to add: {}", source))]
+    AddEntryDecodeFailed { source: DecodeError },
+    #[snafu(display("The entry to add was published by a different author than the log's"))]
+    AddEntryAuthorDoesNotMatchLog,
+    #[snafu(display(
+        "The entry to add has log_id {} but the log has log_id {}",
+        actual,
+        expected
+    ))]
+    AddEntryLogIdDoesNotMatchLog { actual: u64, expected: u64 },
+    #[snafu(display("Could not get the lipmaa entry from the store: {}", source))]
+    AddEntryGetLipmaaEntry { source: ES::Error },
+    #[snafu(display(
+        "The lipmaa entry (seq_num {}) of the entry to add is not in the store",
+        lipmaa_seq_num
+    ))]
+    AddEntryNoLipmaalinkInStore { lipmaa_seq_num: u64 },
+    #[snafu(display("Could not get the backlink entry from the store: {}", source))]
+    AddEntryGetBacklinkEntry { source: ES::Error },
+    #[snafu(display("The entry to add failed verification: {}", source))]
+    AddEntryFailedVerification { source: VerifyError },
+    #[snafu(display("Could not add the payload to the payload store: {}", source))]
+    AddEntryAddPayloadFailed { source: PS::Error },
+    #[snafu(display("Could not add the entry to the store: {}", source))]
+    AddEntryFailedToAddEntryToLog { source: ES::Error },
+    #[snafu(display("Could not decode an entry in the batch: {}", source))]
+    AddBatchDecodeFailed { source: DecodeError },
+    #[snafu(display(
+        "Entry {} in the batch was published by a different author than the log's",
+        seq_num
+    ))]
+    AddBatchAuthorDoesNotMatchLog { seq_num: u64 },
+    #[snafu(display(
+        "Entry {} in the batch has log_id {} but the log has log_id {}",
+        seq_num,
+        actual,
+        expected
+    ))]
+    AddBatchLogIdDoesNotMatchLog {
+        seq_num: u64,
+        actual: u64,
+        expected: u64,
+    },
+    #[snafu(display("The batch has more than one entry with seq_num {}", seq_num))]
+    AddBatchDuplicateSeqNum { seq_num: u64 },
+    #[snafu(display("Could not get an entry the batch links to from the store: {}", source))]
+    AddBatchGetLinkedEntry { source: ES::Error },
+    #[snafu(display(
+        "The lipmaa entry (seq_num {}) of entry {} in the batch is not in the batch or the store",
+        lipmaa_seq_num,
+        seq_num
+    ))]
+    AddBatchNoLipmaalinkInStore { seq_num: u64, lipmaa_seq_num: u64 },
+    #[snafu(display("The batch failed verification: {}", source))]
+    AddBatchFailedVerification { source: VerifyError },
+    #[snafu(display(
+        "Could not add a payload in the batch to the payload store: {}",
+        source
+    ))]
+    AddBatchAddPayloadFailed { source: PS::Error },
+    #[snafu(display("Could not add the batch to the store: {}", source))]
+    AddBatchFailedToAddEntriesToLog { source: ES::Error },
+    #[snafu(display("Could not get the lipmaa entry from the store: {}", source))]
+    PublishEntryGetLipmaaEntry { source: ES::Error },
+    #[snafu(display("Could not get the backlink entry from the store: {}", source))]
+    PublishEntryGetBacklinkEntry { source: ES::Error },
+    #[snafu(display("Could not publish the new entry: {}", source))]
+    PublishNewEntryFailed { source: PublishError },
+    #[snafu(display("Could not add the published payload to the payload store: {}", source))]
+    PublishEntryAddPayloadFailed { source: PS::Error },
+    #[snafu(display("Could not add the published entry to the store: {}", source))]
+    PublishEntryAppendFailed { source: ES::Error },
+    #[snafu(display("Can't publish to a log without a key pair"))]
     PublishWithoutKeypair,
+    #[snafu(display("The log's key pair does not match its public key"))]
+    PublishKeypairDoesNotMatchLog,
+    #[snafu(display("Could not get the entry from the store: {}", source))]
+    GetPayloadGetEntry { source: ES::Error },
+    #[snafu(display("Could not decode the entry from the store: {}", source))]
+    GetPayloadDecodeEntry { source: DecodeError },
+    #[snafu(display("Could not get the payload from the payload store: {}", source))]
+    GetPayloadFailed { source: PS::Error },
+    #[snafu(display("Could not get the entry from the store: {}", source))]
+    DeletePayloadGetEntry { source: ES::Error },
+    #[snafu(display("Could not decode the entry from the store: {}", source))]
+    DeletePayloadDecodeEntry { source: DecodeError },
+    #[snafu(display("Could not delete the payload from the payload store: {}", source))]
+    DeletePayloadFailed { source: PS::Error },
+    #[snafu(display("Could not get an entry to prune from the store: {}", source))]
+    PruneGetEntry { source: ES::Error },
+    #[snafu(display("Could not decode an entry to prune: {}", source))]
+    PruneDecodeEntry { source: DecodeError },
+    #[snafu(display("Could not remove the pruned entries from the store: {}", source))]
+    PruneRemoveEntries { source: ES::Error },
+    #[snafu(display("Could not delete a pruned payload from the payload store: {}", source))]
+    PruneDeletePayload { source: PS::Error },
+    #[snafu(display("Could not get an entry to catch up on from the store: {}", source))]
+    SubscribeGetEntry { source: ES::Error },
+}
+
+impl<ES: EntryStore + Debug, PS: PayloadStore + Debug> From<AddError> for Error<ES, PS> {
+    fn from(error: AddError) -> Self {
+        match error {
+            AddError::Decode { source } => Error::AddEntryDecodeFailed { source },
+            AddError::AuthorDoesNotMatchLog { .. } => Error::AddEntryAuthorDoesNotMatchLog,
+            AddError::LogIdDoesNotMatchLog {
+                actual, expected, ..
+            } => Error::AddEntryLogIdDoesNotMatchLog { actual, expected },
+            AddError::Verification { source } => Error::AddEntryFailedVerification { source },
+        }
+    }
+}
+
+impl<ES: EntryStore + Debug, PS: PayloadStore + Debug> From<CheckPublishError> for Error<ES, PS> {
+    fn from(error: CheckPublishError) -> Self {
+        match error {
+            CheckPublishError::WithoutKeypair => Error::PublishWithoutKeypair,
+            CheckPublishError::KeypairDoesNotMatchLog => Error::PublishKeypairDoesNotMatchLog,
+            CheckPublishError::NewEntry { source } => Error::PublishNewEntryFailed { source },
+        }
+    }
 }
@@ -1,28 +1,143 @@
 pub use crate::entry_store::EntryStore;
+pub use crate::payload_store::{MemoryPayloadStore, PayloadStore};
 use bamboo_rs_core_ed25519_yasmf::{Keypair, PublicKey};
 
 pub mod add;
-pub mod publish;
+pub mod add_batch;
+pub mod delete;
 pub mod error;
+pub mod payload;
+pub mod pending;
+pub mod publish;
+pub mod subscribe;
 
-pub use add::*;
-pub use publish::*;
 pub use error::*;
+pub use pending::{PendingEntries, PendingEntry, PendingLimits};
+use subscribe::Subscribers;
+pub use subscribe::{CallbackId, Event, Overflow, Subscription};
 
-pub struct Log<Store: EntryStore> {
+/// A single bamboo log, the entries of one author with one `log_id`, kept in an [EntryStore].
+///
+/// Pass a `key_pair` to be able to [publish](Log::publish) to the log. Entries from other peers
+/// can be [added](Log::add) without one. Payloads that are published or added are kept in a
+/// [PayloadStore]. Callbacks and [Subscription]s can follow the entries appended to it.
+pub struct Log<Store: EntryStore, Payloads: PayloadStore = MemoryPayloadStore> {
     pub store: Store,
+    pub payload_store: Payloads,
     pub public_key: PublicKey,
     key_pair: Option<Keypair>,
     log_id: u64,
+    pending: PendingEntries,
+    subscribers: Subscribers,
 }
 
 impl<Store: EntryStore> Log<Store> {
-    pub fn new(store: Store, public_key: PublicKey, key_pair: Option<Keypair>, log_id: u64) -> Log<Store> {
+    /// Create a log that keeps its payloads in a [MemoryPayloadStore].
+    pub fn new(
+        store: Store,
+        public_key: PublicKey,
+        key_pair: Option<Keypair>,
+        log_id: u64,
+    ) -> Log<Store> {
+        Log::with_payload_store(
+            store,
+            MemoryPayloadStore::new(),
+            public_key,
+            key_pair,
+            log_id,
+        )
+    }
+}
+
+impl<Store: EntryStore, Payloads: PayloadStore> Log<Store, Payloads> {
+    /// Create a log that keeps its payloads in `payload_store`.
+    pub fn with_payload_store(
+        store: Store,
+        payload_store: Payloads,
+        public_key: PublicKey,
+        key_pair: Option<Keypair>,
+        log_id: u64,
+    ) -> Log<Store, Payloads> {
         Log {
             store,
+            payload_store,
             public_key,
             key_pair,
-            log_id
+            log_id,
+            pending: PendingEntries::default(),
+            subscribers: Subscribers::default(),
         }
     }
+
+    /// The `log_id` of this log.
+    pub fn log_id(&self) -> u64 {
+        self.log_id
+    }
+
+    /// The entries waiting for their lipmaa entry to be [added](Log::add).
+    pub fn pending(&self) -> &PendingEntries {
+        &self.pending
+    }
+
+    /// The pending entries, to change their limits or evict them.
+    pub fn pending_mut(&mut self) -> &mut PendingEntries {
+        &mut self.pending
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::Log;
+    use crate::entry_store::{EntryStore, MemoryEntryStore};
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use rand::rngs::OsRng;
+
+    /// An empty log with a new key pair to publish with.
+    pub(crate) fn new_log() -> Log<MemoryEntryStore> {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0)
+    }
+
+    /// The payloads "message number i" for every i in `range`.
+    pub(crate) fn payloads(range: impl Iterator<Item = u64>) -> Vec<Vec<u8>> {
+        range
+            .map(|i| format!("message number {}", i).into_bytes())
+            .collect()
+    }
+
+    /// A log of `keypair` with `payloads` published to it.
+    pub(crate) fn published_log(keypair: Keypair, payloads: &[Vec<u8>]) -> Log<MemoryEntryStore> {
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0);
+        for payload in payloads {
+            log.publish(payload, false).unwrap();
+        }
+        log
+    }
+
+    /// A log with a new key pair and the entries 1 to `n - 1` published to it.
+    pub(crate) fn n_valid_entries(n: u64) -> Log<MemoryEntryStore> {
+        let mut csprng: OsRng = OsRng {};
+        published_log(Keypair::generate(&mut csprng), &payloads(1..n))
+    }
+
+    /// An empty log to add the entries of `log` to.
+    pub(crate) fn empty_copy(log: &Log<MemoryEntryStore>) -> Log<MemoryEntryStore> {
+        Log::new(MemoryEntryStore::new(), log.public_key, None, log.log_id)
+    }
+
+    /// The entries of `log` at `seq_nums` and their payloads, to add as a batch.
+    pub(crate) fn entries_and_payloads(
+        log: &Log<MemoryEntryStore>,
+        seq_nums: impl Iterator<Item = u64>,
+    ) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        seq_nums
+            .map(|seq_num| {
+                (
+                    log.store.get_entry(seq_num).unwrap().unwrap(),
+                    log.get_payload(seq_num).unwrap(),
+                )
+            })
+            .collect()
+    }
 }
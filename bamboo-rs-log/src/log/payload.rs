@@ -0,0 +1,114 @@
+use super::Log;
+use crate::entry_store::EntryStore;
+use crate::payload_store::PayloadStore;
+use bamboo_rs_core_ed25519_yasmf::entry::decode;
+use core::fmt::Debug;
+use snafu::ResultExt;
+
+use super::error::*;
+
+impl<Store: EntryStore + Debug, Payloads: PayloadStore + Debug> Log<Store, Payloads> {
+    /// Get the payload of the entry at `seq_num`.
+    ///
+    /// Returns `None` if the entry isn't in the log, or if its payload was never published or
+    /// added.
+    pub fn get_payload(&self, seq_num: u64) -> Result<Option<Vec<u8>>, Error<Store, Payloads>> {
+        let entry_bytes = match self
+            .store
+            .get_entry_ref(seq_num)
+            .context(GetPayloadGetEntry)?
+        {
+            Some(entry_bytes) => entry_bytes,
+            None => return Ok(None),
+        };
+        let entry = decode(entry_bytes).context(GetPayloadDecodeEntry)?;
+
+        self.payload_store
+            .get_payload(entry.payload_hash)
+            .context(GetPayloadFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entry_store::MemoryEntryStore;
+    use crate::payload_store::{FilePayloadStore, MemoryPayloadStore};
+    use crate::{EntryStore, Log};
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
+    use rand::rngs::OsRng;
+    use tempfile::tempdir;
+
+    #[test]
+    fn published_payloads_can_be_got() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0);
+        log.publish(b"first", false).unwrap();
+        log.publish(b"second", false).unwrap();
+
+        assert_eq!(log.get_payload(1).unwrap(), Some(b"first".to_vec()));
+        assert_eq!(log.get_payload(2).unwrap(), Some(b"second".to_vec()));
+        assert_eq!(log.get_payload(3).unwrap(), None);
+    }
+
+    #[test]
+    fn added_payloads_are_stored_only_when_given() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let public_key = keypair.public;
+
+        let mut log = Log::new(MemoryEntryStore::new(), public_key, Some(keypair), 0);
+        log.publish(b"first", false).unwrap();
+        log.publish(b"second", false).unwrap();
+
+        let mut remote_log = Log::new(MemoryEntryStore::new(), public_key, None, 0);
+        remote_log
+            .add(&log.store.get_entry(1).unwrap().unwrap(), Some(b"first"))
+            .unwrap();
+        remote_log
+            .add(&log.store.get_entry(2).unwrap().unwrap(), None)
+            .unwrap();
+
+        assert_eq!(remote_log.get_payload(1).unwrap(), Some(b"first".to_vec()));
+        assert_eq!(remote_log.get_payload(2).unwrap(), None);
+        assert_eq!(remote_log.payload_store.store.len(), 1);
+    }
+
+    #[test]
+    fn payloads_with_the_same_hash_are_stored_once() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut log = Log::with_payload_store(
+            MemoryEntryStore::new(),
+            MemoryPayloadStore::new(),
+            keypair.public,
+            Some(keypair),
+            0,
+        );
+        log.publish(b"same", false).unwrap();
+        log.publish(b"same", false).unwrap();
+
+        assert_eq!(log.get_payload(2).unwrap(), Some(b"same".to_vec()));
+        assert_eq!(log.payload_store.store.len(), 1);
+    }
+
+    #[test]
+    fn payloads_can_be_kept_in_files() {
+        let dir = tempdir().unwrap();
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+
+        let mut log = Log::with_payload_store(
+            MemoryEntryStore::new(),
+            FilePayloadStore::open(dir.path()).unwrap(),
+            keypair.public,
+            Some(keypair),
+            0,
+        );
+        log.publish(b"in a file", false).unwrap();
+
+        assert_eq!(log.get_payload(1).unwrap(), Some(b"in a file".to_vec()));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Limits on the entries a [Log](crate::Log) holds while it waits for their lipmaa entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingLimits {
+    /// The most entries to hold. `0` turns the buffer off, so [add](crate::Log::add) fails when the
+    /// lipmaa entry is missing.
+    pub max_entries: usize,
+    /// The most bytes of entries and payloads to hold.
+    pub max_bytes: usize,
+    /// How long to hold an entry for.
+    pub max_age: Duration,
+}
+
+impl Default for PendingLimits {
+    /// 1024 entries, 4 MiB and 10 minutes.
+    fn default() -> Self {
+        PendingLimits {
+            max_entries: 1024,
+            max_bytes: 4 * 1024 * 1024,
+            max_age: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// An entry whose lipmaa entry wasn't in the log when it was added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub entry: Vec<u8>,
+    pub payload: Option<Vec<u8>>,
+    /// The seq_num of the lipmaa entry it's waiting for.
+    pub lipmaa_seq_num: u64,
+    pub received: Instant,
+}
+
+impl PendingEntry {
+    fn size(&self) -> usize {
+        self.entry.len() + self.payload.as_ref().map_or(0, |payload| payload.len())
+    }
+}
+
+/// The bounded buffer of [PendingEntry]s in a [Log](crate::Log), by seq_num.
+///
+/// When it's over its [PendingLimits] the oldest entries are evicted first.
+#[derive(Debug, Default)]
+pub struct PendingEntries {
+    limits: PendingLimits,
+    entries: BTreeMap<u64, PendingEntry>,
+    bytes: usize,
+}
+
+impl PendingEntries {
+    pub fn new(limits: PendingLimits) -> PendingEntries {
+        PendingEntries {
+            limits,
+            entries: BTreeMap::new(),
+            bytes: 0,
+        }
+    }
+
+    pub fn limits(&self) -> PendingLimits {
+        self.limits
+    }
+
+    /// Change the limits, evicting entries until the buffer is within them.
+    pub fn set_limits(&mut self, limits: PendingLimits) {
+        self.limits = limits;
+        self.evict_expired();
+        self.evict_to_fit(0, 0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The bytes of entries and payloads held.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn get(&self, seq_num: u64) -> Option<&PendingEntry> {
+        self.entries.get(&seq_num)
+    }
+
+    /// The pending entries in seq_num order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &PendingEntry)> {
+        self.entries
+            .iter()
+            .map(|(seq_num, pending)| (*seq_num, pending))
+    }
+
+    pub fn remove(&mut self, seq_num: u64) -> Option<PendingEntry> {
+        let pending = self.entries.remove(&seq_num)?;
+        self.bytes -= pending.size();
+        Some(pending)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    /// Evict entries held for longer than `max_age`, returning how many were evicted.
+    pub fn evict_older_than(&mut self, max_age: Duration) -> usize {
+        let now = Instant::now();
+        let expired: Vec<u64> = self
+            .iter()
+            .filter(|(_, pending)| now.duration_since(pending.received) > max_age)
+            .map(|(seq_num, _)| seq_num)
+            .collect();
+
+        for seq_num in expired.iter() {
+            self.remove(*seq_num);
+        }
+        expired.len()
+    }
+
+    /// Evict entries held for longer than the limits' `max_age`.
+    pub fn evict_expired(&mut self) -> usize {
+        self.evict_older_than(self.limits.max_age)
+    }
+
+    /// Hold `pending` at `seq_num`, replacing any entry already there and evicting the oldest
+    /// entries to make room. Returns `false` if it can't fit even in an empty buffer.
+    pub(crate) fn insert(&mut self, seq_num: u64, pending: PendingEntry) -> bool {
+        self.remove(seq_num);
+        let size = pending.size();
+        if self.limits.max_entries == 0 || size > self.limits.max_bytes {
+            return false;
+        }
+
+        self.evict_expired();
+        self.evict_to_fit(1, size);
+        self.bytes += size;
+        self.entries.insert(seq_num, pending);
+        true
+    }
+
+    /// Take the lowest pending entry whose lipmaa entry `is_in_log`.
+    pub(crate) fn take_ready<F: Fn(u64) -> bool>(
+        &mut self,
+        is_in_log: F,
+    ) -> Option<(u64, PendingEntry)> {
+        let seq_num = self
+            .iter()
+            .find(|(_, pending)| is_in_log(pending.lipmaa_seq_num))
+            .map(|(seq_num, _)| seq_num)?;
+        self.remove(seq_num).map(|pending| (seq_num, pending))
+    }
+
+    /// Evict the oldest entries until `entries` more entries of `bytes` in total fit.
+    fn evict_to_fit(&mut self, entries: usize, bytes: usize) {
+        while !self.entries.is_empty()
+            && (self.entries.len() + entries > self.limits.max_entries
+                || self.bytes + bytes > self.limits.max_bytes)
+        {
+            let oldest = self
+                .iter()
+                .min_by_key(|(_, pending)| pending.received)
+                .map(|(seq_num, _)| seq_num)
+                .expect("entries is not empty");
+            self.remove(oldest);
+        }
+    }
+}
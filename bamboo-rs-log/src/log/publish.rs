@@ -1,25 +1,33 @@
 use core::fmt::Debug;
 use lipmaa_link::lipmaa;
 
+use crate::checks::{publish_next, publishing_key_pair};
 use crate::entry_store::EntryStore;
-use bamboo_rs_core_ed25519_yasmf::entry::publish;
-use snafu::{ResultExt, OptionExt};
+use crate::payload_store::PayloadStore;
+use bamboo_rs_core_ed25519_yasmf::entry::MAX_ENTRY_SIZE;
+use snafu::ResultExt;
 
-use super::Log;
 use super::error::*;
+use super::Log;
+
+impl<Store: EntryStore + Debug, Payloads: PayloadStore + Debug> Log<Store, Payloads> {
+    /// Publish a new entry with `payload` to the end of the log, and add `payload` to the payload
+    /// store.
+    pub fn publish(
+        &mut self,
+        payload: &[u8],
+        is_end_of_feed: bool,
+    ) -> Result<(), Error<Store, Payloads>> {
+        let mut buff = [0u8; MAX_ENTRY_SIZE];
 
-impl<Store: EntryStore + Debug> Log<Store> {
-    pub fn publish(&mut self, payload: &[u8], is_end_of_feed: bool) -> Result<(), Error<Store>> {
-        let mut buff = [0u8; 512];
+        let key_pair = publishing_key_pair(self.key_pair.as_ref(), &self.public_key)?;
 
-        let key_pair = self.key_pair.as_ref().context(PublishWithoutKeypair)?;
         let last_seq_num = self.store.get_last_seq();
         let seq_num = last_seq_num.unwrap_or(0) + 1;
-        let lipmaa_link_seq = lipmaa(seq_num);
 
         let lipmaa_entry_bytes = self
             .store
-            .get_entry_ref(lipmaa_link_seq)
+            .get_entry_ref(lipmaa(seq_num))
             .context(PublishEntryGetLipmaaEntry)?;
 
         let backlink_bytes = self
@@ -27,7 +35,7 @@ impl<Store: EntryStore + Debug> Log<Store> {
             .get_entry_ref(last_seq_num.unwrap_or(0))
             .context(PublishEntryGetBacklinkEntry)?;
 
-        let length = publish(
+        let (length, payload_hash) = publish_next(
             &mut buff,
             key_pair,
             self.log_id,
@@ -36,75 +44,104 @@ impl<Store: EntryStore + Debug> Log<Store> {
             last_seq_num,
             lipmaa_entry_bytes,
             backlink_bytes,
-        )
-        .context(PublishNewEntryFailed)?;
+        )?;
 
         self.store
             .add_entry(&buff[..length], seq_num)
-            .context(PublishEntryAppendFailed)
+            .context(PublishEntryAppendFailed)?;
+        self.notify_appended(&[seq_num]);
+
+        self.payload_store
+            .add_payload((&payload_hash).into(), payload)
+            .context(PublishEntryAddPayloadFailed)?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::entry_store::MemoryEntryStore;
-    use crate::log::{Log, Error};
+    use crate::log::{Error, Log};
     use crate::EntryStore;
     use bamboo_rs_core_ed25519_yasmf::entry::decode;
-    use bamboo_rs_core_ed25519_yasmf::{Keypair};
+    use bamboo_rs_core_ed25519_yasmf::entry::publish::Error as PublishError;
+    use bamboo_rs_core_ed25519_yasmf::Keypair;
 
     use rand::rngs::OsRng;
 
     #[test]
     fn publish_and_verify_signature() {
-        let mut csprng: OsRng = OsRng::new().unwrap();
+        let mut csprng: OsRng = OsRng {};
         let keypair: Keypair = Keypair::generate(&mut csprng);
 
-        let mut log = Log::new(
-            MemoryEntryStore::new(),
-            keypair.public.clone(),
-            Some(keypair),
-        );
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0);
         let payload = [1, 2, 3];
         log.publish(&payload, false).unwrap();
+        log.publish(&payload, false).unwrap();
+
+        let entry_bytes = log.store.get_entry_ref(2).unwrap().unwrap();
+
+        let entry = decode(entry_bytes).unwrap();
+        assert_eq!(entry.seq_num, 2);
+        assert_eq!(entry.log_id, 0);
+        assert!(entry.verify_signature().is_ok());
+    }
+
+    #[test]
+    fn publish_uses_log_id() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
 
-        let entry_bytes = log.store.get_entry_ref(1).unwrap().unwrap();
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 7);
+        log.publish(&[1, 2, 3], false).unwrap();
 
-        let mut entry = decode(entry_bytes).unwrap();
-        assert!(entry.verify_signature().unwrap());
+        let entry = decode(log.store.get_entry_ref(1).unwrap().unwrap()).unwrap();
+        assert_eq!(entry.log_id, 7);
     }
 
     #[test]
     fn publish_after_an_end_of_feed_message_errors() {
-        let mut csprng: OsRng = OsRng::new().unwrap();
+        let mut csprng: OsRng = OsRng {};
         let keypair: Keypair = Keypair::generate(&mut csprng);
 
-        let mut log = Log::new(
-            MemoryEntryStore::new(),
-            keypair.public.clone(),
-            Some(keypair),
-        );
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, Some(keypair), 0);
         let payload = [1, 2, 3];
 
         //publish an end of feed message.
         log.publish(&payload, true).unwrap();
 
         match log.publish(&payload, false) {
-            Err(Error::PublishNewEntryFailed) => {}
-            _ => panic!("expected publish to fail with an error"),
+            Err(Error::PublishNewEntryFailed {
+                source: PublishError::PublishAfterEndOfFeed,
+            }) => {}
+            e => panic!("expected publish to fail with an error, got: {:?}", e),
         }
     }
 
     #[test]
     fn publish_without_secret_key_errors() {
-        let mut csprng: OsRng = OsRng::new().unwrap();
+        let mut csprng: OsRng = OsRng {};
         let keypair: Keypair = Keypair::generate(&mut csprng);
 
-        let mut log = Log::new(MemoryEntryStore::new(), keypair.public.clone(), None);
+        let mut log = Log::new(MemoryEntryStore::new(), keypair.public, None, 0);
         let payload = [1, 2, 3];
 
         match log.publish(&payload, false) {
-            Err(Error::PublishNewEntryFailed) => {}
+            Err(Error::PublishWithoutKeypair) => {}
+            e => panic!("expected publish to fail with an error, got: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn publish_with_a_different_key_pair_errors() {
+        let mut csprng: OsRng = OsRng {};
+        let keypair: Keypair = Keypair::generate(&mut csprng);
+        let other: Keypair = Keypair::generate(&mut csprng);
+
+        let mut log = Log::new(MemoryEntryStore::new(), other.public, Some(keypair), 0);
+
+        match log.publish(&[1, 2, 3], false) {
+            Err(Error::PublishKeypairDoesNotMatchLog) => {}
             e => panic!("expected publish to fail with an error, got: {:?}", e),
         }
     }
@@ -0,0 +1,497 @@
+use super::Log;
+use crate::entry_store::EntryStore;
+use crate::payload_store::PayloadStore;
+use core::fmt::Debug;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use futures_core::Stream;
+use snafu::ResultExt;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::error::*;
+
+/// What a [Subscription] does with new entries when its buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drop new entries until there's room, then tell the subscriber how many it missed with
+    /// [Event::Dropped].
+    Drop,
+    /// End the subscription with [Event::Lagged], so the subscriber can catch up again with
+    /// [Log::subscribe_from].
+    Lag,
+}
+
+/// Something that happened to the log a [Subscription] follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// An entry was appended to the log.
+    Appended { seq_num: u64, entry: Vec<u8> },
+    /// `count` appended entries were dropped because the buffer was full.
+    Dropped { count: u64 },
+    /// The buffer was full when the entry at `seq_num` was appended, so the subscription ended.
+    /// Entries from `seq_num` on were not delivered.
+    Lagged { seq_num: u64 },
+}
+
+/// Identifies a callback registered with [Log::on_append].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CallbackId(u64);
+
+#[derive(Debug)]
+struct Buffer {
+    events: VecDeque<Event>,
+    capacity: usize,
+    overflow: Overflow,
+    dropped: u64,
+    lagged_at: Option<u64>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl Buffer {
+    fn push(&mut self, seq_num: u64, entry: &[u8]) {
+        if self.closed || self.lagged_at.is_some() {
+            return;
+        }
+
+        // The Dropped marker takes up room in the buffer too.
+        let needed = if self.dropped > 0 { 2 } else { 1 };
+        if self.events.len() + needed <= self.capacity {
+            if self.dropped > 0 {
+                self.events.push_back(Event::Dropped {
+                    count: self.dropped,
+                });
+                self.dropped = 0;
+            }
+            self.events.push_back(Event::Appended {
+                seq_num,
+                entry: entry.to_vec(),
+            });
+        } else {
+            match self.overflow {
+                Overflow::Drop => self.dropped += 1,
+                Overflow::Lag => self.lagged_at = Some(seq_num),
+            }
+        }
+        self.wake();
+    }
+
+    fn next_event(&mut self) -> Option<Event> {
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+        if self.dropped > 0 {
+            let count = self.dropped;
+            self.dropped = 0;
+            return Some(Event::Dropped { count });
+        }
+        let seq_num = self.lagged_at.take()?;
+        self.closed = true;
+        Some(Event::Lagged { seq_num })
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        self.wake();
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn lock(buffer: &Mutex<Buffer>) -> MutexGuard<'_, Buffer> {
+    buffer.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The entries appended to a [Log], from [subscribe](Log::subscribe) or
+/// [subscribe_from](Log::subscribe_from).
+///
+/// Events are buffered until they're taken, as a [Stream] or without waiting with
+/// [try_iter](Subscription::try_iter). The stream ends when the log is dropped or after an
+/// [Event::Lagged]. Dropping the subscription unsubscribes it.
+#[derive(Debug)]
+pub struct Subscription {
+    buffer: Arc<Mutex<Buffer>>,
+}
+
+impl Subscription {
+    /// Take the next event if there is one, without waiting.
+    pub fn try_next(&mut self) -> Option<Event> {
+        lock(&self.buffer).next_event()
+    }
+
+    /// Take the events buffered so far, without waiting.
+    pub fn try_iter(&mut self) -> impl Iterator<Item = Event> + '_ {
+        core::iter::from_fn(move || self.try_next())
+    }
+
+    /// True once no more events will arrive, because the log was dropped or the subscription
+    /// lagged. Events already buffered can still be taken.
+    pub fn is_closed(&self) -> bool {
+        lock(&self.buffer).closed
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut buffer = lock(&self.buffer);
+        if let Some(event) = buffer.next_event() {
+            return Poll::Ready(Some(event));
+        }
+        if buffer.closed {
+            return Poll::Ready(None);
+        }
+        buffer.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+type Callback = Box<dyn FnMut(u64, &[u8]) + Send>;
+
+/// The callbacks and subscriptions of a [Log].
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    callbacks: Vec<(CallbackId, Callback)>,
+    buffers: Vec<Arc<Mutex<Buffer>>>,
+    next_id: u64,
+}
+
+impl Subscribers {
+    fn is_empty(&self) -> bool {
+        self.callbacks.is_empty() && self.buffers.is_empty()
+    }
+
+    fn notify(&mut self, seq_num: u64, entry: &[u8]) {
+        for (_, callback) in self.callbacks.iter_mut() {
+            callback(seq_num, entry);
+        }
+        // Subscriptions that were dropped only have our reference left.
+        self.buffers.retain(|buffer| Arc::strong_count(buffer) > 1);
+        for buffer in self.buffers.iter() {
+            lock(buffer).push(seq_num, entry);
+        }
+    }
+}
+
+impl Drop for Subscribers {
+    fn drop(&mut self) {
+        for buffer in self.buffers.iter() {
+            lock(buffer).close();
+        }
+    }
+}
+
+impl<Store: EntryStore + Debug, Payloads: PayloadStore + Debug> Log<Store, Payloads> {
+    /// Call `callback` with the seq_num and bytes of every entry appended to the log from now on,
+    /// by [publish](Log::publish), [add](Log::add) or [add_batch](Log::add_batch).
+    ///
+    /// Callbacks are called in the order they were added, before the append returns.
+    pub fn on_append<F: FnMut(u64, &[u8]) + Send + 'static>(&mut self, callback: F) -> CallbackId {
+        let id = CallbackId(self.subscribers.next_id);
+        self.subscribers.next_id += 1;
+        self.subscribers.callbacks.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Stop calling the callback with `id`. Returns `false` if it was already removed.
+    pub fn remove_callback(&mut self, id: CallbackId) -> bool {
+        let len = self.subscribers.callbacks.len();
+        self.subscribers
+            .callbacks
+            .retain(|(callback_id, _)| *callback_id != id);
+        self.subscribers.callbacks.len() < len
+    }
+
+    /// Subscribe to the entries appended to the log from now on, buffering up to `capacity`
+    /// events until they're taken. An [Event::Dropped] counts towards `capacity` too. A
+    /// `capacity` of 0 is treated as 1, so every subscription can hold at least one event.
+    pub fn subscribe(&mut self, capacity: usize, overflow: Overflow) -> Subscription {
+        let buffer = Arc::new(Mutex::new(Buffer {
+            events: VecDeque::new(),
+            capacity: capacity.max(1),
+            overflow,
+            dropped: 0,
+            lagged_at: None,
+            closed: false,
+            waker: None,
+        }));
+        self.subscribers.buffers.push(buffer.clone());
+        Subscription { buffer }
+    }
+
+    /// Subscribe starting with the entries already in the log from `seq_num` on, in seq_num
+    /// order, then follow the entries appended from now on.
+    ///
+    /// `capacity` is at least 1, like [subscribe](Log::subscribe). The entries caught up on count
+    /// towards it, so catching up on more entries than that drops or lags just like following
+    /// does. After an [Event::Lagged], subscribing again
+    /// from its seq_num carries on where the subscription stopped.
+    pub fn subscribe_from(
+        &mut self,
+        seq_num: u64,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> Result<Subscription, Error<Store, Payloads>> {
+        let subscription = self.subscribe(capacity, overflow);
+        if let Some(last_seq_num) = self.store.get_last_seq() {
+            let mut buffer = lock(&subscription.buffer);
+            for seq_num in seq_num.max(1)..=last_seq_num {
+                if buffer.lagged_at.is_some() {
+                    break;
+                }
+                if let Some(entry) = self
+                    .store
+                    .get_entry_ref(seq_num)
+                    .context(SubscribeGetEntry)?
+                {
+                    buffer.push(seq_num, entry);
+                }
+            }
+        }
+        Ok(subscription)
+    }
+
+    /// Tell the callbacks and subscriptions about the entries at `seq_nums`, just appended.
+    pub(crate) fn notify_appended(&mut self, seq_nums: &[u64]) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        for seq_num in seq_nums {
+            if let Ok(Some(entry)) = self.store.get_entry_ref(*seq_num) {
+                self.subscribers.notify(*seq_num, entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, Overflow};
+    use crate::entry_store::MemoryEntryStore;
+    use crate::log::tests::new_log;
+    use crate::{EntryStore, Log};
+    use futures::executor::{block_on, block_on_stream};
+    use futures::StreamExt;
+    use std::sync::{Arc, Mutex};
+
+    fn appended(log: &Log<MemoryEntryStore>, seq_num: u64) -> Event {
+        Event::Appended {
+            seq_num,
+            entry: log.store.get_entry(seq_num).unwrap().unwrap(),
+        }
+    }
+
+    #[test]
+    fn callbacks_are_called_for_published_and_added_entries() {
+        let mut log = new_log();
+        let mut remote_log = Log::new(MemoryEntryStore::new(), log.public_key, None, 0);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let remote_seen = seen.clone();
+        remote_log.on_append(move |seq_num, entry| {
+            remote_seen.lock().unwrap().push((seq_num, entry.to_vec()));
+        });
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let log_published = published.clone();
+        let id = log.on_append(move |seq_num, _| log_published.lock().unwrap().push(seq_num));
+
+        for i in 1..=3u8 {
+            log.publish(&[i], false).unwrap();
+            let entry = log.store.get_entry(i as u64).unwrap().unwrap();
+            remote_log.add(&entry, None).unwrap();
+        }
+        assert!(log.remove_callback(id));
+        assert!(!log.remove_callback(id));
+        log.publish(&[4], false).unwrap();
+
+        assert_eq!(*published.lock().unwrap(), vec![1, 2, 3]);
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        assert_eq!(seen[2], (3, log.store.get_entry(3).unwrap().unwrap()));
+    }
+
+    #[test]
+    fn subscriptions_stream_appended_entries() {
+        let mut log = new_log();
+        let mut subscription = log.subscribe(16, Overflow::Drop);
+        assert_eq!(subscription.try_next(), None);
+
+        for i in 1..=3u8 {
+            log.publish(&[i], false).unwrap();
+        }
+        let events: Vec<Event> = subscription.try_iter().collect();
+        assert_eq!(
+            events,
+            (1..=3)
+                .map(|seq_num| appended(&log, seq_num))
+                .collect::<Vec<_>>()
+        );
+
+        log.publish(&[4], false).unwrap();
+        assert_eq!(block_on(subscription.next()), Some(appended(&log, 4)));
+
+        // The stream ends once the log is gone.
+        log.publish(&[5], false).unwrap();
+        let expected = appended(&log, 5);
+        drop(log);
+        assert!(subscription.is_closed());
+        assert_eq!(
+            block_on_stream(subscription).collect::<Vec<_>>(),
+            vec![expected]
+        );
+    }
+
+    #[test]
+    fn subscribe_from_catches_up_then_follows() {
+        let mut log = new_log();
+        for i in 1..=5u8 {
+            log.publish(&[i], false).unwrap();
+        }
+
+        let mut subscription = log.subscribe_from(3, 16, Overflow::Drop).unwrap();
+        log.publish(&[6], false).unwrap();
+
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            (3..=6)
+                .map(|seq_num| appended(&log, seq_num))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn a_full_subscription_drops_entries_and_says_so() {
+        let mut log = new_log();
+        let mut subscription = log.subscribe(2, Overflow::Drop);
+
+        for i in 1..=5u8 {
+            log.publish(&[i], false).unwrap();
+        }
+        assert_eq!(subscription.try_next(), Some(appended(&log, 1)));
+        // There's room for entry 6 but not for saying 3 were dropped before it.
+        log.publish(&[6], false).unwrap();
+
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            vec![appended(&log, 2), Event::Dropped { count: 4 }]
+        );
+        log.publish(&[7], false).unwrap();
+        log.publish(&[8], false).unwrap();
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            vec![appended(&log, 7), appended(&log, 8)]
+        );
+
+        // Once the buffer has room, the Dropped marker is buffered before the next entry.
+        log.publish(&[9], false).unwrap();
+        log.publish(&[10], false).unwrap();
+        log.publish(&[11], false).unwrap();
+        assert_eq!(subscription.try_next(), Some(appended(&log, 9)));
+        assert_eq!(subscription.try_next(), Some(appended(&log, 10)));
+        log.publish(&[12], false).unwrap();
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            vec![Event::Dropped { count: 1 }, appended(&log, 12)]
+        );
+    }
+
+    #[test]
+    fn a_subscription_holds_at_least_one_event() {
+        let mut log = new_log();
+        let mut subscription = log.subscribe(0, Overflow::Lag);
+
+        log.publish(&[1], false).unwrap();
+        assert_eq!(subscription.try_next(), Some(appended(&log, 1)));
+
+        let mut subscription = log.subscribe_from(1, 0, Overflow::Drop).unwrap();
+        log.publish(&[2], false).unwrap();
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            vec![appended(&log, 1), Event::Dropped { count: 1 }]
+        );
+    }
+
+    #[test]
+    fn a_lagging_subscription_ends_and_can_catch_up() {
+        let mut log = new_log();
+        let subscription = log.subscribe(2, Overflow::Lag);
+
+        for i in 1..=5u8 {
+            log.publish(&[i], false).unwrap();
+        }
+        assert_eq!(
+            block_on_stream(subscription).collect::<Vec<_>>(),
+            vec![
+                appended(&log, 1),
+                appended(&log, 2),
+                Event::Lagged { seq_num: 3 }
+            ]
+        );
+
+        let mut subscription = log.subscribe_from(3, 2, Overflow::Lag).unwrap();
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            vec![
+                appended(&log, 3),
+                appended(&log, 4),
+                Event::Lagged { seq_num: 5 }
+            ]
+        );
+        assert!(subscription.is_closed());
+
+        let mut subscription = log.subscribe_from(5, 2, Overflow::Lag).unwrap();
+        log.publish(&[6], false).unwrap();
+        assert_eq!(
+            subscription.try_iter().collect::<Vec<_>>(),
+            vec![appended(&log, 5), appended(&log, 6)]
+        );
+    }
+
+    #[test]
+    fn subscriptions_see_pending_and_batched_entries() {
+        let mut remote_log = new_log();
+        for i in 1..=14u8 {
+            remote_log.publish(&[i], false).unwrap();
+        }
+        let entries: Vec<(Vec<u8>, Option<Vec<u8>>)> = (1..=14)
+            .map(|seq_num| (remote_log.store.get_entry(seq_num).unwrap().unwrap(), None))
+            .collect();
+
+        let mut log = Log::new(MemoryEntryStore::new(), remote_log.public_key, None, 0);
+        let mut subscription = log.subscribe(16, Overflow::Drop);
+
+        // Entry 13 links to 4, so it's pending until the batch adds 4.
+        log.add(&entries[12].0, None).unwrap();
+        log.add_batch(&entries[..4]).unwrap();
+        log.add_batch(&entries[4..12]).unwrap();
+        log.add(&entries[13].0, None).unwrap();
+
+        let seq_nums: Vec<u64> = subscription
+            .try_iter()
+            .map(|event| match event {
+                Event::Appended { seq_num, .. } => seq_num,
+                event => panic!("Expected an appended entry, got {:?}", event),
+            })
+            .collect();
+        assert_eq!(
+            seq_nums,
+            vec![1, 2, 3, 4, 13, 5, 6, 7, 8, 9, 10, 11, 12, 14]
+        );
+    }
+
+    #[test]
+    fn dropped_subscriptions_are_removed() {
+        let mut log = new_log();
+        let subscription = log.subscribe(1, Overflow::Drop);
+        drop(subscription);
+
+        log.publish(&[1], false).unwrap();
+        assert!(log.subscribers.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use super::*;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum Error {
+    #[snafu(display("Could not create payload directory {}: {}", path.display(), source))]
+    CreatePayloadDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read payload {}: {}", path.display(), source))]
+    ReadPayload {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not delete payload {}: {}", path.display(), source))]
+    DeletePayload {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not write payload {}: {}", path.display(), source))]
+    WritePayload {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// A [PayloadStore] that keeps each payload in a file in a directory, named by the hex of its
+/// encoded hash.
+///
+/// Payloads are written to a temporary file and fsynced before being renamed into place, so a
+/// crash never leaves a partly written payload behind.
+#[derive(Debug)]
+pub struct FilePayloadStore {
+    dir: PathBuf,
+}
+
+impl FilePayloadStore {
+    /// Open the store in `dir`, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<FilePayloadStore> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).context(CreatePayloadDir { path: dir.clone() })?;
+        Ok(FilePayloadStore { dir })
+    }
+
+    /// The directory the payloads are in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn payload_path(&self, hash: &YasmfHash<&[u8]>) -> PathBuf {
+        self.dir.join(hex::encode(hash_key(hash)))
+    }
+}
+
+impl PayloadStore for FilePayloadStore {
+    type Error = Error;
+
+    fn get_payload(&self, hash: YasmfHash<&[u8]>) -> Result<Option<Vec<u8>>> {
+        let path = self.payload_path(&hash);
+        match fs::read(&path) {
+            Ok(payload) => Ok(Some(payload)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(Error::ReadPayload { path, source }),
+        }
+    }
+    fn add_payload(&mut self, hash: YasmfHash<&[u8]>, payload: &[u8]) -> Result<()> {
+        let path = self.payload_path(&hash);
+        if path.exists() {
+            return Ok(());
+        }
+
+        let tmp_path = path.with_extension("tmp");
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(payload)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .and_then(|_| File::open(&self.dir)?.sync_all())
+            .context(WritePayload { path })
+    }
+    fn delete_payload(&mut self, hash: YasmfHash<&[u8]>) -> Result<()> {
+        let path = self.payload_path(&hash);
+        match fs::remove_file(&path) {
+            Ok(()) => File::open(&self.dir)
+                .and_then(|dir| dir.sync_all())
+                .context(DeletePayload { path }),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(source) => Err(Error::DeletePayload { path, source }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilePayloadStore;
+    use crate::payload_store::PayloadStore;
+    use bamboo_rs_core_ed25519_yasmf::yasmf_hash::new_blake3;
+    use bamboo_rs_core_ed25519_yasmf::YasmfHash;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn payloads_persist_across_reopening() {
+        let dir = tempdir().unwrap();
+        let payload = b"hello bamboo";
+        let hash = new_blake3(payload);
+        let other_hash = new_blake3(b"other");
+
+        let mut store = FilePayloadStore::open(dir.path()).unwrap();
+        store.add_payload((&hash).into(), payload).unwrap();
+        store.add_payload((&hash).into(), payload).unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let store = FilePayloadStore::open(dir.path()).unwrap();
+        let hash: YasmfHash<&[u8]> = (&hash).into();
+        assert_eq!(store.get_payload(hash).unwrap(), Some(payload.to_vec()));
+        assert_eq!(store.get_payload((&other_hash).into()).unwrap(), None);
+    }
+
+    #[test]
+    fn deleted_payloads_are_removed() {
+        let dir = tempdir().unwrap();
+        let payload = b"hello bamboo";
+        let hash = new_blake3(payload);
+
+        let mut store = FilePayloadStore::open(dir.path()).unwrap();
+        store.add_payload((&hash).into(), payload).unwrap();
+        store.delete_payload((&hash).into()).unwrap();
+        store.delete_payload((&hash).into()).unwrap();
+
+        assert_eq!(store.get_payload((&hash).into()).unwrap(), None);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+use std::collections::HashMap;
+
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum Error {}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// A [PayloadStore] that keeps payloads in a [HashMap] keyed by their encoded hash.
+#[derive(Debug, Default)]
+pub struct MemoryPayloadStore {
+    pub store: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryPayloadStore {
+    pub fn new() -> MemoryPayloadStore {
+        MemoryPayloadStore {
+            store: HashMap::new(),
+        }
+    }
+    pub fn clear(&mut self) {
+        self.store.clear()
+    }
+}
+
+impl PayloadStore for MemoryPayloadStore {
+    type Error = Error;
+
+    fn get_payload(&self, hash: YasmfHash<&[u8]>) -> Result<Option<Vec<u8>>> {
+        Ok(self.store.get(&hash_key(&hash)).cloned())
+    }
+    fn add_payload(&mut self, hash: YasmfHash<&[u8]>, payload: &[u8]) -> Result<()> {
+        self.store
+            .entry(hash_key(&hash))
+            .or_insert_with(|| payload.to_vec());
+        Ok(())
+    }
+    fn delete_payload(&mut self, hash: YasmfHash<&[u8]>) -> Result<()> {
+        self.store.remove(&hash_key(&hash));
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+pub mod file_payload_store;
+pub mod memory_payload_store;
+use bamboo_rs_core_ed25519_yasmf::YasmfHash;
+use core::fmt::Debug;
+use core::fmt::Display;
+pub use file_payload_store::FilePayloadStore;
+pub use memory_payload_store::*;
+use snafu::AsErrorSource;
+
+/// Stores payloads by their hash, so a payload shared by many entries is only stored once.
+pub trait PayloadStore {
+    type Error: Display + Debug + AsErrorSource;
+
+    fn get_payload(&self, hash: YasmfHash<&[u8]>) -> Result<Option<Vec<u8>>, Self::Error>;
+    /// Store `payload`, which the caller has already checked hashes to `hash`.
+    fn add_payload(&mut self, hash: YasmfHash<&[u8]>, payload: &[u8]) -> Result<(), Self::Error>;
+    /// Delete the payload with `hash`. Deleting a payload that isn't in the store does nothing.
+    fn delete_payload(&mut self, hash: YasmfHash<&[u8]>) -> Result<(), Self::Error>;
+}
+
+/// The encoded hash, used as the key of a payload.
+pub(crate) fn hash_key(hash: &YasmfHash<&[u8]>) -> Vec<u8> {
+    let mut key = vec![0; hash.encoding_length()];
+    hash.encode(&mut key)
+        .expect("key is the hash's encoding length");
+    key
+}
@@ -2,14 +2,15 @@ pub mod utils;
 
 pub use utils::set_panic_hook;
 
-use arrayvec::*;
 use bamboo_rs_core_ed25519_yasmf::entry::{
-    decode as decode_entry, into_owned, publish as publish_entry, verify as verify_entry,
+    decode as decode_entry, publish as publish_entry, verify as verify_entry, OwnedEntry,
     MAX_ENTRY_SIZE,
 };
-use bamboo_rs_core_ed25519_yasmf::yasmf_hash::new_blake3;
-use bamboo_rs_core_ed25519_yasmf::{lipmaa, Entry, YasmfHash};
+use bamboo_rs_core_ed25519_yasmf::hash::OwnedYasmfHash;
+use bamboo_rs_core_ed25519_yasmf::keystore::{KdfParams, Keystore};
+use bamboo_rs_core_ed25519_yasmf::lipmaa;
 use bamboo_rs_core_ed25519_yasmf::{Keypair, PublicKey, SecretKey, Signature};
+use core::convert::TryFrom;
 use rand::rngs::OsRng;
 use wasm_bindgen::prelude::*;
 
@@ -34,8 +35,8 @@ pub fn max_entry_size() -> u32 {
 
 #[wasm_bindgen(inspectable)]
 pub struct BambooEntry {
-    hash: YasmfHash<ArrayVec<[u8; 32]>>,
-    value: Entry<ArrayVec<[u8; 32]>, ArrayVec<[u8; 64]>>,
+    hash: OwnedYasmfHash,
+    value: OwnedEntry,
 }
 
 #[wasm_bindgen]
@@ -108,14 +109,11 @@ impl BambooEntry {
 
 #[wasm_bindgen]
 pub fn decode(buffer: &[u8]) -> Result<BambooEntry, JsValue> {
-    let hash = new_blake3(buffer);
     let entry = decode_entry(buffer).map_err(|err| JsValue::from_str(&err.to_string()))?;
 
-    let entry = into_owned(&entry);
-    let bamboo_entry = BambooEntry {
-        hash,
-        value: entry,
-    };
+    let entry = OwnedEntry::try_from(&entry).map_err(|err| JsValue::from_str(&err.to_string()))?;
+    let hash = entry.entry_hash();
+    let bamboo_entry = BambooEntry { hash, value: entry };
 
     Ok(bamboo_entry)
 }
@@ -202,4 +200,28 @@ impl KeyPair {
         let keypair: Keypair = Keypair::generate(&mut csprng);
         KeyPair { inner: keypair }
     }
+    /// Decrypt a key pair from keystore bytes with a password.
+    #[wasm_bindgen(js_name = fromKeystore)]
+    pub fn from_keystore(keystore: &[u8], password: &[u8]) -> Result<KeyPair, JsValue> {
+        Keystore::from_bytes(keystore)
+            .and_then(|keystore| keystore.decrypt(password))
+            .map(|inner| KeyPair { inner })
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+    /// Encrypt the key pair into keystore bytes with a password.
+    #[wasm_bindgen(js_name = toKeystore)]
+    pub fn to_keystore(&self, password: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut csprng: OsRng = OsRng {};
+        Keystore::encrypt(&self.inner, password, KdfParams::default(), &mut csprng)
+            .map(|keystore| keystore.as_bytes().to_vec())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+/// Read the public key of a keystore, without its password.
+#[wasm_bindgen(js_name = keystorePublicKey)]
+pub fn keystore_public_key(keystore: &[u8]) -> Result<Vec<u8>, JsValue> {
+    Keystore::from_bytes(keystore)
+        .map(|keystore| keystore.public_key().as_bytes().to_vec())
+        .map_err(|err| JsValue::from_str(&err.to_string()))
 }